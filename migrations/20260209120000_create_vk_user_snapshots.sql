@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS vk_user_snapshots
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    first_name varchar(128),
+    last_name varchar(128),
+    city varchar(128),
+    is_closed boolean,
+    about text,
+    status text,
+    photo text,
+    changed_at timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT vk_user_snapshots_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS vk_user_snapshots_user_vk_user_changed_idx
+    ON vk_user_snapshots(user_id, vk_user_id, changed_at);
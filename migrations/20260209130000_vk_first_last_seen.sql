@@ -0,0 +1,61 @@
+ALTER TABLE vk_users
+    RENAME COLUMN finded_date TO first_seen_at;
+
+ALTER TABLE vk_users
+    ADD COLUMN last_seen_at timestamptz;
+
+UPDATE vk_users
+SET last_seen_at = first_seen_at;
+
+ALTER TABLE vk_users
+    ALTER COLUMN last_seen_at SET NOT NULL;
+
+DROP INDEX IF EXISTS vk_users_user_id_finded_date_idx;
+
+CREATE INDEX IF NOT EXISTS vk_users_user_id_first_seen_at_idx
+    ON vk_users(user_id, first_seen_at DESC);
+
+CREATE INDEX IF NOT EXISTS vk_users_user_id_last_seen_at_idx
+    ON vk_users(user_id, last_seen_at DESC);
+
+ALTER TABLE vk_post_likes
+    RENAME COLUMN found_date TO first_seen_at;
+
+ALTER TABLE vk_post_likes
+    ADD COLUMN last_seen_at timestamptz;
+
+UPDATE vk_post_likes
+SET last_seen_at = first_seen_at;
+
+ALTER TABLE vk_post_likes
+    ALTER COLUMN last_seen_at SET NOT NULL;
+
+DROP INDEX IF EXISTS vk_post_likes_user_found_date_idx;
+DROP INDEX IF EXISTS vk_post_likes_user_vk_user_found_date_idx;
+
+CREATE INDEX IF NOT EXISTS vk_post_likes_user_last_seen_idx
+    ON vk_post_likes(user_id, last_seen_at DESC);
+
+CREATE INDEX IF NOT EXISTS vk_post_likes_user_vk_user_last_seen_idx
+    ON vk_post_likes(user_id, vk_user_id, last_seen_at DESC);
+
+ALTER TABLE vk_comment_likes
+    RENAME COLUMN found_date TO first_seen_at;
+
+ALTER TABLE vk_comment_likes
+    ADD COLUMN last_seen_at timestamptz;
+
+UPDATE vk_comment_likes
+SET last_seen_at = first_seen_at;
+
+ALTER TABLE vk_comment_likes
+    ALTER COLUMN last_seen_at SET NOT NULL;
+
+DROP INDEX IF EXISTS vk_comment_likes_user_found_date_idx;
+DROP INDEX IF EXISTS vk_comment_likes_user_vk_user_found_date_idx;
+
+CREATE INDEX IF NOT EXISTS vk_comment_likes_user_last_seen_idx
+    ON vk_comment_likes(user_id, last_seen_at DESC);
+
+CREATE INDEX IF NOT EXISTS vk_comment_likes_user_vk_user_last_seen_idx
+    ON vk_comment_likes(user_id, vk_user_id, last_seen_at DESC);
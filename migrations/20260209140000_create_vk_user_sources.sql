@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS vk_user_sources
+(
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    group_id bigint NOT NULL,
+    source_kind varchar(16) NOT NULL
+        CHECK (source_kind IN ('member', 'liker', 'commenter', 'author')),
+    -- '' for members, post id for post authors/likers, post_id:comment_id for comments
+    source_ref text NOT NULL DEFAULT '',
+    first_seen_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, vk_user_id, group_id, source_kind, source_ref),
+    CONSTRAINT vk_user_sources_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE,
+    CONSTRAINT vk_user_sources_groups_fk
+        FOREIGN KEY (user_id, group_id)
+            REFERENCES groups (user_id, group_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS vk_user_sources_user_group_idx
+    ON vk_user_sources(user_id, group_id, source_kind);
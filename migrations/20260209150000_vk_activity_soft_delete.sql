@@ -0,0 +1,27 @@
+ALTER TABLE vk_posts
+    ADD COLUMN IF NOT EXISTS deleted_at timestamptz;
+
+ALTER TABLE vk_comments
+    ADD COLUMN IF NOT EXISTS deleted_at timestamptz;
+
+ALTER TABLE vk_post_likes
+    ADD COLUMN IF NOT EXISTS removed_at timestamptz;
+
+ALTER TABLE vk_comment_likes
+    ADD COLUMN IF NOT EXISTS removed_at timestamptz;
+
+CREATE INDEX IF NOT EXISTS vk_posts_user_deleted_at_idx
+    ON vk_posts(user_id, deleted_at DESC)
+    WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS vk_comments_user_deleted_at_idx
+    ON vk_comments(user_id, deleted_at DESC)
+    WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS vk_post_likes_user_removed_at_idx
+    ON vk_post_likes(user_id, removed_at DESC)
+    WHERE removed_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS vk_comment_likes_user_removed_at_idx
+    ON vk_comment_likes(user_id, removed_at DESC)
+    WHERE removed_at IS NOT NULL;
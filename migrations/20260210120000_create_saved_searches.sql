@@ -0,0 +1,54 @@
+CREATE OR REPLACE FUNCTION vk_bdate_age(bdate text)
+RETURNS integer
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT CASE
+        WHEN bdate ~ '^\d{1,2}\.\d{1,2}\.\d{4}$' THEN
+            date_part('year', current_date)::int - split_part(bdate, '.', 3)::int
+            - CASE
+                WHEN (date_part('month', current_date)::int, date_part('day', current_date)::int)
+                     < (split_part(bdate, '.', 2)::int, split_part(bdate, '.', 1)::int)
+                THEN 1
+                ELSE 0
+              END
+    END
+$$;
+
+CREATE TABLE IF NOT EXISTS saved_searches
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name varchar(128) NOT NULL,
+    sex smallint CHECK (sex IN (1, 2)),
+    city varchar(128),
+    age_min smallint CHECK (age_min >= 0),
+    age_max smallint CHECK (age_max >= 0),
+    open_profile boolean,
+    min_groups integer CHECK (min_groups >= 1),
+    checked_at timestamptz NOT NULL DEFAULT now(),
+    created_at timestamptz NOT NULL DEFAULT now(),
+    CHECK (age_min IS NULL OR age_max IS NULL OR age_min <= age_max)
+);
+
+CREATE INDEX IF NOT EXISTS saved_searches_user_id_created_at_idx
+    ON saved_searches(user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS saved_search_matches
+(
+    search_id uuid NOT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    matched_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (search_id, vk_user_id),
+    CONSTRAINT saved_search_matches_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS saved_search_matches_search_matched_at_idx
+    ON saved_search_matches(search_id, matched_at DESC);
+
+ALTER TABLE user_settings
+    ADD COLUMN last_search_run_at timestamptz;
@@ -0,0 +1,29 @@
+ALTER TABLE user_settings
+    ADD COLUMN email_notify_matches boolean NOT NULL DEFAULT false,
+    ADD COLUMN email_notify_crawl_failures boolean NOT NULL DEFAULT false,
+    ADD COLUMN email_digest varchar(8) NOT NULL DEFAULT 'off'
+        CHECK (email_digest IN ('off', 'daily', 'weekly')),
+    ADD COLUMN last_digest_at timestamptz;
+
+CREATE TABLE IF NOT EXISTS email_outbox
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind varchar(16) NOT NULL CHECK (kind IN ('match', 'crawl_failed', 'digest')),
+    subject text NOT NULL,
+    body text NOT NULL,
+    status varchar(16) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts integer NOT NULL DEFAULT 0,
+    next_attempt_at timestamptz NOT NULL DEFAULT now(),
+    last_error text,
+    sent_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS email_outbox_pending_idx
+    ON email_outbox(next_attempt_at)
+    WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS email_outbox_user_created_at_idx
+    ON email_outbox(user_id, created_at DESC);
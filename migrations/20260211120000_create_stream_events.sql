@@ -0,0 +1,147 @@
+CREATE TABLE IF NOT EXISTS stream_events
+(
+    id bigserial PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_type varchar(32) NOT NULL,
+    data jsonb NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS stream_events_user_id_idx
+    ON stream_events(user_id, id);
+
+CREATE INDEX IF NOT EXISTS stream_events_created_at_idx
+    ON stream_events(created_at);
+
+CREATE OR REPLACE FUNCTION stream_vk_users_inserted()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO stream_events (user_id, event_type, data)
+    SELECT
+        n.user_id,
+        'vk_user.created',
+        jsonb_build_object(
+            'vk_user_id', n.vk_user_id,
+            'first_name', n.first_name,
+            'last_name', n.last_name,
+            'photo', n.photo
+        )
+    FROM new_rows AS n;
+    RETURN NULL;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION stream_vk_posts_inserted()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO stream_events (user_id, event_type, data)
+    SELECT
+        n.user_id,
+        'vk_post.created',
+        jsonb_build_object(
+            'group_id', n.group_id,
+            'post_id', n.post_id,
+            'from_id', n.from_id
+        )
+    FROM new_rows AS n;
+    RETURN NULL;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION stream_vk_comments_inserted()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO stream_events (user_id, event_type, data)
+    SELECT
+        n.user_id,
+        'vk_comment.created',
+        jsonb_build_object(
+            'group_id', n.group_id,
+            'post_id', n.post_id,
+            'comment_id', n.comment_id,
+            'from_id', n.from_id
+        )
+    FROM new_rows AS n;
+    RETURN NULL;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION stream_vk_post_likes_inserted()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO stream_events (user_id, event_type, data)
+    SELECT
+        n.user_id,
+        'vk_post_like.created',
+        jsonb_build_object(
+            'group_id', n.group_id,
+            'post_id', n.post_id,
+            'vk_user_id', n.vk_user_id
+        )
+    FROM new_rows AS n;
+    RETURN NULL;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION stream_vk_comment_likes_inserted()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO stream_events (user_id, event_type, data)
+    SELECT
+        n.user_id,
+        'vk_comment_like.created',
+        jsonb_build_object(
+            'group_id', n.group_id,
+            'post_id', n.post_id,
+            'comment_id', n.comment_id,
+            'vk_user_id', n.vk_user_id
+        )
+    FROM new_rows AS n;
+    RETURN NULL;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS vk_users_stream_trigger ON vk_users;
+CREATE TRIGGER vk_users_stream_trigger
+AFTER INSERT ON vk_users
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_vk_users_inserted();
+
+DROP TRIGGER IF EXISTS vk_posts_stream_trigger ON vk_posts;
+CREATE TRIGGER vk_posts_stream_trigger
+AFTER INSERT ON vk_posts
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_vk_posts_inserted();
+
+DROP TRIGGER IF EXISTS vk_comments_stream_trigger ON vk_comments;
+CREATE TRIGGER vk_comments_stream_trigger
+AFTER INSERT ON vk_comments
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_vk_comments_inserted();
+
+DROP TRIGGER IF EXISTS vk_post_likes_stream_trigger ON vk_post_likes;
+CREATE TRIGGER vk_post_likes_stream_trigger
+AFTER INSERT ON vk_post_likes
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_vk_post_likes_inserted();
+
+DROP TRIGGER IF EXISTS vk_comment_likes_stream_trigger ON vk_comment_likes;
+CREATE TRIGGER vk_comment_likes_stream_trigger
+AFTER INSERT ON vk_comment_likes
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_vk_comment_likes_inserted();
@@ -0,0 +1,33 @@
+ALTER TABLE vk_users
+    ADD COLUMN is_favorite boolean NOT NULL DEFAULT false,
+    ADD COLUMN is_hidden boolean NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS vk_users_user_favorite_idx
+    ON vk_users(user_id)
+    WHERE is_favorite;
+
+CREATE TABLE IF NOT EXISTS vk_user_tags
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name varchar(64) NOT NULL,
+    color varchar(7) CHECK (color ~ '^#[0-9a-f]{6}$'),
+    created_at timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS vk_user_tag_assignments
+(
+    tag_id uuid NOT NULL REFERENCES vk_user_tags(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (tag_id, vk_user_id),
+    CONSTRAINT vk_user_tag_assignments_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS vk_user_tag_assignments_vk_user_idx
+    ON vk_user_tag_assignments(user_id, vk_user_id);
@@ -0,0 +1,19 @@
+ALTER TABLE notes
+    ADD COLUMN vk_user_id bigint,
+    ADD COLUMN group_id bigint,
+    ADD CONSTRAINT notes_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE SET NULL (vk_user_id),
+    ADD CONSTRAINT notes_groups_fk
+        FOREIGN KEY (user_id, group_id)
+            REFERENCES groups (user_id, group_id)
+            ON DELETE SET NULL (group_id);
+
+CREATE INDEX IF NOT EXISTS notes_user_vk_user_idx
+    ON notes(user_id, vk_user_id, created_at DESC)
+    WHERE vk_user_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS notes_user_group_idx
+    ON notes(user_id, group_id, created_at DESC)
+    WHERE group_id IS NOT NULL;
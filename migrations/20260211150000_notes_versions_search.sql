@@ -0,0 +1,21 @@
+ALTER TABLE notes
+    ADD COLUMN updated_at timestamptz NOT NULL DEFAULT now(),
+    ADD COLUMN search tsvector
+        GENERATED ALWAYS AS (to_tsvector('simple', title || ' ' || body)) STORED;
+
+UPDATE notes SET updated_at = created_at;
+
+CREATE INDEX IF NOT EXISTS notes_search_idx
+    ON notes USING gin (search);
+
+CREATE TABLE IF NOT EXISTS note_versions
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    note_id uuid NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    title text NOT NULL,
+    body text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS note_versions_note_id_created_at_idx
+    ON note_versions(note_id, created_at DESC);
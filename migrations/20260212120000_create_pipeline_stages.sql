@@ -0,0 +1,77 @@
+CREATE TABLE IF NOT EXISTS pipeline_stages
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name varchar(64) NOT NULL,
+    position integer NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS pipeline_stages_user_position_idx
+    ON pipeline_stages(user_id, position);
+
+CREATE TABLE IF NOT EXISTS vk_user_stages
+(
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    stage_id uuid NOT NULL REFERENCES pipeline_stages(id) ON DELETE CASCADE,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, vk_user_id),
+    CONSTRAINT vk_user_stages_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS vk_user_stages_stage_idx
+    ON vk_user_stages(stage_id);
+
+CREATE TABLE IF NOT EXISTS vk_user_stage_changes
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL,
+    vk_user_id bigint NOT NULL,
+    from_stage_id uuid REFERENCES pipeline_stages(id) ON DELETE SET NULL,
+    from_stage_name varchar(64),
+    to_stage_id uuid REFERENCES pipeline_stages(id) ON DELETE SET NULL,
+    to_stage_name varchar(64),
+    changed_at timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT vk_user_stage_changes_vk_users_fk
+        FOREIGN KEY (user_id, vk_user_id)
+            REFERENCES vk_users (user_id, vk_user_id)
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS vk_user_stage_changes_vk_user_idx
+    ON vk_user_stage_changes(user_id, vk_user_id, changed_at DESC);
+
+CREATE OR REPLACE FUNCTION ensure_default_pipeline_stages()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    INSERT INTO pipeline_stages (user_id, name, position)
+    SELECT NEW.id, stage.name, stage.position
+    FROM (
+        VALUES ('new', 1), ('reviewed', 2), ('contacted', 3), ('replied', 4), ('closed', 5)
+    ) AS stage(name, position)
+    ON CONFLICT (user_id, name) DO NOTHING;
+    RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS users_create_default_pipeline_stages_trigger ON users;
+
+CREATE TRIGGER users_create_default_pipeline_stages_trigger
+AFTER INSERT ON users
+FOR EACH ROW
+EXECUTE FUNCTION ensure_default_pipeline_stages();
+
+INSERT INTO pipeline_stages (user_id, name, position)
+SELECT u.id, stage.name, stage.position
+FROM users AS u
+CROSS JOIN (
+    VALUES ('new', 1), ('reviewed', 2), ('contacted', 3), ('replied', 4), ('closed', 5)
+) AS stage(name, position)
+ON CONFLICT (user_id, name) DO NOTHING;
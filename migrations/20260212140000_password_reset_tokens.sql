@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS password_reset_tokens
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash text NOT NULL UNIQUE,
+    expires_at timestamptz NOT NULL,
+    used_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS password_reset_tokens_user_created_at_idx
+    ON password_reset_tokens(user_id, created_at DESC);
+
+ALTER TABLE email_outbox
+    DROP CONSTRAINT IF EXISTS email_outbox_kind_check,
+    ADD CONSTRAINT email_outbox_kind_check
+        CHECK (kind IN ('match', 'crawl_failed', 'digest', 'verification', 'password_reset'));
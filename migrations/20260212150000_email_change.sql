@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS email_change_tokens
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email citext NOT NULL,
+    token_hash text NOT NULL UNIQUE,
+    expires_at timestamptz NOT NULL,
+    used_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS email_change_tokens_user_created_at_idx
+    ON email_change_tokens(user_id, created_at DESC);
+
+ALTER TABLE email_outbox
+    ADD COLUMN to_email citext,
+    DROP CONSTRAINT IF EXISTS email_outbox_kind_check,
+    ADD CONSTRAINT email_outbox_kind_check
+        CHECK (kind IN (
+            'match', 'crawl_failed', 'digest', 'verification', 'password_reset', 'email_change'
+        ));
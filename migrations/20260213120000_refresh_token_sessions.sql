@@ -0,0 +1,10 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN user_agent text NULL,
+    ADD COLUMN ip_address text NULL,
+    ADD COLUMN last_used_at timestamptz NOT NULL DEFAULT now();
+
+UPDATE refresh_tokens SET last_used_at = created_at;
+
+CREATE INDEX refresh_tokens_user_active_idx
+    ON refresh_tokens (user_id, last_used_at DESC)
+    WHERE revoked_at IS NULL;
@@ -0,0 +1,44 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN family_id uuid NOT NULL DEFAULT uuid_generate_v4();
+
+WITH RECURSIVE chain AS (
+    SELECT t.id, t.id AS family_id
+    FROM refresh_tokens AS t
+    WHERE NOT EXISTS (SELECT 1 FROM refresh_tokens AS p WHERE p.replaced_by = t.id)
+    UNION ALL
+    SELECT t.id, c.family_id
+    FROM refresh_tokens AS t
+    JOIN refresh_tokens AS p ON p.replaced_by = t.id
+    JOIN chain AS c ON c.id = p.id
+)
+UPDATE refresh_tokens AS t
+SET family_id = chain.family_id
+FROM chain
+WHERE chain.id = t.id;
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens(family_id);
+
+CREATE TABLE IF NOT EXISTS security_events
+(
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind varchar(32) NOT NULL CHECK (kind IN ('refresh_token_reuse')),
+    user_agent text,
+    ip_address text,
+    details jsonb NOT NULL DEFAULT '{}'::jsonb,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS security_events_user_created_at_idx
+    ON security_events(user_id, created_at DESC);
+
+ALTER TABLE user_settings
+    ADD COLUMN email_notify_security boolean NOT NULL DEFAULT true;
+
+ALTER TABLE email_outbox
+    DROP CONSTRAINT IF EXISTS email_outbox_kind_check,
+    ADD CONSTRAINT email_outbox_kind_check
+        CHECK (kind IN (
+            'match', 'crawl_failed', 'digest', 'verification', 'password_reset', 'email_change',
+            'security_alert'
+        ));
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS login_throttles
+(
+    scope varchar(8) NOT NULL CHECK (scope IN ('email', 'ip')),
+    key text NOT NULL,
+    failures integer NOT NULL DEFAULT 0,
+    last_failed_at timestamptz NOT NULL DEFAULT now(),
+    blocked_until timestamptz,
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX IF NOT EXISTS login_throttles_last_failed_at_idx
+    ON login_throttles(last_failed_at);
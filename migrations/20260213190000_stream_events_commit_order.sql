@@ -0,0 +1,31 @@
+-- Event ids are handed out before commit, so a long transaction can commit
+-- rows below ids that streams already delivered. Streams order by the writing
+-- transaction instead and only deliver transactions that have finished.
+ALTER TABLE stream_events
+    ADD COLUMN IF NOT EXISTS xact_id bigint NOT NULL DEFAULT 0;
+ALTER TABLE stream_events
+    ALTER COLUMN xact_id SET DEFAULT pg_current_xact_id()::text::bigint;
+
+DROP INDEX IF EXISTS stream_events_user_id_idx;
+CREATE INDEX IF NOT EXISTS stream_events_user_xact_idx
+    ON stream_events(user_id, xact_id, id);
+
+-- Notifications are delivered on commit, which is exactly when new events
+-- become visible to open streams.
+CREATE OR REPLACE FUNCTION stream_events_notify()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', n.user_id::text)
+    FROM (SELECT DISTINCT user_id FROM new_rows) AS n;
+    RETURN NULL;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS stream_events_notify_trigger ON stream_events;
+CREATE TRIGGER stream_events_notify_trigger
+AFTER INSERT ON stream_events
+REFERENCING NEW TABLE AS new_rows
+FOR EACH STATEMENT
+EXECUTE FUNCTION stream_events_notify();
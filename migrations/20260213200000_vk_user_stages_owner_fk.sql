@@ -0,0 +1,17 @@
+-- A contact may only be placed in a stage owned by the same account.
+ALTER TABLE pipeline_stages
+    ADD CONSTRAINT pipeline_stages_user_id_id_key UNIQUE (user_id, id);
+
+DELETE FROM vk_user_stages AS s
+USING pipeline_stages AS p
+WHERE p.id = s.stage_id
+  AND p.user_id <> s.user_id;
+
+ALTER TABLE vk_user_stages
+    DROP CONSTRAINT IF EXISTS vk_user_stages_stage_id_fkey;
+
+ALTER TABLE vk_user_stages
+    ADD CONSTRAINT vk_user_stages_stage_fk
+        FOREIGN KEY (user_id, stage_id)
+            REFERENCES pipeline_stages (user_id, id)
+            ON DELETE CASCADE;
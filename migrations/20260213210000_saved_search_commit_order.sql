@@ -0,0 +1,31 @@
+-- `last_seen_at` comes from the crawler's clock and is written in a
+-- transaction that may commit much later, so it cannot tell saved searches
+-- which rows they have already looked at. Rows are stamped with the writing
+-- transaction instead, and searches only advance past transactions that have
+-- finished, the same way the event stream orders `stream_events`.
+ALTER TABLE vk_users
+    ADD COLUMN IF NOT EXISTS seen_xact bigint NOT NULL DEFAULT 0;
+ALTER TABLE vk_users
+    ALTER COLUMN seen_xact SET DEFAULT pg_current_xact_id()::text::bigint;
+
+CREATE INDEX IF NOT EXISTS vk_users_user_seen_xact_idx
+    ON vk_users(user_id, seen_xact);
+
+ALTER TABLE saved_searches
+    ADD COLUMN IF NOT EXISTS checked_xact bigint NOT NULL DEFAULT 0;
+ALTER TABLE saved_searches
+    ALTER COLUMN checked_xact SET DEFAULT pg_snapshot_xmin(pg_current_snapshot())::text::bigint;
+
+-- Rows the timestamp cursor had not reached yet are still due for a run.
+UPDATE saved_searches
+SET checked_xact = 1;
+
+UPDATE vk_users AS v
+SET seen_xact = 1
+FROM (
+    SELECT user_id, MIN(checked_at) AS checked_at
+    FROM saved_searches
+    GROUP BY user_id
+) AS s
+WHERE s.user_id = v.user_id
+  AND v.last_seen_at > s.checked_at;
@@ -0,0 +1,8 @@
+-- Snapshots written by one transaction share `changed_at` and ids are random,
+-- so history is ordered by insertion instead.
+ALTER TABLE vk_user_snapshots
+    ADD COLUMN IF NOT EXISTS seq bigint GENERATED ALWAYS AS IDENTITY;
+
+DROP INDEX IF EXISTS vk_user_snapshots_user_vk_user_changed_idx;
+CREATE INDEX IF NOT EXISTS vk_user_snapshots_user_vk_user_seq_idx
+    ON vk_user_snapshots(user_id, vk_user_id, seq);
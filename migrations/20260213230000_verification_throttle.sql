@@ -0,0 +1,4 @@
+ALTER TABLE login_throttles
+    DROP CONSTRAINT IF EXISTS login_throttles_scope_check,
+    ADD CONSTRAINT login_throttles_scope_check
+        CHECK (scope IN ('email', 'ip', 'two_factor', 'verification'));
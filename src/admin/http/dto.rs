@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AccountsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CrawlHealthQuery {
+    pub hours: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccountUsageDto {
+    pub active_sessions: i64,
+    pub active_api_keys: i64,
+    pub groups: i64,
+    pub vk_users: i64,
+    pub notes: i64,
+    pub last_crawl_at: Option<OffsetDateTime>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminAccountDto {
+    pub id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub email_verified_at: Option<OffsetDateTime>,
+    pub disabled_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+    pub two_factor_enabled: bool,
+    pub usage: AccountUsageDto,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrawlSourceHealthDto {
+    pub source: String,
+    pub succeeded: i64,
+    pub failed: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FailingAccountDto {
+    pub user_id: Uuid,
+    pub email: String,
+    pub failed: i64,
+    pub succeeded: i64,
+    pub last_error: Option<String>,
+    pub last_failed_at: OffsetDateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrawlHealthResponse {
+    pub since: OffsetDateTime,
+    pub started: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub active_accounts: i64,
+    pub failing_accounts: i64,
+    pub last_failure_at: Option<OffsetDateTime>,
+    pub by_source: Vec<CrawlSourceHealthDto>,
+    pub top_failing_accounts: Vec<FailingAccountDto>,
+}
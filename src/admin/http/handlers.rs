@@ -0,0 +1,272 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    admin::repo::{AccountSummary, CrawlSourceHealth, FailingAccount},
+    auth::http::RevokeSessionsResponse,
+    error::{ApiError, ApiResult},
+    extractors::admin_user::AdminUser,
+};
+
+use super::dto::{
+    AccountUsageDto, AccountsQuery, AdminAccountDto, CrawlHealthQuery, CrawlHealthResponse,
+    CrawlSourceHealthDto, FailingAccountDto,
+};
+
+const DEFAULT_HEALTH_WINDOW_HOURS: i64 = 24;
+
+/// Crawl events are pruned after a week, so a longer window would lie.
+const MAX_HEALTH_WINDOW_HOURS: i64 = 7 * 24;
+
+const TOP_FAILING_ACCOUNTS: i64 = 20;
+
+fn account_dto(account: AccountSummary) -> AdminAccountDto {
+    AdminAccountDto {
+        id: account.id,
+        email: account.email,
+        role: account.role,
+        email_verified_at: account.email_verified_at,
+        disabled_at: account.disabled_at,
+        created_at: account.created_at,
+        two_factor_enabled: account.two_factor_enabled,
+        usage: AccountUsageDto {
+            active_sessions: account.active_sessions,
+            active_api_keys: account.active_api_keys,
+            groups: account.groups,
+            vk_users: account.vk_users,
+            notes: account.notes,
+            last_crawl_at: account.last_crawl_at,
+        },
+    }
+}
+
+fn source_dto(source: CrawlSourceHealth) -> CrawlSourceHealthDto {
+    CrawlSourceHealthDto {
+        source: source.source,
+        succeeded: source.succeeded,
+        failed: source.failed,
+    }
+}
+
+fn failing_account_dto(account: FailingAccount) -> FailingAccountDto {
+    FailingAccountDto {
+        user_id: account.user_id,
+        email: account.email,
+        failed: account.failed,
+        succeeded: account.succeeded,
+        last_error: account.last_error,
+        last_failed_at: account.last_failed_at,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    params(AccountsQuery),
+    responses(
+        (status = 200, description = "Accounts with usage stats, oldest first", body = [AdminAccountDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Admin role required", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin"
+)]
+pub async fn list_accounts(
+    _admin: AdminUser,
+    State(state): State<AppState>,
+    Query(q): Query<AccountsQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<AdminAccountDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let accounts = crate::admin::repo::list_accounts(&state.db, limit, offset)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(accounts.into_iter().map(account_dto).collect()),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/disable",
+    params(
+        ("id" = Uuid, Path, description = "Account id")
+    ),
+    responses(
+        (status = 204, description = "Account disabled and all of its sessions revoked"),
+        (status = 400, description = "Admins cannot disable their own account", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Admin role required", body = crate::error::ErrorBody),
+        (status = 404, description = "Account not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin"
+)]
+pub async fn disable_account(
+    admin: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    if id == admin.id {
+        return Err(ApiError::BadRequest(
+            "admins cannot disable their own account".to_string(),
+        ));
+    }
+
+    let found = crate::admin::repo::set_account_disabled(&state.db, id, true)
+        .await
+        .map_err(ApiError::Db)?;
+    if !found {
+        return Err(ApiError::NotFound);
+    }
+    crate::auth::repo::revoke_all_sessions(&state.db, id)
+        .await
+        .map_err(ApiError::Db)?;
+    state.sessions.forget_user(id);
+    tracing::warn!(admin_id = %admin.id, user_id = %id, "account disabled");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/enable",
+    params(
+        ("id" = Uuid, Path, description = "Account id")
+    ),
+    responses(
+        (status = 204, description = "Account enabled; the user can log in again"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Admin role required", body = crate::error::ErrorBody),
+        (status = 404, description = "Account not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin"
+)]
+pub async fn enable_account(
+    admin: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let found = crate::admin::repo::set_account_disabled(&state.db, id, false)
+        .await
+        .map_err(ApiError::Db)?;
+    if !found {
+        return Err(ApiError::NotFound);
+    }
+    tracing::info!(admin_id = %admin.id, user_id = %id, "account enabled");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/sessions/revoke",
+    params(
+        ("id" = Uuid, Path, description = "Account id")
+    ),
+    responses(
+        (status = 200, description = "Every session of the account revoked", body = RevokeSessionsResponse),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Admin role required", body = crate::error::ErrorBody),
+        (status = 404, description = "Account not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin"
+)]
+pub async fn revoke_account_sessions(
+    admin: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<(StatusCode, Json<RevokeSessionsResponse>)> {
+    let exists = crate::admin::repo::account_exists(&state.db, id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !exists {
+        return Err(ApiError::NotFound);
+    }
+
+    let revoked = crate::auth::repo::revoke_all_sessions(&state.db, id)
+        .await
+        .map_err(ApiError::Db)?;
+    state.sessions.forget_user(id);
+    tracing::warn!(admin_id = %admin.id, user_id = %id, revoked, "sessions revoked by admin");
+
+    Ok((StatusCode::OK, Json(RevokeSessionsResponse { revoked })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/crawl-health",
+    params(CrawlHealthQuery),
+    responses(
+        (status = 200, description = "Crawl outcomes across all accounts in the window", body = CrawlHealthResponse),
+        (status = 400, description = "Invalid window", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Admin role required", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Admin"
+)]
+pub async fn crawl_health(
+    _admin: AdminUser,
+    State(state): State<AppState>,
+    Query(q): Query<CrawlHealthQuery>,
+) -> ApiResult<(StatusCode, Json<CrawlHealthResponse>)> {
+    let hours = q.hours.unwrap_or(DEFAULT_HEALTH_WINDOW_HOURS);
+    if !(1..=MAX_HEALTH_WINDOW_HOURS).contains(&hours) {
+        return Err(ApiError::BadRequest(format!(
+            "hours must be between 1 and {MAX_HEALTH_WINDOW_HOURS}"
+        )));
+    }
+    let since = OffsetDateTime::now_utc() - Duration::hours(hours);
+
+    let totals = crate::admin::repo::crawl_totals(&state.db, since)
+        .await
+        .map_err(ApiError::Db)?;
+    let by_source = crate::admin::repo::crawl_health_by_source(&state.db, since)
+        .await
+        .map_err(ApiError::Db)?;
+    let failing = crate::admin::repo::list_failing_accounts(&state.db, since, TOP_FAILING_ACCOUNTS)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(CrawlHealthResponse {
+            since,
+            started: totals.started,
+            succeeded: totals.succeeded,
+            failed: totals.failed,
+            active_accounts: totals.active_accounts,
+            failing_accounts: totals.failing_accounts,
+            last_failure_at: totals.last_failure_at,
+            by_source: by_source.into_iter().map(source_dto).collect(),
+            top_failing_accounts: failing.into_iter().map(failing_account_dto).collect(),
+        }),
+    ))
+}
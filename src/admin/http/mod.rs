@@ -0,0 +1,25 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{
+    AccountUsageDto, AdminAccountDto, CrawlHealthResponse, CrawlSourceHealthDto, FailingAccountDto,
+};
+pub use handlers::{
+    crawl_health, disable_account, enable_account, list_accounts, revoke_account_sessions,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(list_accounts))
+        .route("/users/{id}/disable", post(disable_account))
+        .route("/users/{id}/enable", post(enable_account))
+        .route("/users/{id}/sessions/revoke", post(revoke_account_sessions))
+        .route("/crawl-health", get(crawl_health))
+}
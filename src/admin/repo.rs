@@ -0,0 +1,273 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserRole {
+    User,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserRole::User => "user",
+            UserRole::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(UserRole::User),
+            "admin" => Some(UserRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub email_verified_at: Option<OffsetDateTime>,
+    pub disabled_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+    pub two_factor_enabled: bool,
+    pub active_sessions: i64,
+    pub active_api_keys: i64,
+    pub groups: i64,
+    pub vk_users: i64,
+    pub notes: i64,
+    pub last_crawl_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrawlTotals {
+    pub started: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub active_accounts: i64,
+    pub failing_accounts: i64,
+    pub last_failure_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrawlSourceHealth {
+    pub source: String,
+    pub succeeded: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailingAccount {
+    pub user_id: Uuid,
+    pub email: String,
+    pub failed: i64,
+    pub succeeded: i64,
+    pub last_error: Option<String>,
+    pub last_failed_at: OffsetDateTime,
+}
+
+pub async fn get_user_role(db: &PgPool, user_id: Uuid) -> Result<Option<UserRole>, sqlx::Error> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT role
+        FROM users
+        WHERE id = $1 AND disabled_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(role.as_deref().and_then(UserRole::parse))
+}
+
+/// Grants the admin role to the given accounts; used to bootstrap the first
+/// administrators from configuration. Only verified addresses count, since
+/// anyone can register an address before its owner does. Returns the emails
+/// that now belong to an admin.
+pub async fn promote_admins(db: &PgPool, emails: &[String]) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        WITH matched AS (
+            SELECT id, email, role
+            FROM users
+            WHERE email = ANY($1::text[]::citext[]) AND email_verified_at IS NOT NULL
+        ),
+        promoted AS (
+            UPDATE users u
+            SET role = 'admin'
+            FROM matched m
+            WHERE u.id = m.id AND m.role <> 'admin'
+        )
+        SELECT email::text AS "email!"
+        FROM matched
+        "#,
+        emails
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn list_accounts(
+    db: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AccountSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        AccountSummary,
+        r#"
+        SELECT
+            u.id,
+            u.email::text AS "email!",
+            u.role,
+            u.email_verified_at,
+            u.disabled_at,
+            u.created_at,
+            EXISTS (
+                SELECT 1 FROM user_totp t
+                WHERE t.user_id = u.id AND t.confirmed_at IS NOT NULL
+            ) AS "two_factor_enabled!",
+            (
+                SELECT COUNT(*) FROM refresh_tokens rt
+                WHERE rt.user_id = u.id AND rt.revoked_at IS NULL AND rt.expires_at > now()
+            ) AS "active_sessions!",
+            (
+                SELECT COUNT(*) FROM api_keys k
+                WHERE k.user_id = u.id AND k.revoked_at IS NULL AND k.expires_at > now()
+            ) AS "active_api_keys!",
+            (SELECT COUNT(*) FROM groups g WHERE g.user_id = u.id) AS "groups!",
+            (SELECT COUNT(*) FROM vk_users v WHERE v.user_id = u.id) AS "vk_users!",
+            (SELECT COUNT(*) FROM notes n WHERE n.user_id = u.id) AS "notes!",
+            (
+                SELECT MAX(e.created_at) FROM stream_events e
+                WHERE e.user_id = u.id AND e.event_type = 'crawl.finished'
+            ) AS last_crawl_at
+        FROM users u
+        ORDER BY u.created_at, u.id
+        LIMIT $1 OFFSET $2
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Returns `false` when the account does not exist.
+pub async fn set_account_disabled(
+    db: &PgPool,
+    user_id: Uuid,
+    disabled: bool,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE users
+        SET disabled_at = CASE
+            WHEN $2 THEN COALESCE(disabled_at, now())
+            ELSE NULL
+        END
+        WHERE id = $1
+        "#,
+        user_id,
+        disabled
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn account_exists(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM users WHERE id = $1) AS "exists!"
+        "#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn crawl_totals(db: &PgPool, since: OffsetDateTime) -> Result<CrawlTotals, sqlx::Error> {
+    sqlx::query_as!(
+        CrawlTotals,
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE event_type = 'crawl.started') AS "started!",
+            COUNT(*) FILTER (
+                WHERE event_type = 'crawl.finished' AND data->>'status' = 'succeeded'
+            ) AS "succeeded!",
+            COUNT(*) FILTER (
+                WHERE event_type = 'crawl.finished' AND data->>'status' = 'failed'
+            ) AS "failed!",
+            COUNT(DISTINCT user_id) AS "active_accounts!",
+            COUNT(DISTINCT user_id) FILTER (
+                WHERE event_type = 'crawl.finished' AND data->>'status' = 'failed'
+            ) AS "failing_accounts!",
+            MAX(created_at) FILTER (
+                WHERE event_type = 'crawl.finished' AND data->>'status' = 'failed'
+            ) AS last_failure_at
+        FROM stream_events
+        WHERE event_type IN ('crawl.started', 'crawl.finished') AND created_at >= $1
+        "#,
+        since
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn crawl_health_by_source(
+    db: &PgPool,
+    since: OffsetDateTime,
+) -> Result<Vec<CrawlSourceHealth>, sqlx::Error> {
+    sqlx::query_as!(
+        CrawlSourceHealth,
+        r#"
+        SELECT
+            COALESCE(data->>'source', 'unknown') AS "source!",
+            COUNT(*) FILTER (WHERE data->>'status' = 'succeeded') AS "succeeded!",
+            COUNT(*) FILTER (WHERE data->>'status' = 'failed') AS "failed!"
+        FROM stream_events
+        WHERE event_type = 'crawl.finished' AND created_at >= $1
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        since
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn list_failing_accounts(
+    db: &PgPool,
+    since: OffsetDateTime,
+    limit: i64,
+) -> Result<Vec<FailingAccount>, sqlx::Error> {
+    sqlx::query_as!(
+        FailingAccount,
+        r#"
+        SELECT
+            e.user_id,
+            u.email::text AS "email!",
+            COUNT(*) FILTER (WHERE e.data->>'status' = 'failed') AS "failed!",
+            COUNT(*) FILTER (WHERE e.data->>'status' = 'succeeded') AS "succeeded!",
+            (ARRAY_AGG(e.data->>'error' ORDER BY e.created_at DESC)
+                FILTER (WHERE e.data->>'status' = 'failed'))[1] AS last_error,
+            MAX(e.created_at) FILTER (WHERE e.data->>'status' = 'failed') AS "last_failed_at!"
+        FROM stream_events e
+        JOIN users u ON u.id = e.user_id
+        WHERE e.event_type = 'crawl.finished' AND e.created_at >= $1
+        GROUP BY e.user_id, u.email
+        HAVING COUNT(*) FILTER (WHERE e.data->>'status' = 'failed') > 0
+        ORDER BY 3 DESC, 6 DESC
+        LIMIT $2
+        "#,
+        since,
+        limit
+    )
+    .fetch_all(db)
+    .await
+}
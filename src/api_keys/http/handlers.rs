@@ -0,0 +1,177 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    api_keys::repo::{ApiKey, ApiScope, KEY_PREFIX},
+    auth::tokens::{hash_token, new_opaque_token},
+    error::{ApiError, ApiResult},
+    extractors::auth_user::AuthUser,
+};
+
+use super::dto::{ApiKeyDto, CreateApiKeyRequest, CreateApiKeyResponse};
+
+const MAX_NAME_LEN: usize = 100;
+
+const DEFAULT_EXPIRES_IN_DAYS: i64 = 90;
+
+const MAX_EXPIRES_IN_DAYS: i64 = 365;
+
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+fn api_key_dto(key: ApiKey) -> ApiKeyDto {
+    ApiKeyDto {
+        id: key.id,
+        name: key.name,
+        key_prefix: key.key_prefix,
+        scopes: key.scopes,
+        expires_at: key.expires_at,
+        last_used_at: key.last_used_at,
+        created_at: key.created_at,
+    }
+}
+
+fn parse_scopes(values: &[String]) -> ApiResult<Vec<ApiScope>> {
+    let mut scopes = Vec::with_capacity(values.len());
+    for value in values {
+        let scope = ApiScope::parse(value.trim()).ok_or_else(|| {
+            let known: Vec<&str> = ApiScope::ALL.iter().map(|s| s.as_str()).collect();
+            ApiError::BadRequest(format!("scopes must be any of {}", known.join(", ")))
+        })?;
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    if scopes.is_empty() {
+        return Err(ApiError::BadRequest("scopes must not be empty".to_string()));
+    }
+    Ok(scopes)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created; the key is shown only once", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid name, scopes or expiry", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "API keys cannot manage API keys", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "API Keys"
+)]
+pub async fn create_api_key(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> ApiResult<(StatusCode, Json<CreateApiKeyResponse>)> {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "name must be 1 to {MAX_NAME_LEN} characters"
+        )));
+    }
+    let scopes = parse_scopes(&req.scopes)?;
+    let expires_in_days = req.expires_in_days.unwrap_or(DEFAULT_EXPIRES_IN_DAYS);
+    if !(1..=MAX_EXPIRES_IN_DAYS).contains(&expires_in_days) {
+        return Err(ApiError::BadRequest(format!(
+            "expires_in_days must be between 1 and {MAX_EXPIRES_IN_DAYS}"
+        )));
+    }
+
+    let key = format!("{KEY_PREFIX}{}", new_opaque_token());
+    let created = crate::api_keys::repo::create_api_key(
+        &state.db,
+        user.id,
+        name,
+        &key[..DISPLAY_PREFIX_LEN],
+        &hash_token(&key),
+        &scopes,
+        OffsetDateTime::now_utc() + Duration::days(expires_in_days),
+    )
+    .await
+    .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse {
+            id: created.id,
+            name: created.name,
+            key,
+            key_prefix: created.key_prefix,
+            scopes: created.scopes,
+            expires_at: created.expires_at,
+            created_at: created.created_at,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    responses(
+        (status = 200, description = "Active API keys of the current user", body = [ApiKeyDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "API keys cannot manage API keys", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "API Keys"
+)]
+pub async fn list_api_keys(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<ApiKeyDto>>)> {
+    let keys = crate::api_keys::repo::list_api_keys(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(keys.into_iter().map(api_key_dto).collect()),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    params(
+        ("id" = Uuid, Path, description = "API key id")
+    ),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "API keys cannot manage API keys", body = crate::error::ErrorBody),
+        (status = 404, description = "API key not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "API Keys"
+)]
+pub async fn revoke_api_key(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let revoked = crate::api_keys::repo::revoke_api_key(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !revoked {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
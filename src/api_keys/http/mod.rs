@@ -0,0 +1,18 @@
+use axum::{
+    Router,
+    routing::{delete, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{ApiKeyDto, CreateApiKeyRequest, CreateApiKeyResponse};
+pub use handlers::{create_api_key, list_api_keys, revoke_api_key};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_api_key).get(list_api_keys))
+        .route("/{id}", delete(revoke_api_key))
+}
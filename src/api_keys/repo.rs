@@ -0,0 +1,187 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Personal API keys are sent as bearer tokens and start with this prefix,
+/// which tells them apart from access JWTs.
+pub const KEY_PREFIX: &str = "fwk_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    VkUsersRead,
+    VkUsersWrite,
+    GroupsRead,
+    GroupsWrite,
+    NotesRead,
+    NotesWrite,
+    SearchesRead,
+    SearchesWrite,
+    WebhooksRead,
+    WebhooksWrite,
+    Ingest,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 11] = [
+        ApiScope::VkUsersRead,
+        ApiScope::VkUsersWrite,
+        ApiScope::GroupsRead,
+        ApiScope::GroupsWrite,
+        ApiScope::NotesRead,
+        ApiScope::NotesWrite,
+        ApiScope::SearchesRead,
+        ApiScope::SearchesWrite,
+        ApiScope::WebhooksRead,
+        ApiScope::WebhooksWrite,
+        ApiScope::Ingest,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::VkUsersRead => "vk_users:read",
+            ApiScope::VkUsersWrite => "vk_users:write",
+            ApiScope::GroupsRead => "groups:read",
+            ApiScope::GroupsWrite => "groups:write",
+            ApiScope::NotesRead => "notes:read",
+            ApiScope::NotesWrite => "notes:write",
+            ApiScope::SearchesRead => "searches:read",
+            ApiScope::SearchesWrite => "searches:write",
+            ApiScope::WebhooksRead => "webhooks:read",
+            ApiScope::WebhooksWrite => "webhooks:write",
+            ApiScope::Ingest => "ingest",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyOwner {
+    pub user_id: Uuid,
+    pub scopes: Vec<ApiScope>,
+}
+
+pub async fn create_api_key(
+    db: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    key_prefix: &str,
+    key_hash: &str,
+    scopes: &[ApiScope],
+    expires_at: OffsetDateTime,
+) -> Result<ApiKey, sqlx::Error> {
+    let scopes: Vec<String> = scopes
+        .iter()
+        .map(|scope| scope.as_str().to_string())
+        .collect();
+
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (user_id, name, key_prefix, key_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, name, key_prefix, scopes, expires_at, last_used_at, created_at
+        "#,
+        user_id,
+        name,
+        key_prefix,
+        key_hash,
+        &scopes,
+        expires_at
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn list_api_keys(db: &PgPool, user_id: Uuid) -> Result<Vec<ApiKey>, sqlx::Error> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, name, key_prefix, scopes, expires_at, last_used_at, created_at
+        FROM api_keys
+        WHERE user_id = $1 AND revoked_at IS NULL
+        ORDER BY created_at DESC, id
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn revoke_api_key(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = now()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        "#,
+        id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+/// Looks up a live key by hash and bumps `last_used_at` at most once a minute.
+pub async fn authenticate_api_key(
+    db: &PgPool,
+    key_hash: &str,
+) -> Result<Option<ApiKeyOwner>, sqlx::Error> {
+    let Some(row) = sqlx::query!(
+        r#"
+        SELECT k.id, k.user_id, k.scopes, k.last_used_at
+        FROM api_keys k
+        JOIN users u ON u.id = k.user_id
+        WHERE k.key_hash = $1
+          AND k.revoked_at IS NULL
+          AND k.expires_at > now()
+          AND u.disabled_at IS NULL
+        "#,
+        key_hash
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let stale = row
+        .last_used_at
+        .is_none_or(|at| OffsetDateTime::now_utc() - at > time::Duration::minutes(1));
+    if stale {
+        sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = now()
+            WHERE id = $1
+            "#,
+            row.id
+        )
+        .execute(db)
+        .await?;
+    }
+
+    Ok(Some(ApiKeyOwner {
+        user_id: row.user_id,
+        scopes: row
+            .scopes
+            .iter()
+            .filter_map(|scope| ApiScope::parse(scope))
+            .collect(),
+    }))
+}
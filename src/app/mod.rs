@@ -1,3 +1,4 @@
 pub mod docs;
 pub mod openapi;
 pub mod router;
+pub mod trusted_proxies;
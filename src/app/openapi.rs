@@ -31,18 +31,76 @@ impl Modify for SecurityAddon {
         crate::auth::http::handlers::login,
         crate::auth::http::handlers::refresh,
         crate::auth::http::handlers::logout,
+        crate::auth::http::handlers::verify_email,
+        crate::auth::http::handlers::resend_verification,
+        crate::auth::http::handlers::forgot_password,
+        crate::auth::http::handlers::reset_password,
+        crate::auth::http::handlers::confirm_email_change_page,
+        crate::auth::http::handlers::confirm_email_change,
+        crate::auth::http::handlers::change_password,
+        crate::auth::http::handlers::change_email,
+        crate::auth::http::handlers::list_sessions,
+        crate::auth::http::handlers::revoke_session,
+        crate::auth::http::handlers::revoke_all_sessions,
+        crate::auth::http::handlers::login_two_factor,
+        crate::auth::http::handlers::enable_two_factor,
+        crate::auth::http::handlers::confirm_two_factor,
+        crate::auth::http::handlers::disable_two_factor,
         crate::user_settings::http::handlers::get_user_settings,
         crate::user_settings::http::handlers::update_user_settings,
         crate::notes::http::handlers::create_note,
         crate::notes::http::handlers::list_notes,
         crate::notes::http::handlers::get_note,
+        crate::notes::http::handlers::update_note,
+        crate::notes::http::handlers::list_note_versions,
+        crate::notes::http::handlers::restore_note_version,
         crate::notes::http::handlers::delete_note,
         crate::groups::http::handlers::create_group,
         crate::groups::http::handlers::list_groups,
         crate::groups::http::handlers::delete_group,
         crate::vk_users::http::handlers::list_vk_users,
+        crate::vk_users::http::handlers::get_vk_user_history,
+        crate::vk_users::http::handlers::list_vk_user_notes,
+        crate::vk_users::http::handlers::set_vk_user_stage,
+        crate::vk_users::http::handlers::list_vk_user_stage_history,
+        crate::vk_users::http::handlers::update_vk_user,
+        crate::vk_users::http::handlers::assign_vk_user_tag,
+        crate::vk_users::http::handlers::unassign_vk_user_tag,
+        crate::tags::http::handlers::create_tag,
+        crate::tags::http::handlers::list_tags,
+        crate::tags::http::handlers::update_tag,
+        crate::tags::http::handlers::delete_tag,
+        crate::pipeline::http::handlers::create_stage,
+        crate::pipeline::http::handlers::list_stages,
+        crate::pipeline::http::handlers::update_stage,
+        crate::pipeline::http::handlers::delete_stage,
+        crate::pipeline::http::handlers::get_counts,
         crate::vk_tokens::http::handlers::add_vk_tokens,
-        crate::vk_tokens::http::handlers::delete_vk_tokens
+        crate::vk_tokens::http::handlers::delete_vk_tokens,
+        crate::vk_events::http::handlers::list_vk_events,
+        crate::export::http::handlers::export_vk_users,
+        crate::export::http::handlers::export_activity,
+        crate::ingest::http::handlers::ingest_batch,
+        crate::ingest::http::handlers::ingest_vk_raw,
+        crate::saved_searches::http::handlers::create_saved_search,
+        crate::saved_searches::http::handlers::list_saved_searches,
+        crate::saved_searches::http::handlers::delete_saved_search,
+        crate::saved_searches::http::handlers::run_saved_search,
+        crate::saved_searches::http::handlers::list_saved_search_matches,
+        crate::webhooks::http::handlers::create_webhook,
+        crate::webhooks::http::handlers::list_webhooks,
+        crate::webhooks::http::handlers::delete_webhook,
+        crate::webhooks::http::handlers::list_webhook_deliveries,
+        crate::webhooks::http::handlers::send_test_webhook,
+        crate::api_keys::http::handlers::create_api_key,
+        crate::api_keys::http::handlers::list_api_keys,
+        crate::api_keys::http::handlers::revoke_api_key,
+        crate::admin::http::handlers::list_accounts,
+        crate::admin::http::handlers::disable_account,
+        crate::admin::http::handlers::enable_account,
+        crate::admin::http::handlers::revoke_account_sessions,
+        crate::admin::http::handlers::crawl_health,
+        crate::event_stream::http::handlers::stream_events
     ),
     components(schemas(
         crate::error::ErrorBody,
@@ -54,17 +112,74 @@ impl Modify for SecurityAddon {
         crate::auth::http::RefreshRequest,
         crate::auth::http::RefreshResponse,
         crate::auth::http::LogoutRequest,
+        crate::auth::http::VerifyEmailRequest,
+        crate::auth::http::ResendVerificationRequest,
+        crate::auth::http::ForgotPasswordRequest,
+        crate::auth::http::ResetPasswordRequest,
+        crate::auth::http::ChangePasswordRequest,
+        crate::auth::http::ChangeEmailRequest,
+        crate::auth::http::ConfirmEmailChangeRequest,
+        crate::auth::http::SessionDto,
+        crate::auth::http::RevokeSessionsResponse,
+        crate::auth::http::LoginChallengeResponse,
+        crate::auth::http::LoginTwoFactorRequest,
+        crate::auth::http::EnableTwoFactorRequest,
+        crate::auth::http::TwoFactorEnrollmentResponse,
+        crate::auth::http::ConfirmTwoFactorRequest,
+        crate::auth::http::DisableTwoFactorRequest,
         crate::user_settings::http::UserSettingsDto,
         crate::user_settings::http::UpdateUserSettingsRequest,
         crate::notes::http::CreateNoteRequest,
         crate::notes::http::NoteDto,
+        crate::notes::http::UpdateNoteRequest,
+        crate::notes::http::NoteVersionDto,
         crate::groups::http::CreateGroupRequest,
         crate::groups::http::GroupDto,
         crate::vk_users::http::VkUserDto,
+        crate::vk_users::http::VkUserSourceDto,
+        crate::vk_users::http::VkUserHistoryEntryDto,
+        crate::vk_users::http::VkUserFieldChangeDto,
+        crate::vk_users::http::UpdateVkUserRequest,
+        crate::vk_users::http::VkUserStageDto,
+        crate::vk_users::http::SetVkUserStageRequest,
+        crate::vk_users::http::VkUserStageChangeDto,
+        crate::tags::http::CreateTagRequest,
+        crate::tags::http::UpdateTagRequest,
+        crate::tags::http::TagDto,
+        crate::pipeline::http::CreateStageRequest,
+        crate::pipeline::http::UpdateStageRequest,
+        crate::pipeline::http::PipelineStageDto,
+        crate::pipeline::http::StageCountDto,
+        crate::pipeline::http::PipelineCountsDto,
         crate::vk_tokens::http::AddVkTokensRequest,
         crate::vk_tokens::http::AddVkTokensResponse,
         crate::vk_tokens::http::DeleteVkTokensRequest,
-        crate::vk_tokens::http::DeleteVkTokensResponse
+        crate::vk_tokens::http::DeleteVkTokensResponse,
+        crate::vk_events::http::VkEventDto,
+        crate::ingest::http::BatchVkUserDto,
+        crate::ingest::http::BatchVkPostDto,
+        crate::ingest::http::BatchVkCommentDto,
+        crate::ingest::http::BatchVkPostLikeDto,
+        crate::ingest::http::BatchVkCommentLikeDto,
+        crate::ingest::http::IngestBatchRequest,
+        crate::ingest::http::IngestBatchResponse,
+        crate::ingest::http::IngestCountsDto,
+        crate::ingest::http::VkRawIngestResponse,
+        crate::saved_searches::http::CreateSavedSearchRequest,
+        crate::saved_searches::http::SavedSearchDto,
+        crate::saved_searches::http::SavedSearchMatchDto,
+        crate::webhooks::http::CreateWebhookRequest,
+        crate::webhooks::http::CreateWebhookResponse,
+        crate::webhooks::http::WebhookDto,
+        crate::webhooks::http::WebhookDeliveryDto,
+        crate::api_keys::http::CreateApiKeyRequest,
+        crate::api_keys::http::CreateApiKeyResponse,
+        crate::api_keys::http::ApiKeyDto,
+        crate::admin::http::AdminAccountDto,
+        crate::admin::http::AccountUsageDto,
+        crate::admin::http::CrawlHealthResponse,
+        crate::admin::http::CrawlSourceHealthDto,
+        crate::admin::http::FailingAccountDto
     )),
     modifiers(&SecurityAddon),
     tags(
@@ -74,7 +189,17 @@ impl Modify for SecurityAddon {
         (name = "Notes", description = "Notes endpoints"),
         (name = "Groups", description = "User groups endpoints"),
         (name = "VK Users", description = "VK users management endpoints"),
-        (name = "VK Tokens", description = "VK tokens management endpoints")
+        (name = "Tags", description = "Tags for organising VK users"),
+        (name = "Pipeline", description = "Contact pipeline stages for VK users"),
+        (name = "VK Tokens", description = "VK tokens management endpoints"),
+        (name = "VK Events", description = "Deleted VK activity events"),
+        (name = "Export", description = "CSV, NDJSON and XLSX exports"),
+        (name = "Ingest", description = "Crawl batch and raw VK API response ingestion"),
+        (name = "Saved Searches", description = "Saved VK user search criteria and matches"),
+        (name = "Webhooks", description = "Outbound webhook subscriptions and deliveries"),
+        (name = "API Keys", description = "Personal API keys for scripts and integrations"),
+        (name = "Events", description = "Server-Sent Events stream of new finds"),
+        (name = "Admin", description = "Instance administration, admins only")
     )
 )]
 pub struct ApiDoc;
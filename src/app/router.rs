@@ -6,12 +6,23 @@ use crate::app::docs;
 pub fn build_router(state: AppState) -> Router {
     Router::new()
         .merge(crate::core::http::routes())
+        .merge(crate::auth::http::account_routes())
         .nest("/auth", crate::auth::http::routes())
         .nest("/notes", crate::notes::http::routes())
         .nest("/settings", crate::user_settings::http::routes())
         .nest("/groups", crate::groups::http::routes())
         .nest("/vk-users", crate::vk_users::http::routes())
+        .nest("/tags", crate::tags::http::routes())
+        .nest("/pipeline", crate::pipeline::http::routes())
         .nest("/vk-tokens", crate::vk_tokens::http::routes())
+        .nest("/vk-events", crate::vk_events::http::routes())
+        .nest("/export", crate::export::http::routes())
+        .nest("/ingest", crate::ingest::http::routes())
+        .nest("/saved-searches", crate::saved_searches::http::routes())
+        .nest("/webhooks", crate::webhooks::http::routes())
+        .nest("/api-keys", crate::api_keys::http::routes())
+        .nest("/events", crate::event_stream::http::routes())
+        .nest("/admin", crate::admin::http::routes())
         .route("/docs", get(docs::swagger_ui))
         .route("/api-docs/openapi.json", get(docs::openapi_spec))
         .with_state(state)
@@ -0,0 +1,107 @@
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return None;
+        }
+
+        Some(Cidr {
+            network: addr,
+            prefix,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reverse proxies whose `X-Forwarded-For` / `X-Real-IP` headers are believed.
+/// Requests from any other peer are attributed to the peer address itself, so
+/// clients cannot pick the IP that sessions, security events and the login
+/// throttle see.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    ranges: Vec<Cidr>,
+}
+
+impl TrustedProxies {
+    /// Comma-separated IPs or CIDR ranges, e.g. `10.0.0.0/8, 127.0.0.1`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let ranges = value
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| Cidr::parse(v).ok_or(format!("invalid trusted proxy range {v}")))
+            .collect::<Result<_, _>>()?;
+
+        Ok(TrustedProxies { ranges })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("TRUSTED_PROXIES") {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Ok(TrustedProxies::default()),
+        }
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    /// Walks the forwarding chain from the peer towards the client and
+    /// returns the right-most hop that is not a trusted proxy.
+    pub fn client_ip(
+        &self,
+        peer: IpAddr,
+        forwarded_for: Option<&str>,
+        real_ip: Option<&str>,
+    ) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        let Some(forwarded_for) = forwarded_for else {
+            return real_ip.and_then(|v| v.trim().parse().ok()).unwrap_or(peer);
+        };
+
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',') {
+            let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = hop;
+            if !self.contains(hop) {
+                break;
+            }
+        }
+        client
+    }
+}
@@ -0,0 +1,70 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        repo::EmailChanged,
+        tokens::{hash_token, new_opaque_token},
+    },
+    notifications::repo::EmailKind,
+};
+
+pub const TOKEN_TTL: Duration = Duration::hours(24);
+
+pub async fn send_email_change_email(
+    db: &PgPool,
+    user_id: Uuid,
+    new_email: &str,
+    public_base_url: &str,
+) -> Result<(), sqlx::Error> {
+    let token = new_opaque_token();
+
+    crate::auth::repo::create_email_change_token(
+        db,
+        user_id,
+        new_email,
+        &hash_token(&token),
+        OffsetDateTime::now_utc() + TOKEN_TTL,
+    )
+    .await?;
+
+    let body = format!(
+        "Open this link to confirm your new email address:\n\n\
+         {}/auth/email/confirm?token={token}\n\n\
+         It expires in 24 hours. Your current address stays active until then.\n",
+        public_base_url.trim_end_matches('/')
+    );
+    crate::notifications::repo::enqueue_email_to(
+        db,
+        user_id,
+        new_email,
+        EmailKind::EmailChange,
+        "Confirm your new email address",
+        &body,
+    )
+    .await
+}
+
+/// Tells the previous address about a completed change, so an owner whose
+/// session was hijacked learns that the account moved away from them.
+pub async fn send_email_changed_notice(
+    db: &PgPool,
+    changed: &EmailChanged,
+) -> Result<(), sqlx::Error> {
+    let body = format!(
+        "The email address of your account was changed to {}.\n\n\
+         If you did not make this change, reset your password right away and \
+         contact support.\n",
+        changed.new_email
+    );
+    crate::notifications::repo::enqueue_email_to(
+        db,
+        changed.user_id,
+        &changed.old_email,
+        EmailKind::EmailChange,
+        "Your email address was changed",
+        &body,
+    )
+    .await
+}
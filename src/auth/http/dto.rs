@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -43,3 +44,91 @@ pub struct RefreshResponse {
 pub struct LogoutRequest {
     pub refresh_token: String,
 }
+
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResendVerificationRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub current_password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ConfirmEmailChangeRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SessionDto {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoginChallengeResponse {
+    pub challenge_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginTwoFactorRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EnableTwoFactorRequest {
+    pub password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorEnrollmentResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ConfirmTwoFactorRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    pub code: String,
+}
@@ -1,31 +1,84 @@
-use argon2::{
-    Argon2, PasswordHash, PasswordVerifier,
-    password_hash::{
-        Error as PHError, PasswordHasher, SaltString,
-        rand_core::{OsRng, RngCore},
-    },
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
 };
-use axum::{Json, extract::State, http::StatusCode};
-use base64::Engine;
-use jsonwebtoken::{Header, encode};
-use sha2::{Digest, Sha256};
-use time::{Duration, OffsetDateTime};
+use time::OffsetDateTime;
+use uuid::Uuid;
 
 use crate::{
-    AppState, Claims,
-    auth::http::{
-        LoginRequest, LoginResponse, RegisterRequest, RegisterResponse,
-        dto::{LogoutRequest, RefreshRequest, RefreshResponse},
+    AppState,
+    auth::{
+        http::{
+            LoginRequest, LoginResponse, RegisterRequest, RegisterResponse,
+            dto::{
+                ChangeEmailRequest, ChangePasswordRequest, ConfirmEmailChangeRequest,
+                ConfirmTwoFactorRequest, DisableTwoFactorRequest, EnableTwoFactorRequest,
+                ForgotPasswordRequest, LoginChallengeResponse, LoginTwoFactorRequest,
+                LogoutRequest, RefreshRequest, RefreshResponse, ResendVerificationRequest,
+                ResetPasswordRequest, RevokeSessionsResponse, SessionDto,
+                TwoFactorEnrollmentResponse, VerifyEmailRequest,
+            },
+        },
+        login_throttle,
+        passwords::{hash_password, validate_new_password, verify_password},
+        repo::Session,
+        tokens::{
+            ACCESS_TOKEN_TTL, REFRESH_REUSE_GRACE, REFRESH_TOKEN_TTL, encode_access_token,
+            hash_token, new_opaque_token,
+        },
+        totp, two_factor,
     },
     error::{ApiError, ApiResult},
+    extractors::{auth_user::AuthUser, client_info::ClientInfo},
 };
 
+fn session_dto(session: Session) -> SessionDto {
+    SessionDto {
+        id: session.id,
+        user_agent: session.user_agent,
+        ip_address: session.ip_address,
+        created_at: session.created_at,
+        last_used_at: session.last_used_at,
+        expires_at: session.expires_at,
+    }
+}
+
+const MAX_EMAIL_LEN: usize = 254;
+
+fn validate_email(email: &str) -> ApiResult<()> {
+    let invalid = || ApiError::BadRequest("email must be a valid email address".to_string());
+
+    if email.len() > MAX_EMAIL_LEN || email.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+    let domain_ok = domain.contains('.')
+        && !domain.contains('@')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'));
+    if local.is_empty() || local.len() > 64 || !domain_ok {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+async fn send_verification_email(state: &AppState, user_id: uuid::Uuid) {
+    if let Err(e) = crate::auth::verification::send_verification_email(&state.db, user_id).await {
+        tracing::error!("failed to send verification email: {e}");
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/auth/register",
     request_body = RegisterRequest,
     responses(
-        (status = 201, description = "User registered", body = RegisterResponse),
+        (status = 201, description = "User registered; a verification token is emailed", body = RegisterResponse),
+        (status = 400, description = "Invalid email", body = crate::error::ErrorBody),
         (status = 409, description = "Email already exists", body = crate::error::ErrorBody),
         (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
     ),
@@ -36,12 +89,8 @@ pub async fn register(
     Json(mut req): Json<RegisterRequest>,
 ) -> ApiResult<(StatusCode, Json<RegisterResponse>)> {
     req.email = req.email.trim().to_string();
-    let salt_string = SaltString::generate(OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(req.password.as_bytes(), &salt_string)
-        .map_err(|error| ApiError::Hash(error.to_string()))?
-        .to_string();
+    validate_email(&req.email)?;
+    let password_hash = hash_password(&req.password)?;
 
     let res = sqlx::query_scalar!(
         r#"
@@ -58,6 +107,7 @@ pub async fn register(
         }
         Err(e) => return Err(ApiError::Db(e)),
     };
+    send_verification_email(&state, user_id).await;
     Ok((
         StatusCode::CREATED,
         Json(RegisterResponse {
@@ -73,19 +123,31 @@ pub async fn register(
     request_body = LoginRequest,
     responses(
         (status = 200, description = "Access and refresh tokens", body = LoginResponse),
+        (status = 202, description = "Password accepted; finish with a 2FA code at /auth/login/2fa", body = LoginChallengeResponse),
         (status = 401, description = "Invalid credentials", body = crate::error::ErrorBody),
+        (status = 403, description = "Email address is not verified or the account is disabled", body = crate::error::ErrorBody),
+        (status = 429, description = "Too many failed attempts; see the Retry-After header", body = crate::error::ErrorBody),
         (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
     ),
     tag = "Auth"
 )]
 pub async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(login_reqest): Json<LoginRequest>,
-) -> ApiResult<(StatusCode, Json<LoginResponse>)> {
+) -> ApiResult<Response> {
     let email = login_reqest.email.trim().to_string();
+    let attempt = login_throttle::reserve_attempt(
+        &state.db,
+        &state.login_throttle,
+        &login_throttle::email_key(&email),
+        client.ip.as_deref(),
+    )
+    .await?;
+
     let row = sqlx::query!(
         r#"
-    SELECT id, password_hash
+    SELECT id, password_hash, email_verified_at, disabled_at
     FROM users
     WHERE email = $1
     "#,
@@ -94,65 +156,168 @@ pub async fn login(
     .fetch_optional(&state.db)
     .await
     .map_err(ApiError::Db)?;
-    let row = match row {
-        Some(r) => r,
-        None => return Err(ApiError::Unauthorized),
+    let verified = match &row {
+        Some(r) => verify_password(&login_reqest.password, &r.password_hash),
+        None => Err(ApiError::Unauthorized),
     };
-    let parsed =
-        PasswordHash::new(&row.password_hash).map_err(|e| ApiError::Hash(e.to_string()))?;
-    match Argon2::default().verify_password(login_reqest.password.as_bytes(), &parsed) {
-        Ok(()) => {}
-        Err(PHError::Password) => return Err(ApiError::Unauthorized),
-        Err(e) => return Err(ApiError::Hash(e.to_string())),
+    if let Err(e) = verified {
+        if matches!(e, ApiError::Unauthorized) {
+            attempt
+                .record_failure(&state.db, &state.login_throttle)
+                .await
+                .map_err(ApiError::Db)?;
+        }
+        return Err(e);
     }
-    let now = OffsetDateTime::now_utc().unix_timestamp();
-    let exp = now + 60 * 30; // 30 minutes
-    let claims = Claims {
-        sub: row.id.to_string(),
-        iat: now,
-        exp,
-    };
+    let row = row.ok_or(ApiError::Unauthorized)?;
+    let totp_enabled = crate::auth::repo::is_totp_enabled(&state.db, row.id)
+        .await
+        .map_err(ApiError::Db)?;
+    // The email counter is only cleared once the second factor passes too.
+    attempt
+        .record_success(&state.db, totp_enabled)
+        .await
+        .map_err(ApiError::Db)?;
+    if row.disabled_at.is_some() {
+        return Err(ApiError::AccountDisabled);
+    }
+    if state.email_verification.blocks_login() && row.email_verified_at.is_none() {
+        return Err(ApiError::EmailNotVerified);
+    }
+
+    if totp_enabled {
+        let challenge_token = new_opaque_token();
+        crate::auth::repo::create_login_challenge(
+            &state.db,
+            row.id,
+            &hash_token(&challenge_token),
+            OffsetDateTime::now_utc() + two_factor::CHALLENGE_TTL,
+        )
+        .await
+        .map_err(ApiError::Db)?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(LoginChallengeResponse {
+                challenge_token,
+                expires_in: two_factor::CHALLENGE_TTL.whole_seconds(),
+            }),
+        )
+            .into_response());
+    }
+
+    let tokens = issue_login_tokens(&state, row.id, &client).await?;
+    Ok((StatusCode::OK, Json(tokens)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/2fa",
+    request_body = LoginTwoFactorRequest,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = LoginResponse),
+        (status = 401, description = "Invalid or expired challenge, or wrong code", body = crate::error::ErrorBody),
+        (status = 403, description = "Account is disabled", body = crate::error::ErrorBody),
+        (status = 429, description = "Too many wrong codes for this account; see the Retry-After header", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    tag = "Auth"
+)]
+pub async fn login_two_factor(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<LoginTwoFactorRequest>,
+) -> ApiResult<(StatusCode, Json<LoginResponse>)> {
+    let (challenge_id, user_id) = crate::auth::repo::claim_login_challenge_attempt(
+        &state.db,
+        &hash_token(req.challenge_token.trim()),
+        two_factor::MAX_CHALLENGE_ATTEMPTS,
+    )
+    .await
+    .map_err(ApiError::Db)?
+    .ok_or(ApiError::Unauthorized)?;
+    let attempt =
+        login_throttle::reserve_two_factor_attempt(&state.db, &state.login_throttle, user_id)
+            .await?;
 
-    let token = encode(&Header::default(), &claims, &state.jwt_enc)
-        .map_err(|e| ApiError::Hash(e.to_string()))?;
+    let factor =
+        two_factor::verify_second_factor(&state.db, &state.vk_token_enc_key, user_id, &req.code)
+            .await
+            .map_err(ApiError::Db)?;
+    if factor.is_none() {
+        attempt
+            .record_failure(&state.db, &state.login_throttle)
+            .await
+            .map_err(ApiError::Db)?;
+        return Err(ApiError::Unauthorized);
+    }
+    attempt
+        .record_success(&state.db, false)
+        .await
+        .map_err(ApiError::Db)?;
+    if let Some(email) = crate::auth::repo::get_user_email(&state.db, user_id)
+        .await
+        .map_err(ApiError::Db)?
+    {
+        login_throttle::clear_email(&state.db, &login_throttle::email_key(&email))
+            .await
+            .map_err(ApiError::Db)?;
+    }
 
-    let refresh_token = new_refresh_token();
-    let token_hash = hash_refresh_token(&refresh_token);
-    let expaires_at = OffsetDateTime::now_utc() + time::Duration::days(30);
+    let consumed = crate::auth::repo::consume_login_challenge(&state.db, challenge_id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !consumed {
+        return Err(ApiError::Unauthorized);
+    }
+    let disabled = crate::auth::repo::is_account_disabled(&state.db, user_id)
+        .await
+        .map_err(ApiError::Db)?;
+    if disabled {
+        return Err(ApiError::AccountDisabled);
+    }
+
+    let tokens = issue_login_tokens(&state, user_id, &client).await?;
+    Ok((StatusCode::OK, Json(tokens)))
+}
 
-    let _row = sqlx::query!(
+async fn issue_login_tokens(
+    state: &AppState,
+    user_id: Uuid,
+    client: &ClientInfo,
+) -> ApiResult<LoginResponse> {
+    let refresh_token = new_opaque_token();
+    let token_hash = hash_token(&refresh_token);
+    let expaires_at = OffsetDateTime::now_utc() + REFRESH_TOKEN_TTL;
+
+    let session = sqlx::query!(
         r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2 , $3)
-        RETURNING id
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at, user_agent, ip_address)
+        VALUES ($1, $2 , $3, $4, $5)
+        RETURNING family_id
         "#,
-        row.id,
+        user_id,
         token_hash,
-        expaires_at
+        expaires_at,
+        client.user_agent,
+        client.ip
     )
     .fetch_one(&state.db)
     .await
     .map_err(ApiError::Db)?;
 
-    Ok((
-        StatusCode::OK,
-        Json(LoginResponse {
-            access_token: token,
-            refresh_token,
-            token_type: "Bearer",
-        }),
-    ))
-}
+    let token = encode_access_token(
+        state,
+        user_id,
+        session.family_id,
+        time::Duration::minutes(30),
+    )?;
 
-fn new_refresh_token() -> String {
-    let mut bytes = [0_u8; 32];
-    OsRng.fill_bytes(&mut bytes);
-    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
-}
-
-fn hash_refresh_token(token: &str) -> String {
-    let digest = Sha256::digest(token.as_bytes());
-    hex::encode(digest)
+    Ok(LoginResponse {
+        access_token: token,
+        refresh_token,
+        token_type: "Bearer",
+    })
 }
 
 #[utoipa::path(
@@ -162,23 +327,30 @@ fn hash_refresh_token(token: &str) -> String {
     responses(
         (status = 200, description = "Access and refresh tokens rotated", body = RefreshResponse),
         (status = 401, description = "Invalid or expired refresh token", body = crate::error::ErrorBody),
+        (status = 403, description = "Account is disabled", body = crate::error::ErrorBody),
         (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
     ),
     tag = "Auth"
 )]
 pub async fn refresh(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(req): Json<RefreshRequest>,
 ) -> ApiResult<(StatusCode, Json<RefreshResponse>)> {
     let now = OffsetDateTime::now_utc();
-    let token_hash = hash_refresh_token(&req.refresh_token);
+    let token_hash = hash_token(&req.refresh_token);
 
     let mut tx = state.db.begin().await.map_err(ApiError::Db)?;
     let old = sqlx::query!(
         r#"
-        SELECT id, user_id, expires_at, revoked_at
-        FROM refresh_tokens
-        WHERE token_hash = $1
+        SELECT rt.id, rt.user_id, rt.family_id, rt.expires_at, rt.revoked_at, rt.replaced_by,
+               u.disabled_at,
+               (next.replaced_by IS NULL AND next.revoked_at IS NULL) AS successor_unused
+        FROM refresh_tokens rt
+        JOIN users u ON u.id = rt.user_id
+        LEFT JOIN refresh_tokens next ON next.id = rt.replaced_by
+        WHERE rt.token_hash = $1
+        FOR UPDATE OF rt
         "#,
         token_hash
     )
@@ -187,24 +359,53 @@ pub async fn refresh(
     .map_err(ApiError::Db)?
     .ok_or(ApiError::Unauthorized)?;
 
+    if old.replaced_by.is_some() {
+        tx.rollback().await.map_err(ApiError::Db)?;
+        let retried = old.successor_unused == Some(true)
+            && old
+                .revoked_at
+                .is_some_and(|rotated_at| now - rotated_at < REFRESH_REUSE_GRACE);
+        if retried {
+            return Err(ApiError::Unauthorized);
+        }
+        crate::auth::security_events::handle_refresh_token_reuse(
+            &state.db,
+            old.user_id,
+            old.family_id,
+            client.user_agent.as_deref(),
+            client.ip.as_deref(),
+        )
+        .await
+        .map_err(ApiError::Db)?;
+        state.sessions.forget_user(old.user_id);
+        return Err(ApiError::Unauthorized);
+    }
+
     if old.revoked_at.is_some() || old.expires_at <= now {
         return Err(ApiError::Unauthorized);
     }
+    if old.disabled_at.is_some() {
+        return Err(ApiError::AccountDisabled);
+    }
 
     // new refresh token
-    let new_refresh_token = new_refresh_token();
-    let new_hash = hash_refresh_token(&new_refresh_token);
-    let new_expires_at = now + Duration::days(30);
+    let new_refresh_token = new_opaque_token();
+    let new_hash = hash_token(&new_refresh_token);
+    let new_expires_at = now + REFRESH_TOKEN_TTL;
 
     let new_row = sqlx::query!(
         r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2, $3)
+        INSERT INTO refresh_tokens
+            (user_id, family_id, token_hash, expires_at, user_agent, ip_address)
+        VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING id
         "#,
         old.user_id,
+        old.family_id,
         new_hash,
-        new_expires_at
+        new_expires_at,
+        client.user_agent,
+        client.ip
     )
     .fetch_one(&mut *tx)
     .await
@@ -213,7 +414,7 @@ pub async fn refresh(
     let upd = sqlx::query!(
         r#"
         UPDATE refresh_tokens
-        SET revoked_at = $1, replaced_by = $2
+        SET revoked_at = $1, replaced_by = $2, last_used_at = $1
         WHERE id = $3 AND revoked_at IS NULL
         "#,
         now,
@@ -229,18 +430,7 @@ pub async fn refresh(
     }
     tx.commit().await.map_err(ApiError::Db)?;
 
-    //new access JWT (15 minutes)
-    let iat = now.unix_timestamp();
-    let exp = iat + 15 * 60;
-
-    let claims = Claims {
-        sub: old.user_id.to_string(),
-        iat,
-        exp,
-    };
-
-    let access_token = encode(&Header::default(), &claims, &state.jwt_enc)
-        .map_err(|e| ApiError::Hash(e.to_string()))?;
+    let access_token = encode_access_token(&state, old.user_id, old.family_id, ACCESS_TOKEN_TTL)?;
 
     Ok((
         StatusCode::OK,
@@ -266,21 +456,588 @@ pub async fn logout(
     State(state): State<AppState>,
     Json(req): Json<LogoutRequest>,
 ) -> ApiResult<StatusCode> {
-    let token_hash = hash_refresh_token(&req.refresh_token);
+    let token_hash = hash_token(&req.refresh_token);
     let now = OffsetDateTime::now_utc();
 
-    let _res = sqlx::query!(
+    let revoked = sqlx::query_scalar!(
         r#"
         UPDATE refresh_tokens
         SET revoked_at = $1
         WHERE token_hash = $2 AND revoked_at IS NULL
+        RETURNING user_id
         "#,
         now,
         token_hash
     )
-    .execute(&state.db)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::Db)?;
+
+    if let Some(user_id) = revoked {
+        state.sessions.forget_user(user_id);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 204, description = "Email address verified"),
+        (status = 400, description = "Invalid or expired verification token", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    tag = "Auth"
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> ApiResult<StatusCode> {
+    crate::auth::repo::verify_email(&state.db, &hash_token(req.token.trim()))
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::BadRequest(
+            "invalid or expired verification token".to_string(),
+        ))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/resend-verification",
+    request_body = ResendVerificationRequest,
+    responses(
+        (status = 202, description = "A new verification token is emailed if the account exists and is not verified"),
+        (status = 429, description = "Too many requests from this client; see the Retry-After header", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    tag = "Auth"
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<ResendVerificationRequest>,
+) -> ApiResult<StatusCode> {
+    if let Some(ip) = client.ip.as_deref() {
+        login_throttle::reserve_verification_request(&state.db, &state.login_throttle, ip).await?;
+    }
+
+    crate::auth::verification::spawn_verification_email(state.db.clone(), req.email);
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password/forgot",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "A reset token is emailed if the account exists")
+    ),
+    tag = "Auth"
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> StatusCode {
+    crate::auth::password_reset::spawn_password_reset(state.db.clone(), req.email);
+    StatusCode::ACCEPTED
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password/reset",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "Password changed and all refresh tokens revoked"),
+        (status = 400, description = "Invalid or expired reset token, or weak password", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    tag = "Auth"
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> ApiResult<StatusCode> {
+    validate_new_password(&req.new_password)?;
+    let password_hash = hash_password(&req.new_password)?;
+
+    let user_id =
+        crate::auth::repo::reset_password(&state.db, &hash_token(req.token.trim()), &password_hash)
+            .await
+            .map_err(ApiError::Db)?
+            .ok_or(ApiError::BadRequest(
+                "invalid or expired reset token".to_string(),
+            ))?;
+    state.sessions.forget_user(user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/password",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; every other session is revoked and a new token pair is issued", body = LoginResponse),
+        (status = 400, description = "New password is too short", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized or wrong current password", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn change_password(
+    user: AuthUser,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(req): Json<ChangePasswordRequest>,
+) -> ApiResult<(StatusCode, Json<LoginResponse>)> {
+    let current_hash = crate::auth::repo::get_password_hash(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::Unauthorized)?;
+    verify_password(&req.current_password, &current_hash)?;
+
+    validate_new_password(&req.new_password)?;
+    let password_hash = hash_password(&req.new_password)?;
+
+    let refresh_token = new_opaque_token();
+    let session_id = crate::auth::repo::change_password(
+        &state.db,
+        user.id,
+        &password_hash,
+        &hash_token(&refresh_token),
+        OffsetDateTime::now_utc() + REFRESH_TOKEN_TTL,
+        client.user_agent.as_deref(),
+        client.ip.as_deref(),
+    )
+    .await
+    .map_err(ApiError::Db)?;
+    state.sessions.forget_user(user.id);
+
+    let access_token = encode_access_token(&state, user.id, session_id, ACCESS_TOKEN_TTL)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LoginResponse {
+            access_token,
+            refresh_token,
+            token_type: "Bearer",
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/email",
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 202, description = "A confirmation link is emailed to the new address"),
+        (status = 400, description = "Invalid email or same as the current one", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized or wrong current password", body = crate::error::ErrorBody),
+        (status = 409, description = "Email already exists", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn change_email(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<ChangeEmailRequest>,
+) -> ApiResult<StatusCode> {
+    let new_email = req.new_email.trim();
+    validate_email(new_email)?;
+
+    let current_hash = crate::auth::repo::get_password_hash(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::Unauthorized)?;
+    verify_password(&req.current_password, &current_hash)?;
+
+    match crate::auth::repo::find_user_id_by_email(&state.db, new_email)
+        .await
+        .map_err(ApiError::Db)?
+    {
+        Some(id) if id == user.id => {
+            return Err(ApiError::BadRequest(
+                "new email must differ from the current one".to_string(),
+            ));
+        }
+        Some(_) => return Err(ApiError::EmailTaken),
+        None => {}
+    }
+
+    crate::auth::email_change::send_email_change_email(
+        &state.db,
+        user.id,
+        new_email,
+        &state.public_base_url,
+    )
+    .await
+    .map_err(ApiError::Db)?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/email/confirm",
+    params(
+        ("token" = String, Query, description = "Confirmation token from the email")
+    ),
+    responses(
+        (status = 200, description = "Page that asks the user to confirm the change; opening it changes nothing", content_type = "text/html", body = String)
+    ),
+    tag = "Auth"
+)]
+pub async fn confirm_email_change_page() -> Html<&'static str> {
+    // Mail scanners and link previews fetch links on their own, so the link
+    // only leads here and the change needs an explicit POST.
+    Html(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <meta name="viewport" content="width=device-width, initial-scale=1" />
+  <meta name="referrer" content="no-referrer" />
+  <title>Confirm your new email address</title>
+</head>
+<body>
+  <h1>Confirm your new email address</h1>
+  <form id="confirm">
+    <button type="submit">Confirm</button>
+  </form>
+  <p id="result" role="status"></p>
+  <script>
+    document.getElementById('confirm').addEventListener('submit', async (event) => {
+      event.preventDefault();
+      const token = new URLSearchParams(window.location.search).get('token') || '';
+      const res = await fetch('/auth/email/confirm', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ token }),
+      });
+      document.getElementById('result').textContent = res.ok
+        ? 'Your email address has been changed.'
+        : res.status === 409
+          ? 'This address is already used by another account.'
+          : 'This link is invalid or has expired.';
+    });
+  </script>
+</body>
+</html>
+"#,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/email/confirm",
+    request_body = ConfirmEmailChangeRequest,
+    responses(
+        (status = 204, description = "Email address changed; the previous address is notified"),
+        (status = 400, description = "Invalid or expired confirmation token", body = crate::error::ErrorBody),
+        (status = 409, description = "Email already exists", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    tag = "Auth"
+)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> ApiResult<StatusCode> {
+    let res =
+        crate::auth::repo::confirm_email_change(&state.db, &hash_token(req.token.trim())).await;
+    let changed = match res {
+        Ok(Some(changed)) => changed,
+        Ok(None) => {
+            return Err(ApiError::BadRequest(
+                "invalid or expired confirmation token".to_string(),
+            ));
+        }
+        Err(sqlx::Error::Database(db_error)) if db_error.code().as_deref() == Some("23505") => {
+            return Err(ApiError::EmailTaken);
+        }
+        Err(e) => return Err(ApiError::Db(e)),
+    };
+
+    crate::auth::email_change::send_email_changed_notice(&state.db, &changed)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/sessions",
+    responses(
+        (status = 200, description = "Active sessions, most recently used first", body = [SessionDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn list_sessions(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<SessionDto>>)> {
+    let sessions = crate::auth::repo::list_sessions(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(sessions.into_iter().map(session_dto).collect()),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me/sessions/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Session id")
+    ),
+    responses(
+        (status = 204, description = "Session revoked; its refresh token no longer works"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Session not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn revoke_session(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let revoked = crate::auth::repo::revoke_session(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !revoked {
+        return Err(ApiError::NotFound);
+    }
+    state.sessions.forget_user(user.id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/sessions/revoke-all",
+    responses(
+        (status = 200, description = "Every session revoked, including the current one", body = RevokeSessionsResponse),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn revoke_all_sessions(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<RevokeSessionsResponse>)> {
+    let revoked = crate::auth::repo::revoke_all_sessions(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+    state.sessions.forget_user(user.id);
+
+    Ok((StatusCode::OK, Json(RevokeSessionsResponse { revoked })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/2fa",
+    request_body = EnableTwoFactorRequest,
+    responses(
+        (status = 200, description = "Enrollment started; confirm it with a code from the authenticator app", body = TwoFactorEnrollmentResponse),
+        (status = 401, description = "Unauthorized or wrong password", body = crate::error::ErrorBody),
+        (status = 409, description = "Two-factor authentication is already enabled", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn enable_two_factor(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<EnableTwoFactorRequest>,
+) -> ApiResult<(StatusCode, Json<TwoFactorEnrollmentResponse>)> {
+    let password_hash = crate::auth::repo::get_password_hash(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::Unauthorized)?;
+    verify_password(&req.password, &password_hash)?;
+
+    let secret = totp::new_secret();
+    let recovery_codes = totp::new_recovery_codes();
+    let recovery_code_hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| hash_token(&totp::normalize_recovery_code(code)))
+        .collect();
+
+    let started = crate::auth::repo::start_totp_enrollment(
+        &state.db,
+        user.id,
+        &secret,
+        &state.vk_token_enc_key,
+        &recovery_code_hashes,
+    )
     .await
     .map_err(ApiError::Db)?;
+    if !started {
+        return Err(ApiError::Conflict(
+            "two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let email = crate::auth::repo::get_user_email(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::Unauthorized)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(TwoFactorEnrollmentResponse {
+            otpauth_uri: totp::otpauth_uri(&secret, &email),
+            secret,
+            recovery_codes,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/2fa/confirm",
+    request_body = ConfirmTwoFactorRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication enabled"),
+        (status = 400, description = "No pending enrollment or invalid code", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 409, description = "Two-factor authentication is already enabled", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn confirm_two_factor(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmTwoFactorRequest>,
+) -> ApiResult<StatusCode> {
+    let enrollment = crate::auth::repo::get_totp(&state.db, user.id, &state.vk_token_enc_key)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::BadRequest(
+            "two-factor enrollment has not been started".to_string(),
+        ))?;
+    if enrollment.confirmed_at.is_some() {
+        return Err(ApiError::Conflict(
+            "two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let step = totp::verify_code(&enrollment.secret, &req.code, now)
+        .ok_or(ApiError::BadRequest("invalid code".to_string()))?;
+
+    let confirmed = crate::auth::repo::confirm_totp(&state.db, user.id, step)
+        .await
+        .map_err(ApiError::Db)?;
+    if !confirmed {
+        return Err(ApiError::Conflict(
+            "two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me/2fa",
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Invalid code", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized or wrong password", body = crate::error::ErrorBody),
+        (status = 404, description = "Two-factor authentication is not enabled", body = crate::error::ErrorBody),
+        (status = 429, description = "Too many wrong passwords or codes for this account; see the Retry-After header", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Core"
+)]
+pub async fn disable_two_factor(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(req): Json<DisableTwoFactorRequest>,
+) -> ApiResult<StatusCode> {
+    let enabled = crate::auth::repo::is_totp_enabled(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !enabled {
+        return Err(ApiError::NotFound);
+    }
+
+    // Shares the account's second-factor budget with login, so a stolen
+    // access token cannot be used to guess codes here instead.
+    let attempt =
+        login_throttle::reserve_two_factor_attempt(&state.db, &state.login_throttle, user.id)
+            .await?;
+    let password_hash = crate::auth::repo::get_password_hash(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::Unauthorized)?;
+    let outcome = match verify_password(&req.password, &password_hash) {
+        Ok(()) => {
+            two_factor::verify_second_factor(&state.db, &state.vk_token_enc_key, user.id, &req.code)
+                .await
+                .map_err(ApiError::Db)?
+                .map(|_| ())
+                .ok_or(ApiError::BadRequest("invalid code".to_string()))
+        }
+        Err(ApiError::Unauthorized) => Err(ApiError::Unauthorized),
+        Err(e) => return Err(e),
+    };
+    if let Err(e) = outcome {
+        attempt
+            .record_failure(&state.db, &state.login_throttle)
+            .await
+            .map_err(ApiError::Db)?;
+        return Err(e);
+    }
+    attempt
+        .record_success(&state.db, false)
+        .await
+        .map_err(ApiError::Db)?;
+
+    crate::auth::repo::disable_totp(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,4 +1,7 @@
-use axum::{Router, routing::post};
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
 
 use crate::AppState;
 
@@ -6,15 +9,47 @@ mod dto;
 pub(crate) mod handlers;
 
 pub use dto::{
-    LoginRequest, LoginResponse, LogoutRequest, RefreshRequest, RefreshResponse, RegisterRequest,
-    RegisterResponse,
+    ChangeEmailRequest, ChangePasswordRequest, ConfirmEmailChangeRequest, ConfirmTwoFactorRequest,
+    DisableTwoFactorRequest, EnableTwoFactorRequest, ForgotPasswordRequest, LoginChallengeResponse,
+    LoginRequest, LoginResponse, LoginTwoFactorRequest, LogoutRequest, RefreshRequest,
+    RefreshResponse, RegisterRequest, RegisterResponse, ResendVerificationRequest,
+    ResetPasswordRequest, RevokeSessionsResponse, SessionDto, TwoFactorEnrollmentResponse,
+    VerifyEmailRequest,
+};
+pub use handlers::{
+    change_email, change_password, confirm_email_change, confirm_email_change_page,
+    confirm_two_factor, disable_two_factor, enable_two_factor, forgot_password, list_sessions,
+    login, login_two_factor, logout, refresh, register, resend_verification, reset_password,
+    revoke_all_sessions, revoke_session, verify_email,
 };
-pub use handlers::{login, logout, refresh, register};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/login/2fa", post(login_two_factor))
         .route("/logout", post(logout))
         .route("/refresh", post(refresh))
+        .route("/verify-email", post(verify_email))
+        .route("/resend-verification", post(resend_verification))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route(
+            "/email/confirm",
+            get(confirm_email_change_page).post(confirm_email_change),
+        )
+}
+
+pub fn account_routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/password", post(change_password))
+        .route("/me/email", post(change_email))
+        .route("/me/sessions", get(list_sessions))
+        .route("/me/sessions/{id}", delete(revoke_session))
+        .route("/me/sessions/revoke-all", post(revoke_all_sessions))
+        .route(
+            "/me/2fa",
+            post(enable_two_factor).delete(disable_two_factor),
+        )
+        .route("/me/2fa/confirm", post(confirm_two_factor))
 }
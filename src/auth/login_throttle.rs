@@ -0,0 +1,264 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleScope {
+    Email,
+    Ip,
+    TwoFactor,
+    Verification,
+}
+
+impl ThrottleScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThrottleScope::Email => "email",
+            ThrottleScope::Ip => "ip",
+            ThrottleScope::TwoFactor => "two_factor",
+            ThrottleScope::Verification => "verification",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleRule {
+    /// Failures allowed before any delay kicks in.
+    pub free_attempts: i32,
+    /// Failures after which the key is locked for `lockout`.
+    pub lockout_after: i32,
+    /// Delay after the first throttled failure; doubles with each further one.
+    pub base_delay: Duration,
+    pub lockout: Duration,
+}
+
+impl ThrottleRule {
+    pub fn delay_for(&self, failures: i32) -> Option<Duration> {
+        if failures >= self.lockout_after {
+            return Some(self.lockout);
+        }
+        if failures <= self.free_attempts {
+            return None;
+        }
+
+        let doublings = (failures - self.free_attempts - 1).min(20) as u32;
+        Some((self.base_delay * 2_i32.pow(doublings)).min(self.lockout))
+    }
+}
+
+/// Failed logins are counted per email and per client IP, wrong second
+/// factors per account and verification email requests per client IP, in
+/// Postgres so every replica sees the same counters. Counters reset once
+/// `window` passes without a failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginThrottlePolicy {
+    pub email: ThrottleRule,
+    pub ip: ThrottleRule,
+    pub two_factor: ThrottleRule,
+    pub verification: ThrottleRule,
+    pub window: Duration,
+}
+
+impl Default for LoginThrottlePolicy {
+    fn default() -> Self {
+        Self {
+            email: ThrottleRule {
+                free_attempts: 3,
+                lockout_after: 10,
+                base_delay: Duration::seconds(1),
+                lockout: Duration::minutes(15),
+            },
+            ip: ThrottleRule {
+                free_attempts: 20,
+                lockout_after: 100,
+                base_delay: Duration::seconds(1),
+                lockout: Duration::minutes(15),
+            },
+            two_factor: ThrottleRule {
+                free_attempts: 3,
+                lockout_after: 5,
+                base_delay: Duration::seconds(5),
+                lockout: Duration::hours(1),
+            },
+            verification: ThrottleRule {
+                free_attempts: 5,
+                lockout_after: 20,
+                base_delay: Duration::seconds(30),
+                lockout: Duration::hours(1),
+            },
+            window: Duration::minutes(15),
+        }
+    }
+}
+
+impl LoginThrottlePolicy {
+    fn rule(&self, scope: ThrottleScope) -> &ThrottleRule {
+        match scope {
+            ThrottleScope::Email => &self.email,
+            ThrottleScope::Ip => &self.ip,
+            ThrottleScope::TwoFactor => &self.two_factor,
+            ThrottleScope::Verification => &self.verification,
+        }
+    }
+}
+
+pub fn email_key(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// An attempt already counted against its keys. The caller reports the
+/// outcome once the credentials have been checked.
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    counted: Vec<(ThrottleScope, String, i32)>,
+}
+
+/// Reserves the attempt before the password is verified. Nothing is counted
+/// while either the client IP or the account is blocked, so a locked account
+/// does not burn the budget of a shared IP and the other way round.
+pub async fn reserve_attempt(
+    db: &PgPool,
+    policy: &LoginThrottlePolicy,
+    email: &str,
+    ip: Option<&str>,
+) -> ApiResult<LoginAttempt> {
+    let mut keys = Vec::with_capacity(2);
+    if let Some(ip) = ip {
+        keys.push((ThrottleScope::Ip, ip.to_string()));
+    }
+    keys.push((ThrottleScope::Email, email.to_string()));
+
+    reserve(db, policy, keys).await
+}
+
+/// Reserves a second-factor attempt for the account, independent of which
+/// challenge or client it comes from.
+pub async fn reserve_two_factor_attempt(
+    db: &PgPool,
+    policy: &LoginThrottlePolicy,
+    user_id: Uuid,
+) -> ApiResult<LoginAttempt> {
+    reserve(
+        db,
+        policy,
+        vec![(ThrottleScope::TwoFactor, user_id.to_string())],
+    )
+    .await
+}
+
+/// Counts a request for a verification email from `ip`. There is no outcome
+/// to wait for, so every request counts as a failure.
+pub async fn reserve_verification_request(
+    db: &PgPool,
+    policy: &LoginThrottlePolicy,
+    ip: &str,
+) -> ApiResult<()> {
+    let attempt = reserve(
+        db,
+        policy,
+        vec![(ThrottleScope::Verification, ip.to_string())],
+    )
+    .await?;
+    attempt
+        .record_failure(db, policy)
+        .await
+        .map_err(ApiError::Db)
+}
+
+async fn reserve(
+    db: &PgPool,
+    policy: &LoginThrottlePolicy,
+    keys: Vec<(ThrottleScope, String)>,
+) -> ApiResult<LoginAttempt> {
+    // A key that is already blocked rejects the attempt before any other key
+    // is charged for it, so guesses at a locked account do not use up the
+    // budget of everyone behind the same IP.
+    let blocked_until = crate::auth::repo::login_blocked_until(db, &keys)
+        .await
+        .map_err(ApiError::Db)?;
+    if let Some(until) = blocked_until {
+        return Err(too_many_requests(Some(until)));
+    }
+
+    let mut counted: Vec<(ThrottleScope, String, i32)> = Vec::with_capacity(keys.len());
+    for (scope, key) in keys {
+        let rule = policy.rule(scope);
+        let reservation = crate::auth::repo::reserve_login_attempt(
+            db,
+            scope,
+            &key,
+            policy.window,
+            rule.lockout_after,
+            rule.lockout,
+        )
+        .await
+        .map_err(ApiError::Db)?;
+
+        if !reservation.reserved {
+            // Blocked since the check above: give the other keys their
+            // attempt back.
+            for (scope, key, _) in &counted {
+                crate::auth::repo::refund_login_attempt(db, *scope, key)
+                    .await
+                    .map_err(ApiError::Db)?;
+            }
+            return Err(too_many_requests(reservation.blocked_until));
+        }
+        counted.push((scope, key, reservation.failures));
+    }
+
+    Ok(LoginAttempt { counted })
+}
+
+fn too_many_requests(blocked_until: Option<OffsetDateTime>) -> ApiError {
+    let remaining = blocked_until.map_or(Duration::ZERO, |until| until - OffsetDateTime::now_utc());
+    ApiError::TooManyRequests {
+        retry_after_secs: remaining.whole_seconds().max(0) as u64 + 1,
+    }
+}
+
+/// Forgets the failures of an account once it has fully signed in.
+pub async fn clear_email(db: &PgPool, email: &str) -> Result<(), sqlx::Error> {
+    crate::auth::repo::clear_login_failures(db, ThrottleScope::Email, email).await
+}
+
+impl LoginAttempt {
+    /// Applies the progressive delay for the failure that was counted up front.
+    pub async fn record_failure(
+        &self,
+        db: &PgPool,
+        policy: &LoginThrottlePolicy,
+    ) -> Result<(), sqlx::Error> {
+        for (scope, key, failures) in &self.counted {
+            if let Some(delay) = policy.rule(*scope).delay_for(*failures) {
+                crate::auth::repo::block_login(db, *scope, key, delay).await?;
+                if *failures >= policy.rule(*scope).lockout_after {
+                    tracing::warn!(scope = scope.as_str(), failures, "login locked out");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clears the account's counters and gives the IP its attempt back. With
+    /// `keep_email` the email counter survives until the second factor passes.
+    pub async fn record_success(&self, db: &PgPool, keep_email: bool) -> Result<(), sqlx::Error> {
+        for (scope, key, _) in &self.counted {
+            match scope {
+                ThrottleScope::Email if keep_email => {}
+                ThrottleScope::Email | ThrottleScope::TwoFactor | ThrottleScope::Verification => {
+                    crate::auth::repo::clear_login_failures(db, *scope, key).await?
+                }
+                ThrottleScope::Ip => {
+                    crate::auth::repo::refund_login_attempt(db, *scope, key).await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
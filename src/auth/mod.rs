@@ -1 +1,12 @@
+pub mod email_change;
 pub mod http;
+pub mod login_throttle;
+pub mod password_reset;
+pub(crate) mod passwords;
+pub mod repo;
+pub mod security_events;
+pub mod session_cache;
+pub(crate) mod tokens;
+pub mod totp;
+pub mod two_factor;
+pub mod verification;
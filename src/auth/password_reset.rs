@@ -0,0 +1,61 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    auth::tokens::{hash_token, new_opaque_token},
+    notifications::repo::EmailKind,
+};
+
+pub const TOKEN_TTL: Duration = Duration::hours(1);
+
+pub const RESEND_INTERVAL: Duration = Duration::minutes(1);
+
+/// Looks the account up and queues its reset email off the request path, so
+/// the response takes the same time and status whether or not `email` has an
+/// account.
+pub fn spawn_password_reset(db: PgPool, email: String) {
+    tokio::spawn(async move {
+        let result = async {
+            match crate::auth::repo::find_user_id_by_email(&db, email.trim()).await? {
+                Some(user_id) => send_password_reset_email(&db, user_id).await,
+                None => Ok(false),
+            }
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::error!("failed to queue password reset email: {e}");
+        }
+    });
+}
+
+pub async fn send_password_reset_email(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let token = new_opaque_token();
+    let now = OffsetDateTime::now_utc();
+
+    let issued = crate::auth::repo::create_password_reset_token(
+        db,
+        user_id,
+        &hash_token(&token),
+        now + TOKEN_TTL,
+        now - RESEND_INTERVAL,
+    )
+    .await?;
+    if !issued {
+        return Ok(false);
+    }
+
+    let body = format!(
+        "Use this token to reset your password:\n\n{token}\n\nIt expires in {} minutes. \
+         If you did not ask for a reset, ignore this email.\n",
+        TOKEN_TTL.whole_minutes()
+    );
+    crate::notifications::repo::enqueue_email(
+        db,
+        user_id,
+        EmailKind::PasswordReset,
+        "Reset your password",
+        &body,
+    )
+    .await
+}
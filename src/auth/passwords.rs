@@ -0,0 +1,34 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordVerifier,
+    password_hash::{Error as PHError, PasswordHasher, SaltString, rand_core::OsRng},
+};
+
+use crate::error::{ApiError, ApiResult};
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+pub(crate) fn validate_new_password(password: &str) -> ApiResult<()> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "password must be at least {MIN_PASSWORD_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn hash_password(password: &str) -> ApiResult<String> {
+    let salt_string = SaltString::generate(OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt_string)
+        .map_err(|error| ApiError::Hash(error.to_string()))?
+        .to_string())
+}
+
+pub(crate) fn verify_password(password: &str, password_hash: &str) -> ApiResult<()> {
+    let parsed = PasswordHash::new(password_hash).map_err(|e| ApiError::Hash(e.to_string()))?;
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(()),
+        Err(PHError::Password) => Err(ApiError::Unauthorized),
+        Err(e) => Err(ApiError::Hash(e.to_string())),
+    }
+}
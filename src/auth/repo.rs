@@ -0,0 +1,904 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::auth::{login_throttle::ThrottleScope, security_events::SecurityEventKind};
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+pub async fn find_user_id_by_email(db: &PgPool, email: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM users
+        WHERE email = $1::text::citext
+        "#,
+        email
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn is_email_verified(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let verified = sqlx::query_scalar!(
+        r#"
+        SELECT email_verified_at IS NOT NULL AS "verified!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(verified.unwrap_or(false))
+}
+
+pub async fn create_email_verification_token(
+    db: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: OffsetDateTime,
+    not_issued_since: OffsetDateTime,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at)
+        SELECT u.id, u.email, $2, $3
+        FROM users AS u
+        WHERE u.id = $1
+          AND u.email_verified_at IS NULL
+          AND NOT EXISTS (
+              SELECT 1
+              FROM email_verification_tokens AS t
+              WHERE t.user_id = u.id AND t.created_at > $4
+          )
+        "#,
+        user_id,
+        token_hash,
+        expires_at,
+        not_issued_since
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn verify_email(db: &PgPool, token_hash: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        WITH used AS (
+            UPDATE email_verification_tokens AS t
+            SET used_at = now()
+            FROM users AS u
+            WHERE t.token_hash = $1
+              AND t.used_at IS NULL
+              AND t.expires_at > now()
+              AND u.id = t.user_id
+              AND u.email = t.email
+            RETURNING t.user_id
+        )
+        UPDATE users
+        SET email_verified_at = COALESCE(email_verified_at, now())
+        FROM used
+        WHERE users.id = used.user_id
+        RETURNING users.id
+        "#,
+        token_hash
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn create_password_reset_token(
+    db: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: OffsetDateTime,
+    not_issued_since: OffsetDateTime,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+        SELECT $1, $2, $3
+        WHERE NOT EXISTS (
+            SELECT 1
+            FROM password_reset_tokens
+            WHERE user_id = $1 AND created_at > $4
+        )
+        "#,
+        user_id,
+        token_hash,
+        expires_at,
+        not_issued_since
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn reset_password(
+    db: &PgPool,
+    token_hash: &str,
+    password_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let user_id = sqlx::query_scalar!(
+        r#"
+        UPDATE password_reset_tokens
+        SET used_at = now()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+        RETURNING user_id
+        "#,
+        token_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password_hash = $2
+        WHERE id = $1
+        "#,
+        user_id,
+        password_hash
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE password_reset_tokens
+        SET used_at = now()
+        WHERE user_id = $1 AND used_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(user_id))
+}
+
+pub async fn is_account_disabled(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let disabled = sqlx::query_scalar!(
+        r#"
+        SELECT disabled_at IS NOT NULL AS "disabled!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(disabled.unwrap_or(true))
+}
+
+pub async fn get_password_hash(db: &PgPool, user_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT password_hash
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn get_user_email(db: &PgPool, user_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT email::text AS "email!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn change_password(
+    db: &PgPool,
+    user_id: Uuid,
+    password_hash: &str,
+    refresh_token_hash: &str,
+    refresh_expires_at: OffsetDateTime,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+) -> Result<Uuid, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password_hash = $2
+        WHERE id = $1
+        "#,
+        user_id,
+        password_hash
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE password_reset_tokens
+        SET used_at = now()
+        WHERE user_id = $1 AND used_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let family_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at, user_agent, ip_address)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING family_id
+        "#,
+        user_id,
+        refresh_token_hash,
+        refresh_expires_at,
+        user_agent,
+        ip_address
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(family_id)
+}
+
+pub async fn create_email_change_token(
+    db: &PgPool,
+    user_id: Uuid,
+    new_email: &str,
+    token_hash: &str,
+    expires_at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE email_change_tokens
+        SET used_at = now()
+        WHERE user_id = $1 AND used_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO email_change_tokens (user_id, new_email, token_hash, expires_at)
+        VALUES ($1, $2::text, $3, $4)
+        "#,
+        user_id,
+        new_email,
+        token_hash,
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailChanged {
+    pub user_id: Uuid,
+    pub old_email: String,
+    pub new_email: String,
+}
+
+pub async fn confirm_email_change(
+    db: &PgPool,
+    token_hash: &str,
+) -> Result<Option<EmailChanged>, sqlx::Error> {
+    sqlx::query_as!(
+        EmailChanged,
+        r#"
+        WITH used AS (
+            UPDATE email_change_tokens
+            SET used_at = now()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+            RETURNING user_id, new_email
+        ),
+        previous AS (
+            SELECT u.id, u.email::text AS email
+            FROM users u
+            JOIN used ON used.user_id = u.id
+        )
+        UPDATE users
+        SET email = used.new_email,
+            email_verified_at = now()
+        FROM used, previous
+        WHERE users.id = used.user_id AND previous.id = users.id
+        RETURNING users.id AS user_id, previous.email AS "old_email!", users.email::text AS "new_email!"
+        "#,
+        token_hash
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn list_sessions(db: &PgPool, user_id: Uuid) -> Result<Vec<Session>, sqlx::Error> {
+    sqlx::query_as!(
+        Session,
+        r#"
+        SELECT id, user_agent, ip_address, created_at, last_used_at, expires_at
+        FROM refresh_tokens
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > now()
+        ORDER BY last_used_at DESC, id
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn revoke_session(db: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > now()
+        "#,
+        id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn revoke_all_sessions(db: &PgPool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn revoke_token_family(db: &PgPool, family_id: Uuid) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE family_id = $1 AND revoked_at IS NULL
+        "#,
+        family_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn record_security_event(
+    db: &PgPool,
+    user_id: Uuid,
+    kind: SecurityEventKind,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+    details: Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO security_events (user_id, kind, user_agent, ip_address, details)
+        VALUES ($1, $2::text, $3, $4, $5)
+        "#,
+        user_id,
+        kind.as_str(),
+        user_agent,
+        ip_address,
+        details
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn is_session_active(
+    db: &PgPool,
+    user_id: Uuid,
+    family_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM refresh_tokens
+            WHERE family_id = $1
+              AND user_id = $2
+              AND revoked_at IS NULL
+              AND expires_at > now()
+        ) AS "active!"
+        "#,
+        family_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoginReservation {
+    /// `false` when the key was already blocked and the attempt was not
+    /// counted.
+    pub reserved: bool,
+    pub failures: i32,
+    pub blocked_until: Option<OffsetDateTime>,
+}
+
+/// Counts an attempt before the credentials are checked. A key that reaches
+/// `lockout_after` is locked by the same statement, so concurrent attempts
+/// cannot all pass the limit.
+pub async fn reserve_login_attempt(
+    db: &PgPool,
+    scope: ThrottleScope,
+    key: &str,
+    window: Duration,
+    lockout_after: i32,
+    lockout: Duration,
+) -> Result<LoginReservation, sqlx::Error> {
+    sqlx::query_as!(
+        LoginReservation,
+        r#"
+        INSERT INTO login_throttles (scope, key, failures, last_failed_at, blocked_until)
+        VALUES (
+            $1::text,
+            $2,
+            1,
+            now(),
+            CASE WHEN 1 >= $4 THEN now() + make_interval(secs => $5) END
+        )
+        ON CONFLICT (scope, key)
+        DO UPDATE SET
+            failures = CASE
+                WHEN login_throttles.blocked_until > now() THEN login_throttles.failures
+                WHEN login_throttles.last_failed_at < now() - make_interval(secs => $3) THEN 1
+                ELSE login_throttles.failures + 1
+            END,
+            last_failed_at = CASE
+                WHEN login_throttles.blocked_until > now() THEN login_throttles.last_failed_at
+                ELSE now()
+            END,
+            blocked_until = CASE
+                WHEN login_throttles.blocked_until > now() THEN login_throttles.blocked_until
+                WHEN CASE
+                    WHEN login_throttles.last_failed_at < now() - make_interval(secs => $3) THEN 1
+                    ELSE login_throttles.failures + 1
+                END >= $4 THEN now() + make_interval(secs => $5)
+                ELSE login_throttles.blocked_until
+            END
+        RETURNING
+            last_failed_at = now() AS "reserved!",
+            failures,
+            blocked_until
+        "#,
+        scope.as_str(),
+        key,
+        window.whole_seconds() as f64,
+        lockout_after,
+        lockout.as_seconds_f64()
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// The latest `blocked_until` among `keys` that are blocked right now.
+pub async fn login_blocked_until(
+    db: &PgPool,
+    keys: &[(ThrottleScope, String)],
+) -> Result<Option<OffsetDateTime>, sqlx::Error> {
+    let scopes: Vec<&str> = keys.iter().map(|(scope, _)| scope.as_str()).collect();
+    let keys: Vec<&str> = keys.iter().map(|(_, key)| key.as_str()).collect();
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT MAX(t.blocked_until)
+        FROM login_throttles AS t
+        JOIN UNNEST($1::text[], $2::text[]) AS k(scope, key)
+            ON k.scope = t.scope AND k.key = t.key
+        WHERE t.blocked_until > now()
+        "#,
+        &scopes as &[&str],
+        &keys as &[&str]
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn refund_login_attempt(
+    db: &PgPool,
+    scope: ThrottleScope,
+    key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE login_throttles
+        SET failures = GREATEST(failures - 1, 0)
+        WHERE scope = $1::text AND key = $2
+        "#,
+        scope.as_str(),
+        key
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn block_login(
+    db: &PgPool,
+    scope: ThrottleScope,
+    key: &str,
+    delay: Duration,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE login_throttles
+        SET blocked_until = GREATEST(blocked_until, now() + make_interval(secs => $3))
+        WHERE scope = $1::text AND key = $2
+        "#,
+        scope.as_str(),
+        key,
+        delay.as_seconds_f64()
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn clear_login_failures(
+    db: &PgPool,
+    scope: ThrottleScope,
+    key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM login_throttles
+        WHERE scope = $1::text AND key = $2
+        "#,
+        scope.as_str(),
+        key
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn prune_login_throttles(
+    db: &PgPool,
+    cutoff: OffsetDateTime,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM login_throttles
+        WHERE last_failed_at < $1
+          AND (blocked_until IS NULL OR blocked_until < now())
+        "#,
+        cutoff
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+#[derive(Debug, Clone)]
+pub struct UserTotp {
+    pub secret: String,
+    pub confirmed_at: Option<OffsetDateTime>,
+}
+
+pub async fn start_totp_enrollment(
+    db: &PgPool,
+    user_id: Uuid,
+    secret: &str,
+    encryption_key: &str,
+    recovery_code_hashes: &[String],
+) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let res = sqlx::query!(
+        r#"
+        INSERT INTO user_totp (user_id, secret_encrypted)
+        VALUES ($1, pgp_sym_encrypt($2, $3, 'cipher-algo=aes256,compress-algo=1'))
+        ON CONFLICT (user_id)
+        DO UPDATE SET
+            secret_encrypted = EXCLUDED.secret_encrypted,
+            last_used_step = NULL,
+            created_at = now()
+        WHERE user_totp.confirmed_at IS NULL
+        "#,
+        user_id,
+        secret,
+        encryption_key
+    )
+    .execute(&mut *tx)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        DELETE FROM totp_recovery_codes
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO totp_recovery_codes (user_id, code_hash)
+        SELECT $1, code_hash
+        FROM UNNEST($2::text[]) AS code_hash
+        "#,
+        user_id,
+        recovery_code_hashes
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn get_totp(
+    db: &PgPool,
+    user_id: Uuid,
+    encryption_key: &str,
+) -> Result<Option<UserTotp>, sqlx::Error> {
+    sqlx::query_as!(
+        UserTotp,
+        r#"
+        SELECT
+            pgp_sym_decrypt(secret_encrypted, $2) AS "secret!",
+            confirmed_at
+        FROM user_totp
+        WHERE user_id = $1
+        "#,
+        user_id,
+        encryption_key
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn is_totp_enabled(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM user_totp
+            WHERE user_id = $1 AND confirmed_at IS NOT NULL
+        ) AS "enabled!"
+        "#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Records `step` as the latest accepted code. Fails when that step, or a
+/// later one, was already used, so a code can't be replayed.
+pub async fn use_totp_step(db: &PgPool, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE user_totp
+        SET last_used_step = $2
+        WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+        user_id,
+        step
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn confirm_totp(db: &PgPool, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE user_totp
+        SET confirmed_at = now(), last_used_step = $2
+        WHERE user_id = $1 AND confirmed_at IS NULL
+        "#,
+        user_id,
+        step
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn use_recovery_code(
+    db: &PgPool,
+    user_id: Uuid,
+    code_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE totp_recovery_codes
+        SET used_at = now()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+        user_id,
+        code_hash
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn disable_totp(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM user_totp
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM totp_recovery_codes
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn create_login_challenge(
+    db: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO login_challenges (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+        user_id,
+        token_hash,
+        expires_at
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Spends one attempt of a challenge that is unused, unexpired and has
+/// attempts left, returning the challenge id and its user. The attempt is
+/// taken before the code is checked so parallel guesses cannot share one.
+pub async fn claim_login_challenge_attempt(
+    db: &PgPool,
+    token_hash: &str,
+    max_attempts: i32,
+) -> Result<Option<(Uuid, Uuid)>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE login_challenges
+        SET attempts = attempts + 1
+        WHERE token_hash = $1
+          AND used_at IS NULL
+          AND expires_at > now()
+          AND attempts < $2
+        RETURNING id, user_id
+        "#,
+        token_hash,
+        max_attempts
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| (row.id, row.user_id)))
+}
+
+pub async fn consume_login_challenge(db: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE login_challenges
+        SET used_at = now()
+        WHERE id = $1 AND used_at IS NULL
+        "#,
+        id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn prune_login_challenges(
+    db: &PgPool,
+    cutoff: OffsetDateTime,
+) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM login_challenges
+        WHERE expires_at < $1
+        "#,
+        cutoff
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,72 @@
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::notifications::repo::EmailKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityEventKind {
+    RefreshTokenReuse,
+}
+
+impl SecurityEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SecurityEventKind::RefreshTokenReuse => "refresh_token_reuse",
+        }
+    }
+}
+
+/// A rotated refresh token was presented again, so whoever holds the family
+/// may be an attacker: end every session of that login and tell the user.
+pub async fn handle_refresh_token_reuse(
+    db: &PgPool,
+    user_id: Uuid,
+    family_id: Uuid,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let revoked = crate::auth::repo::revoke_token_family(db, family_id).await?;
+
+    crate::auth::repo::record_security_event(
+        db,
+        user_id,
+        SecurityEventKind::RefreshTokenReuse,
+        user_agent,
+        ip_address,
+        json!({ "family_id": family_id, "revoked_sessions": revoked }),
+    )
+    .await?;
+    tracing::warn!(
+        %user_id,
+        %family_id,
+        revoked,
+        "refresh token reuse detected, token family revoked"
+    );
+
+    // Replaying a token from an already revoked family changes nothing, so
+    // only the first detection is worth an email.
+    if revoked == 0 {
+        return Ok(());
+    }
+
+    let body = format!(
+        "A sign-in token for your account was used again after it had already been replaced.\n\
+         This usually means the token was copied from one of your devices.\n\n\
+         IP address: {}\nUser agent: {}\n\n\
+         As a precaution we signed out {revoked} session(s) from that login. \
+         If this was not you, change your password.\n",
+        ip_address.unwrap_or("unknown"),
+        user_agent.unwrap_or("unknown"),
+    );
+    crate::notifications::repo::enqueue_email(
+        db,
+        user_id,
+        EmailKind::SecurityAlert,
+        "Suspicious sign-in activity",
+        &body,
+    )
+    .await?;
+
+    Ok(())
+}
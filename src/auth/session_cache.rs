@@ -0,0 +1,92 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const SESSION_CACHE_TTL: Duration = Duration::from_secs(30);
+
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct CachedSession {
+    user_id: Uuid,
+    active: bool,
+    checked_at: Instant,
+}
+
+/// Remembers whether access-token sessions are still alive so `AuthUser`
+/// does not query `refresh_tokens` on every request. Revocations made by this
+/// process evict the user's entries right away; other replicas notice within
+/// the TTL.
+#[derive(Debug, Clone)]
+pub struct SessionCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<Uuid, CachedSession>>>,
+}
+
+impl SessionCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn is_active(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        if let Some(active) = self.get(user_id, session_id) {
+            return Ok(active);
+        }
+
+        let active = crate::auth::repo::is_session_active(db, user_id, session_id).await?;
+        self.insert(user_id, session_id, active);
+        Ok(active)
+    }
+
+    pub fn forget_user(&self, user_id: Uuid) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.user_id != user_id);
+    }
+
+    fn get(&self, user_id: Uuid, session_id: Uuid) -> Option<bool> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&session_id)
+            .filter(|entry| entry.user_id == user_id && entry.checked_at.elapsed() < self.ttl)
+            .map(|entry| entry.active)
+    }
+
+    fn insert(&self, user_id: Uuid, session_id: Uuid, active: bool) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| entry.checked_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(
+            session_id,
+            CachedSession {
+                user_id,
+                active,
+                checked_at: Instant::now(),
+            },
+        );
+    }
+}
+
+impl Default for SessionCache {
+    fn default() -> Self {
+        Self::new(SESSION_CACHE_TTL)
+    }
+}
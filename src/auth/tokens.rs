@@ -0,0 +1,48 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use jsonwebtoken::{Header, encode};
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    AppState, Claims,
+    error::{ApiError, ApiResult},
+};
+
+pub const ACCESS_TOKEN_TTL: Duration = Duration::minutes(15);
+
+pub const REFRESH_TOKEN_TTL: Duration = Duration::days(30);
+
+/// A client that retries a refresh (a lost response, two tabs racing) presents
+/// the token it just rotated. Within this window, and while the new token is
+/// still unused, that is not treated as theft.
+pub const REFRESH_REUSE_GRACE: Duration = Duration::seconds(30);
+
+pub(crate) fn new_opaque_token() -> String {
+    let mut bytes = [0_u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    hex::encode(digest)
+}
+
+pub(crate) fn encode_access_token(
+    state: &AppState,
+    user_id: Uuid,
+    session_id: Uuid,
+    ttl: Duration,
+) -> ApiResult<String> {
+    let iat = OffsetDateTime::now_utc().unix_timestamp();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        sid: session_id,
+        iat,
+        exp: iat + ttl.whole_seconds(),
+    };
+
+    encode(&Header::default(), &claims, &state.jwt_enc).map_err(|e| ApiError::Hash(e.to_string()))
+}
@@ -0,0 +1,49 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::auth::{tokens::hash_token, totp};
+
+pub const CHALLENGE_TTL: Duration = Duration::minutes(5);
+
+pub const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    Totp,
+    RecoveryCode,
+}
+
+/// Accepts either a current TOTP code or an unused recovery code for a user
+/// with confirmed 2FA. Each TOTP step and each recovery code works once.
+pub async fn verify_second_factor(
+    db: &PgPool,
+    encryption_key: &str,
+    user_id: Uuid,
+    code: &str,
+) -> Result<Option<SecondFactor>, sqlx::Error> {
+    let Some(enrollment) = crate::auth::repo::get_totp(db, user_id, encryption_key).await? else {
+        return Ok(None);
+    };
+    if enrollment.confirmed_at.is_none() {
+        return Ok(None);
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    if let Some(step) = totp::verify_code(&enrollment.secret, code, now) {
+        let fresh = crate::auth::repo::use_totp_step(db, user_id, step).await?;
+        return Ok(fresh.then_some(SecondFactor::Totp));
+    }
+
+    let recovery_code = totp::normalize_recovery_code(code);
+    if recovery_code.is_empty() {
+        return Ok(None);
+    }
+    let used =
+        crate::auth::repo::use_recovery_code(db, user_id, &hash_token(&recovery_code)).await?;
+    if used {
+        tracing::info!(%user_id, "2fa recovery code used");
+    }
+
+    Ok(used.then_some(SecondFactor::RecoveryCode))
+}
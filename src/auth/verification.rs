@@ -0,0 +1,124 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::tokens::{hash_token, new_opaque_token},
+    error::{ApiError, ApiResult},
+    notifications::repo::EmailKind,
+};
+
+pub const TOKEN_TTL: Duration = Duration::hours(24);
+
+pub const RESEND_INTERVAL: Duration = Duration::minutes(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmailVerificationPolicy {
+    #[default]
+    Off,
+    Login,
+    Crawl,
+}
+
+impl EmailVerificationPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(EmailVerificationPolicy::Off),
+            "login" => Some(EmailVerificationPolicy::Login),
+            "crawl" => Some(EmailVerificationPolicy::Crawl),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("REQUIRE_EMAIL_VERIFICATION") {
+            Ok(value) => Self::parse(&value).ok_or(format!(
+                "REQUIRE_EMAIL_VERIFICATION must be one of off, login, crawl, got {value}"
+            )),
+            Err(_) => Ok(EmailVerificationPolicy::Off),
+        }
+    }
+
+    pub fn blocks_login(self) -> bool {
+        self == EmailVerificationPolicy::Login
+    }
+
+    pub fn blocks_crawl(self) -> bool {
+        self != EmailVerificationPolicy::Off
+    }
+
+    /// Without a mailer nobody receives a verification token, so a policy that
+    /// blocks anything would lock every new account out for good.
+    pub fn check_mailer(self, mailer_configured: bool) -> Result<(), String> {
+        if self != EmailVerificationPolicy::Off && !mailer_configured {
+            return Err(
+                "REQUIRE_EMAIL_VERIFICATION needs SMTP_HOST so verification emails can be sent"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Looks the account up and queues a new verification email off the request
+/// path, so the response does not tell whether `email` has an account.
+pub fn spawn_verification_email(db: PgPool, email: String) {
+    tokio::spawn(async move {
+        let result = async {
+            match crate::auth::repo::find_user_id_by_email(&db, email.trim()).await? {
+                Some(user_id) => send_verification_email(&db, user_id).await,
+                None => Ok(false),
+            }
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::error!("failed to queue verification email: {e}");
+        }
+    });
+}
+
+pub async fn send_verification_email(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let token = new_opaque_token();
+    let now = OffsetDateTime::now_utc();
+
+    let issued = crate::auth::repo::create_email_verification_token(
+        db,
+        user_id,
+        &hash_token(&token),
+        now + TOKEN_TTL,
+        now - RESEND_INTERVAL,
+    )
+    .await?;
+    if !issued {
+        return Ok(false);
+    }
+
+    let body = format!(
+        "Use this token to verify your email address:\n\n{token}\n\nIt expires in {} hours.\n",
+        TOKEN_TTL.whole_hours()
+    );
+    crate::notifications::repo::enqueue_email(
+        db,
+        user_id,
+        EmailKind::Verification,
+        "Verify your email address",
+        &body,
+    )
+    .await
+}
+
+pub async fn ensure_can_crawl(state: &AppState, user_id: Uuid) -> ApiResult<()> {
+    if !state.email_verification.blocks_crawl() {
+        return Ok(());
+    }
+
+    let verified = crate::auth::repo::is_email_verified(&state.db, user_id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !verified {
+        return Err(ApiError::EmailNotVerified);
+    }
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use axum::{Json, extract::State, http::StatusCode};
 use serde::Serialize;
+use time::OffsetDateTime;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -13,6 +14,8 @@ use crate::{
 pub struct MeResponse {
     id: Uuid,
     email: String,
+    email_verified_at: Option<OffsetDateTime>,
+    role: String,
 }
 
 #[utoipa::path(
@@ -69,10 +72,13 @@ pub async fn me(
     user: AuthUser,
     State(state): State<AppState>,
 ) -> ApiResult<(StatusCode, Json<MeResponse>)> {
-    let row = sqlx::query!(r#"SELECT id, email FROM users WHERE id = $1"#, user.id)
-        .fetch_optional(&state.db)
-        .await
-        .map_err(ApiError::Db)?;
+    let row = sqlx::query!(
+        r#"SELECT id, email, email_verified_at, role FROM users WHERE id = $1"#,
+        user.id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::Db)?;
 
     let row = row.ok_or(ApiError::Unauthorized)?;
 
@@ -81,6 +87,8 @@ pub async fn me(
         Json(MeResponse {
             id: row.id,
             email: row.email,
+            email_verified_at: row.email_verified_at,
+            role: row.role,
         }),
     ))
 }
@@ -1,4 +1,8 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
 use serde::Serialize;
 use utoipa::ToSchema;
 
@@ -11,11 +15,17 @@ pub struct ErrorBody {
 #[derive(Debug)]
 pub enum ApiError {
     EmailTaken,
+    EmailNotVerified,
+    AccountDisabled,
     BadRequest(String),
+    Forbidden(String),
+    Conflict(String),
     Db(sqlx::Error),
     Hash(String),
+    Internal(String),
     Unauthorized,
     NotFound,
+    TooManyRequests { retry_after_secs: u64 },
 }
 
 pub type ApiResult<T> = Result<T, ApiError>;
@@ -33,6 +43,22 @@ impl IntoResponse for ApiError {
                 *res.status_mut() = StatusCode::CONFLICT;
                 res
             }
+            ApiError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                Json(ErrorBody {
+                    error: "EMAIL_NOT_VERIFIED",
+                    message: "Email address is not verified".to_string(),
+                }),
+            )
+                .into_response(),
+            ApiError::AccountDisabled => (
+                StatusCode::FORBIDDEN,
+                Json(ErrorBody {
+                    error: "ACCOUNT_DISABLED",
+                    message: "Account is disabled".to_string(),
+                }),
+            )
+                .into_response(),
             ApiError::BadRequest(msg) => (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorBody {
@@ -41,6 +67,22 @@ impl IntoResponse for ApiError {
                 }),
             )
                 .into_response(),
+            ApiError::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                Json(ErrorBody {
+                    error: "FORBIDDEN",
+                    message: msg,
+                }),
+            )
+                .into_response(),
+            ApiError::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                Json(ErrorBody {
+                    error: "CONFLICT",
+                    message: msg,
+                }),
+            )
+                .into_response(),
             ApiError::Db(e) => {
                 tracing::error!("db error: {e}");
                 (
@@ -63,6 +105,17 @@ impl IntoResponse for ApiError {
                 )
                     .into_response()
             }
+            ApiError::Internal(msg) => {
+                tracing::error!("internal error: {msg}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorBody {
+                        error: "INTERNAL",
+                        message: "Internal server error".to_string(),
+                    }),
+                )
+                    .into_response()
+            }
             ApiError::Unauthorized => (
                 StatusCode::UNAUTHORIZED,
                 Json(ErrorBody {
@@ -79,6 +132,15 @@ impl IntoResponse for ApiError {
                 }),
             )
                 .into_response(),
+            ApiError::TooManyRequests { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(ErrorBody {
+                    error: "TOO_MANY_REQUESTS",
+                    message: format!("Too many attempts, retry in {retry_after_secs} seconds"),
+                }),
+            )
+                .into_response(),
         }
     }
 }
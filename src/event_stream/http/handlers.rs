@@ -0,0 +1,150 @@
+use std::{collections::VecDeque, convert::Infallible, time::Duration};
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::Stream;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    event_stream::repo::{EventPosition, StreamEvent},
+    extractors::scoped_user::{ScopedUser, scope},
+};
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+/// How often a stream looks again while committed events wait on an older,
+/// still running transaction, which may never send a notification itself.
+const HELD_BACK_RETRY: Duration = Duration::from_millis(200);
+/// Safety net for notifications lost while the listener reconnects.
+const IDLE_RECHECK: Duration = Duration::from_secs(30);
+const BATCH_SIZE: i64 = 100;
+
+struct StreamCursor {
+    db: PgPool,
+    user_id: Uuid,
+    position: EventPosition,
+    notifications: broadcast::Receiver<Option<Uuid>>,
+    pending: VecDeque<StreamEvent>,
+    caught_up: bool,
+    held_back: bool,
+}
+
+impl StreamCursor {
+    async fn wait_for_events(&mut self) {
+        if self.held_back {
+            tokio::time::sleep(HELD_BACK_RETRY).await;
+            return;
+        }
+
+        let _ = tokio::time::timeout(IDLE_RECHECK, async {
+            loop {
+                match self.notifications.recv().await {
+                    Ok(Some(user_id)) if user_id != self.user_id => continue,
+                    Err(broadcast::error::RecvError::Closed) => std::future::pending::<()>().await,
+                    _ => return,
+                }
+            }
+        })
+        .await;
+    }
+}
+
+fn sse_event(event: StreamEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.event_type)
+        .data(event.data.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/stream",
+    params(
+        ("Last-Event-ID" = Option<i64>, Header, description = "Resume after this event id")
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of new finds and crawl jobs", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid Last-Event-ID", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Events"
+)]
+pub async fn stream_events(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let notifications = state.stream_notifier.subscribe(&state.db);
+    let position = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => {
+            let last_id = value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|id| *id >= 0)
+                .ok_or(ApiError::BadRequest(
+                    "Last-Event-ID must be a non-negative integer".to_string(),
+                ))?;
+            crate::event_stream::repo::position_of(&state.db, user.id, last_id)
+                .await
+                .map_err(ApiError::Db)?
+        }
+        None => crate::event_stream::repo::latest_position(&state.db, user.id)
+            .await
+            .map_err(ApiError::Db)?,
+    };
+
+    let cursor = StreamCursor {
+        db: state.db,
+        user_id: user.id,
+        position,
+        notifications,
+        pending: VecDeque::new(),
+        caught_up: false,
+        held_back: false,
+    };
+
+    let stream = futures_util::stream::unfold(cursor, |mut cursor| async move {
+        loop {
+            if let Some(event) = cursor.pending.pop_front() {
+                cursor.position = event.position();
+                return Some((Ok(sse_event(event)), cursor));
+            }
+
+            if cursor.caught_up {
+                cursor.wait_for_events().await;
+            }
+
+            match crate::event_stream::repo::list_events_after(
+                &cursor.db,
+                cursor.user_id,
+                cursor.position,
+                BATCH_SIZE,
+            )
+            .await
+            {
+                Ok(page) => {
+                    cursor.held_back = page.held_back;
+                    cursor.caught_up = page.held_back || (page.events.len() as i64) < BATCH_SIZE;
+                    cursor.pending.extend(page.events);
+                }
+                Err(e) => {
+                    tracing::error!("event stream for user {} failed: {e}", cursor.user_id);
+                    return None;
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
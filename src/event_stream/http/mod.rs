@@ -0,0 +1,11 @@
+use axum::{Router, routing::get};
+
+use crate::AppState;
+
+pub(crate) mod handlers;
+
+pub use handlers::stream_events;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/stream", get(stream_events))
+}
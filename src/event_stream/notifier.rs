@@ -0,0 +1,83 @@
+use std::{
+    sync::{Arc, Once},
+    time::Duration,
+};
+
+use sqlx::{PgPool, postgres::PgListener};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const CHANNEL: &str = "stream_events";
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const CAPACITY: usize = 1024;
+
+/// Fans the `stream_events` notifications Postgres sends on commit out to
+/// open streams, so each stream only queries when it may have something new.
+/// A single `LISTEN` connection is opened on the first subscription.
+#[derive(Debug, Clone)]
+pub struct StreamNotifier {
+    tx: broadcast::Sender<Option<Uuid>>,
+    started: Arc<Once>,
+}
+
+impl Default for StreamNotifier {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self {
+            tx,
+            started: Arc::new(Once::new()),
+        }
+    }
+}
+
+impl StreamNotifier {
+    /// Yields the id of each user whose events just committed, or `None` when
+    /// notifications may have been missed and every stream should look again.
+    pub fn subscribe(&self, db: &PgPool) -> broadcast::Receiver<Option<Uuid>> {
+        let rx = self.tx.subscribe();
+        self.started.call_once(|| {
+            tokio::spawn(listen(db.clone(), self.tx.clone()));
+        });
+        rx
+    }
+}
+
+async fn listen(db: PgPool, tx: broadcast::Sender<Option<Uuid>>) {
+    let mut closed = std::pin::pin!(db.close_event());
+
+    while !db.is_closed() {
+        let mut listener = match PgListener::connect_with(&db).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("event stream listener failed to connect: {e}");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(e) = listener.listen(CHANNEL).await {
+            tracing::error!("event stream listener failed to subscribe: {e}");
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            continue;
+        }
+        // Anything committed while we were not listening went unannounced.
+        let _ = tx.send(None);
+
+        loop {
+            tokio::select! {
+                _ = &mut closed => return,
+                received = listener.try_recv() => match received {
+                    Ok(Some(notification)) => {
+                        let _ = tx.send(notification.payload().parse().ok());
+                    }
+                    Ok(None) => {
+                        let _ = tx.send(None);
+                    }
+                    Err(e) => {
+                        tracing::error!("event stream listener failed: {e}");
+                        break;
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEventKind {
+    CrawlStarted,
+    CrawlFinished,
+}
+
+impl StreamEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamEventKind::CrawlStarted => "crawl.started",
+            StreamEventKind::CrawlFinished => "crawl.finished",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    pub id: i64,
+    pub xact_id: i64,
+    pub event_type: String,
+    pub data: Value,
+}
+
+impl StreamEvent {
+    pub fn position(&self) -> EventPosition {
+        EventPosition {
+            xact_id: self.xact_id,
+            id: self.id,
+        }
+    }
+}
+
+/// Where a stream is in a user's events. Ids are handed out before commit, so
+/// streams order by the writing transaction first and never pass a
+/// transaction that might still be running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventPosition {
+    pub xact_id: i64,
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventPage {
+    pub events: Vec<StreamEvent>,
+    /// Committed events exist after the page but are held back until every
+    /// older transaction has finished.
+    pub held_back: bool,
+}
+
+pub async fn record_event(
+    db: &PgPool,
+    user_id: Uuid,
+    kind: StreamEventKind,
+    data: Value,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO stream_events (user_id, event_type, data)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        user_id,
+        kind.as_str(),
+        data
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn latest_position(db: &PgPool, user_id: Uuid) -> Result<EventPosition, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT xact_id, id
+        FROM stream_events
+        WHERE user_id = $1
+          AND xact_id < pg_snapshot_xmin(pg_current_snapshot())::text::bigint
+        ORDER BY xact_id DESC, id DESC
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row
+        .map(|row| EventPosition {
+            xact_id: row.xact_id,
+            id: row.id,
+        })
+        .unwrap_or_default())
+}
+
+/// Position of a previously delivered event, for `Last-Event-ID`. Pruned or
+/// unknown ids resume from the oldest event still stored.
+pub async fn position_of(
+    db: &PgPool,
+    user_id: Uuid,
+    id: i64,
+) -> Result<EventPosition, sqlx::Error> {
+    let xact_id = sqlx::query_scalar!(
+        r#"
+        SELECT xact_id
+        FROM stream_events
+        WHERE user_id = $1 AND id = $2
+        "#,
+        user_id,
+        id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(EventPosition {
+        xact_id: xact_id.unwrap_or(0),
+        id,
+    })
+}
+
+pub async fn list_events_after(
+    db: &PgPool,
+    user_id: Uuid,
+    after: EventPosition,
+    limit: i64,
+) -> Result<EventPage, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            xact_id,
+            event_type,
+            data,
+            xact_id < pg_snapshot_xmin(pg_current_snapshot())::text::bigint AS "settled!"
+        FROM stream_events
+        WHERE user_id = $1 AND (xact_id, id) > ($2, $3)
+        ORDER BY xact_id, id
+        LIMIT $4
+        "#,
+        user_id,
+        after.xact_id,
+        after.id,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    let total = rows.len();
+    let events: Vec<StreamEvent> = rows
+        .into_iter()
+        .take_while(|row| row.settled)
+        .map(|row| StreamEvent {
+            id: row.id,
+            xact_id: row.xact_id,
+            event_type: row.event_type,
+            data: row.data,
+        })
+        .collect();
+
+    Ok(EventPage {
+        held_back: events.len() < total,
+        events,
+    })
+}
+
+pub async fn prune_events(db: &PgPool, older_than: OffsetDateTime) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM stream_events
+        WHERE created_at < $1
+        "#,
+        older_than
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
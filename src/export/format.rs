@@ -0,0 +1,207 @@
+use rust_xlsxwriter::{Workbook, XlsxError};
+use serde_json::{Map, Value};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" => Some(ExportFormat::Ndjson),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportValue<'a> {
+    Null,
+    Int(i64),
+    Bool(bool),
+    Text(&'a str),
+    Time(OffsetDateTime),
+}
+
+impl<'a> From<Option<&'a str>> for ExportValue<'a> {
+    fn from(value: Option<&'a str>) -> Self {
+        value.map_or(ExportValue::Null, ExportValue::Text)
+    }
+}
+
+impl From<Option<i64>> for ExportValue<'_> {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(ExportValue::Null, ExportValue::Int)
+    }
+}
+
+impl From<Option<bool>> for ExportValue<'_> {
+    fn from(value: Option<bool>) -> Self {
+        value.map_or(ExportValue::Null, ExportValue::Bool)
+    }
+}
+
+impl From<Option<OffsetDateTime>> for ExportValue<'_> {
+    fn from(value: Option<OffsetDateTime>) -> Self {
+        value.map_or(ExportValue::Null, ExportValue::Time)
+    }
+}
+
+impl ExportValue<'_> {
+    fn to_text(self) -> String {
+        match self {
+            ExportValue::Null => String::new(),
+            ExportValue::Int(value) => value.to_string(),
+            ExportValue::Bool(value) => value.to_string(),
+            ExportValue::Text(value) => value.to_string(),
+            ExportValue::Time(value) => format_time(value),
+        }
+    }
+
+    fn to_json(self) -> Value {
+        match self {
+            ExportValue::Null => Value::Null,
+            ExportValue::Int(value) => Value::from(value),
+            ExportValue::Bool(value) => Value::from(value),
+            ExportValue::Text(value) => Value::from(value),
+            ExportValue::Time(value) => Value::from(format_time(value)),
+        }
+    }
+}
+
+pub trait ExportRow {
+    const HEADERS: &'static [&'static str];
+
+    fn values(&self) -> Vec<ExportValue<'_>>;
+}
+
+fn format_time(value: OffsetDateTime) -> String {
+    value.format(&Rfc3339).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line<I>(fields: I) -> String
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut line = fields
+        .into_iter()
+        .map(|field| csv_field(&field))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+pub fn csv_header<T: ExportRow>() -> String {
+    csv_line(T::HEADERS.iter().map(|header| header.to_string()))
+}
+
+pub fn csv_rows<T: ExportRow>(rows: &[T]) -> String {
+    rows.iter()
+        .map(|row| csv_line(row.values().into_iter().map(ExportValue::to_text)))
+        .collect()
+}
+
+pub fn ndjson_rows<T: ExportRow>(rows: &[T]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let object: Map<String, Value> = T::HEADERS
+            .iter()
+            .zip(row.values())
+            .map(|(header, value)| (header.to_string(), value.to_json()))
+            .collect();
+        out.push_str(&Value::Object(object).to_string());
+        out.push('\n');
+    }
+    out
+}
+
+/// Workbooks are zipped in memory once every row is written, so xlsx exports
+/// are capped; csv and ndjson stream without a limit.
+pub const MAX_XLSX_ROWS: usize = 100_000;
+
+pub struct XlsxExport {
+    workbook: Workbook,
+    next_row: u32,
+}
+
+impl XlsxExport {
+    pub fn new<T: ExportRow>() -> Result<Self, XlsxError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        for (col, header) in T::HEADERS.iter().enumerate() {
+            worksheet.write_string(0, col as u16, *header)?;
+        }
+
+        Ok(Self {
+            workbook,
+            next_row: 1,
+        })
+    }
+
+    pub fn write_rows<T: ExportRow>(&mut self, rows: &[T]) -> Result<(), XlsxError> {
+        let worksheet = self.workbook.worksheet_from_index(0)?;
+        for row in rows {
+            for (col, value) in row.values().into_iter().enumerate() {
+                let col = col as u16;
+                match value {
+                    ExportValue::Null => {}
+                    ExportValue::Int(value) => {
+                        worksheet.write_number(self.next_row, col, value as f64)?;
+                    }
+                    ExportValue::Bool(value) => {
+                        worksheet.write_boolean(self.next_row, col, value)?;
+                    }
+                    ExportValue::Text(value) => {
+                        worksheet.write_string(self.next_row, col, value)?;
+                    }
+                    ExportValue::Time(value) => {
+                        worksheet.write_string(self.next_row, col, format_time(value))?;
+                    }
+                }
+            }
+            self.next_row += 1;
+        }
+        Ok(())
+    }
+
+    pub fn rows_written(&self) -> usize {
+        self.next_row as usize - 1
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, XlsxError> {
+        self.workbook.save_to_buffer()
+    }
+}
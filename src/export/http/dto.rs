@@ -0,0 +1,23 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportVkUsersQuery {
+    pub format: Option<String>,
+    pub group_id: Option<i64>,
+    pub source_kind: Option<String>,
+    pub tags: Option<String>,
+    pub favorite: Option<bool>,
+    pub include_hidden: Option<bool>,
+    pub stage: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportActivityQuery {
+    pub format: Option<String>,
+    pub kind: Option<String>,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+}
@@ -0,0 +1,194 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures_util::StreamExt;
+use sqlx::postgres::PgRow;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    export::{
+        format::{
+            ExportFormat, ExportRow, MAX_XLSX_ROWS, XlsxExport, csv_header, csv_rows, ndjson_rows,
+        },
+        repo::{ActivityFilter, ActivityKind, ExportCursor},
+    },
+    extractors::scoped_user::{ScopedUser, scope},
+};
+
+use super::dto::{ExportActivityQuery, ExportVkUsersQuery};
+
+fn parse_format(format: Option<&str>) -> ApiResult<ExportFormat> {
+    match format {
+        None => Ok(ExportFormat::Csv),
+        Some(value) => ExportFormat::parse(value).ok_or(ApiError::BadRequest(
+            "format must be one of csv, ndjson, xlsx".to_string(),
+        )),
+    }
+}
+
+async fn export_response<T>(
+    mut cursor: ExportCursor<T>,
+    format: ExportFormat,
+    name: &str,
+) -> ApiResult<Response>
+where
+    T: ExportRow + for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    let body = match format {
+        ExportFormat::Xlsx => {
+            let mut xlsx = XlsxExport::new::<T>().map_err(|e| ApiError::Internal(e.to_string()))?;
+            loop {
+                let rows = cursor.next_batch().await.map_err(ApiError::Db)?;
+                if rows.is_empty() {
+                    break;
+                }
+                if xlsx.rows_written() + rows.len() > MAX_XLSX_ROWS {
+                    return Err(ApiError::BadRequest(format!(
+                        "xlsx exports are limited to {MAX_XLSX_ROWS} rows, \
+                         narrow the filter or use csv or ndjson"
+                    )));
+                }
+                xlsx.write_rows(&rows)
+                    .map_err(|e| ApiError::Internal(e.to_string()))?;
+            }
+            // Every row is written, so give the connection back before zipping.
+            cursor.close().await.map_err(ApiError::Db)?;
+
+            let workbook = tokio::task::spawn_blocking(move || xlsx.finish())
+                .await
+                .map_err(|e| ApiError::Internal(e.to_string()))?
+                .map_err(|e| ApiError::Internal(e.to_string()))?;
+            Body::from(workbook)
+        }
+        ExportFormat::Csv | ExportFormat::Ndjson => {
+            let header = match format {
+                ExportFormat::Csv => Some(Ok::<_, sqlx::Error>(csv_header::<T>())),
+                _ => None,
+            };
+            let rows = futures_util::stream::unfold(Some(cursor), move |cursor| async move {
+                let mut cursor = cursor?;
+                match cursor.next_batch().await {
+                    Ok(rows) if rows.is_empty() => None,
+                    Ok(rows) => {
+                        let chunk = match format {
+                            ExportFormat::Csv => csv_rows(&rows),
+                            _ => ndjson_rows(&rows),
+                        };
+                        Some((Ok(chunk), Some(cursor)))
+                    }
+                    Err(e) => {
+                        tracing::error!("export stream error: {e}");
+                        Some((Err(e), None))
+                    }
+                }
+            });
+            Body::from_stream(futures_util::stream::iter(header).chain(rows))
+        }
+    };
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{name}.{}\"", format.extension()),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/vk-users",
+    params(ExportVkUsersQuery),
+    responses(
+        (status = 200, description = "VK users export file", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 400, description = "Invalid format or filter, or too many rows for xlsx", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Export"
+)]
+pub async fn export_vk_users(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    Query(q): Query<ExportVkUsersQuery>,
+) -> ApiResult<Response> {
+    let format = parse_format(q.format.as_deref())?;
+    let filter = crate::vk_users::http::handlers::parse_filter(
+        q.group_id,
+        q.source_kind.as_deref(),
+        q.tags.as_deref(),
+        q.favorite,
+        q.include_hidden,
+        q.stage.as_deref(),
+    )?;
+
+    let cursor = crate::export::repo::open_vk_users_cursor(&state.db, user.id, &filter)
+        .await
+        .map_err(ApiError::Db)?;
+
+    export_response(cursor, format, "vk-users").await
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/activity",
+    params(ExportActivityQuery),
+    responses(
+        (status = 200, description = "Posts, comments and likes export file", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 400, description = "Invalid format or filter, or too many rows for xlsx", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Export"
+)]
+pub async fn export_activity(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    Query(q): Query<ExportActivityQuery>,
+) -> ApiResult<Response> {
+    let format = parse_format(q.format.as_deref())?;
+    let kind = q
+        .kind
+        .as_deref()
+        .map(|value| {
+            ActivityKind::parse(value).ok_or(ApiError::BadRequest(
+                "kind must be one of post, comment, post_like, comment_like".to_string(),
+            ))
+        })
+        .transpose()?;
+
+    let filter = ActivityFilter {
+        kind,
+        vk_user_id: q.vk_user_id,
+        group_id: q.group_id,
+    };
+
+    let cursor = crate::export::repo::open_activity_cursor(&state.db, user.id, &filter)
+        .await
+        .map_err(ApiError::Db)?;
+
+    export_response(cursor, format, "activity").await
+}
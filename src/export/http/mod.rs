@@ -0,0 +1,14 @@
+use axum::{Router, routing::get};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use handlers::{export_activity, export_vk_users};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/vk-users", get(export_vk_users))
+        .route("/activity", get(export_activity))
+}
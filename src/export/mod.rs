@@ -0,0 +1,3 @@
+pub mod format;
+pub mod http;
+pub mod repo;
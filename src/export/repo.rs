@@ -0,0 +1,341 @@
+use std::marker::PhantomData;
+
+use sqlx::{PgPool, Postgres, Transaction, postgres::PgRow};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    export::format::{ExportRow, ExportValue},
+    vk_user_sources::repo::VkUserSourceKind,
+    vk_users::repo::VkUsersFilter,
+};
+
+const FETCH_BATCH_SIZE: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Post,
+    Comment,
+    PostLike,
+    CommentLike,
+}
+
+impl ActivityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::Post => "post",
+            ActivityKind::Comment => "comment",
+            ActivityKind::PostLike => "post_like",
+            ActivityKind::CommentLike => "comment_like",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "post" => Some(ActivityKind::Post),
+            "comment" => Some(ActivityKind::Comment),
+            "post_like" => Some(ActivityKind::PostLike),
+            "comment_like" => Some(ActivityKind::CommentLike),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivityFilter {
+    pub kind: Option<ActivityKind>,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct VkUserExportRow {
+    pub vk_user_id: i64,
+    pub sex: Option<i16>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub first_seen_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+    pub is_closed: Option<bool>,
+    pub screen_name: Option<String>,
+    pub can_access_closed: Option<bool>,
+    pub about: Option<String>,
+    pub status: Option<String>,
+    pub bdate: Option<String>,
+    pub photo: Option<String>,
+}
+
+impl ExportRow for VkUserExportRow {
+    const HEADERS: &'static [&'static str] = &[
+        "vk_user_id",
+        "sex",
+        "first_name",
+        "last_name",
+        "city",
+        "first_seen_at",
+        "last_seen_at",
+        "is_closed",
+        "screen_name",
+        "can_access_closed",
+        "about",
+        "status",
+        "bdate",
+        "photo",
+    ];
+
+    fn values(&self) -> Vec<ExportValue<'_>> {
+        vec![
+            ExportValue::Int(self.vk_user_id),
+            self.sex.map(i64::from).into(),
+            self.first_name.as_deref().into(),
+            self.last_name.as_deref().into(),
+            self.city.as_deref().into(),
+            ExportValue::Time(self.first_seen_at),
+            ExportValue::Time(self.last_seen_at),
+            self.is_closed.into(),
+            self.screen_name.as_deref().into(),
+            self.can_access_closed.into(),
+            self.about.as_deref().into(),
+            self.status.as_deref().into(),
+            self.bdate.as_deref().into(),
+            self.photo.as_deref().into(),
+        ]
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActivityExportRow {
+    pub kind: String,
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub post_id: i64,
+    pub comment_id: Option<i64>,
+    pub occurred_at: OffsetDateTime,
+    pub text: Option<String>,
+    pub removed_at: Option<OffsetDateTime>,
+}
+
+impl ExportRow for ActivityExportRow {
+    const HEADERS: &'static [&'static str] = &[
+        "kind",
+        "vk_user_id",
+        "group_id",
+        "post_id",
+        "comment_id",
+        "occurred_at",
+        "text",
+        "removed_at",
+    ];
+
+    fn values(&self) -> Vec<ExportValue<'_>> {
+        vec![
+            ExportValue::Text(&self.kind),
+            ExportValue::Int(self.vk_user_id),
+            ExportValue::Int(self.group_id),
+            ExportValue::Int(self.post_id),
+            self.comment_id.into(),
+            ExportValue::Time(self.occurred_at),
+            self.text.as_deref().into(),
+            self.removed_at.into(),
+        ]
+    }
+}
+
+pub struct ExportCursor<T> {
+    tx: Transaction<'static, Postgres>,
+    done: bool,
+    _row: PhantomData<T>,
+}
+
+impl<T> ExportCursor<T>
+where
+    T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+{
+    pub async fn next_batch(&mut self) -> Result<Vec<T>, sqlx::Error> {
+        if self.done {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query_as::<_, T>(&format!(
+            "FETCH FORWARD {FETCH_BATCH_SIZE} FROM export_cursor"
+        ))
+        .fetch_all(&mut *self.tx)
+        .await?;
+
+        if (rows.len() as i64) < FETCH_BATCH_SIZE {
+            self.done = true;
+        }
+
+        Ok(rows)
+    }
+
+    /// Ends the snapshot and hands the connection back to the pool.
+    pub async fn close(self) -> Result<(), sqlx::Error> {
+        self.tx.rollback().await
+    }
+}
+
+pub async fn open_vk_users_cursor(
+    db: &PgPool,
+    user_id: Uuid,
+    filter: &VkUsersFilter,
+) -> Result<ExportCursor<VkUserExportRow>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        r#"
+        DECLARE export_cursor NO SCROLL CURSOR FOR
+        SELECT
+            v.vk_user_id,
+            v.sex,
+            v.first_name,
+            v.last_name,
+            v.city,
+            v.first_seen_at,
+            v.last_seen_at,
+            v.is_closed,
+            v.screen_name,
+            v.can_access_closed,
+            v.about,
+            v.status,
+            v.bdate,
+            v.photo
+        FROM vk_users AS v
+        WHERE v.user_id = $1
+          AND (
+              ($2::bigint IS NULL AND $3::text IS NULL)
+              OR EXISTS (
+                  SELECT 1
+                  FROM vk_user_sources AS s
+                  WHERE s.user_id = v.user_id
+                    AND s.vk_user_id = v.vk_user_id
+                    AND ($2::bigint IS NULL OR s.group_id = $2)
+                    AND ($3::text IS NULL OR s.source_kind = $3)
+              )
+          )
+          AND ($4 OR NOT v.is_hidden)
+          AND ($5::bool IS NULL OR v.is_favorite = $5)
+          AND (
+              cardinality($6::uuid[]) = 0
+              OR EXISTS (
+                  SELECT 1
+                  FROM vk_user_tag_assignments AS a
+                  WHERE a.user_id = v.user_id
+                    AND a.vk_user_id = v.vk_user_id
+                    AND a.tag_id = ANY($6)
+              )
+          )
+          AND ($7::uuid IS NULL OR EXISTS (
+              SELECT 1
+              FROM vk_user_stages AS st
+              WHERE st.user_id = v.user_id
+                AND st.vk_user_id = v.vk_user_id
+                AND st.stage_id = $7
+          ))
+          AND (NOT $8 OR NOT EXISTS (
+              SELECT 1
+              FROM vk_user_stages AS st
+              WHERE st.user_id = v.user_id
+                AND st.vk_user_id = v.vk_user_id
+          ))
+        ORDER BY v.last_seen_at DESC, v.vk_user_id DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(filter.group_id)
+    .bind(filter.source_kind.map(VkUserSourceKind::as_str))
+    .bind(filter.include_hidden)
+    .bind(filter.favorite)
+    .bind(&filter.tag_ids)
+    .bind(filter.stage_id)
+    .bind(filter.unstaged)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(ExportCursor {
+        tx,
+        done: false,
+        _row: PhantomData,
+    })
+}
+
+pub async fn open_activity_cursor(
+    db: &PgPool,
+    user_id: Uuid,
+    filter: &ActivityFilter,
+) -> Result<ExportCursor<ActivityExportRow>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        r#"
+        DECLARE export_cursor NO SCROLL CURSOR FOR
+        SELECT kind, vk_user_id, group_id, post_id, comment_id, occurred_at, text, removed_at
+        FROM (
+            SELECT
+                'post' AS kind,
+                from_id AS vk_user_id,
+                group_id,
+                post_id,
+                NULL::bigint AS comment_id,
+                to_timestamp(created_date) AS occurred_at,
+                post_text AS text,
+                deleted_at AS removed_at
+            FROM vk_posts
+            WHERE user_id = $1
+            UNION ALL
+            SELECT
+                'comment',
+                from_id,
+                group_id,
+                post_id,
+                comment_id,
+                to_timestamp(created_date),
+                comment_text,
+                deleted_at
+            FROM vk_comments
+            WHERE user_id = $1
+            UNION ALL
+            SELECT
+                'post_like',
+                vk_user_id,
+                group_id,
+                post_id,
+                NULL::bigint,
+                first_seen_at,
+                NULL::text,
+                removed_at
+            FROM vk_post_likes
+            WHERE user_id = $1
+            UNION ALL
+            SELECT
+                'comment_like',
+                vk_user_id,
+                group_id,
+                post_id,
+                comment_id,
+                first_seen_at,
+                NULL::text,
+                removed_at
+            FROM vk_comment_likes
+            WHERE user_id = $1
+        ) AS activity
+        WHERE ($2::text IS NULL OR kind = $2)
+          AND ($3::bigint IS NULL OR vk_user_id = $3)
+          AND ($4::bigint IS NULL OR group_id = $4)
+        ORDER BY occurred_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(filter.kind.map(ActivityKind::as_str))
+    .bind(filter.vk_user_id)
+    .bind(filter.group_id)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(ExportCursor {
+        tx,
+        done: false,
+        _row: PhantomData,
+    })
+}
@@ -0,0 +1,36 @@
+use axum::extract::FromRequestParts;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    admin::repo::UserRole,
+    error::{ApiError, ApiResult},
+    extractors::auth_user::AuthUser,
+};
+
+/// An interactive session of an account with the admin role. The role is read
+/// on every request so a demotion takes effect immediately.
+#[derive(Debug, Clone)]
+pub struct AdminUser {
+    pub id: Uuid,
+}
+
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &AppState,
+    ) -> ApiResult<Self> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        let role = crate::admin::repo::get_user_role(&state.db, user.id)
+            .await
+            .map_err(ApiError::Db)?;
+        if role != Some(UserRole::Admin) {
+            return Err(ApiError::Forbidden("admin role required".to_string()));
+        }
+
+        Ok(AdminUser { id: user.id })
+    }
+}
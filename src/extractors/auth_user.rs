@@ -3,16 +3,32 @@ use jsonwebtoken::{Algorithm, Validation, decode};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{AppState, error::ApiError};
+use crate::{
+    AppState,
+    api_keys::repo::{ApiScope, KEY_PREFIX},
+    auth::tokens::hash_token,
+    error::{ApiError, ApiResult},
+};
 
+/// An interactive session. API keys are refused here, so an endpoint is only
+/// reachable with a key when its handler asks for a
+/// [`ScopedUser`](super::scoped_user::ScopedUser) instead.
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub id: Uuid,
 }
 
+/// Who sent a request and, for API keys, what the key may do.
+pub(crate) struct Caller {
+    pub(crate) user_id: Uuid,
+    /// `None` for interactive sessions, which may use every endpoint.
+    pub(crate) api_key_scopes: Option<Vec<ApiScope>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     sub: String, // user_id
+    sid: Uuid,   // refresh token family
     iat: i64,
     exp: i64,
 }
@@ -24,20 +40,59 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut axum::http::request::Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let auth = parts
-            .headers
-            .get(header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
+        let caller = authenticate(parts, state).await?;
+        if caller.api_key_scopes.is_some() {
+            return Err(ApiError::Forbidden(
+                "this endpoint is not available to api keys".to_string(),
+            ));
+        }
+
+        Ok(AuthUser { id: caller.user_id })
+    }
+}
+
+pub(crate) async fn authenticate(
+    parts: &axum::http::request::Parts,
+    state: &AppState,
+) -> ApiResult<Caller> {
+    let auth = parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(ApiError::Unauthorized)?;
+    let token = auth.strip_prefix("Bearer ").ok_or(ApiError::Unauthorized)?;
+
+    if token.starts_with(KEY_PREFIX) {
+        let owner = crate::api_keys::repo::authenticate_api_key(&state.db, &hash_token(token))
+            .await
+            .map_err(ApiError::Db)?
             .ok_or(ApiError::Unauthorized)?;
-        let token = auth.strip_prefix("Bearer ").ok_or(ApiError::Unauthorized)?;
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.validate_exp = true;
 
-        let data = decode::<Claims>(token, &state.jwt_dec, &validation)
-            .map_err(|_| ApiError::Unauthorized)?;
+        return Ok(Caller {
+            user_id: owner.user_id,
+            api_key_scopes: Some(owner.scopes),
+        });
+    }
+
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = true;
 
-        let user_id = Uuid::parse_str(&data.claims.sub).map_err(|_| ApiError::Unauthorized)?;
+    let data =
+        decode::<Claims>(token, &state.jwt_dec, &validation).map_err(|_| ApiError::Unauthorized)?;
 
-        Ok(AuthUser { id: user_id })
+    let user_id = Uuid::parse_str(&data.claims.sub).map_err(|_| ApiError::Unauthorized)?;
+
+    let active = state
+        .sessions
+        .is_active(&state.db, user_id, data.claims.sid)
+        .await
+        .map_err(ApiError::Db)?;
+    if !active {
+        return Err(ApiError::Unauthorized);
     }
+
+    Ok(Caller {
+        user_id,
+        api_key_scopes: None,
+    })
 }
@@ -0,0 +1,53 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, header},
+};
+
+use crate::AppState;
+
+const MAX_USER_AGENT_LEN: usize = 512;
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user_agent = header_str(&parts.headers, header::USER_AGENT.as_str())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect());
+
+        let peer = ConnectInfo::<SocketAddr>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let ip = peer.map(|peer| {
+            state
+                .trusted_proxies
+                .client_ip(
+                    peer,
+                    header_str(&parts.headers, "x-forwarded-for"),
+                    header_str(&parts.headers, "x-real-ip"),
+                )
+                .to_string()
+        });
+
+        Ok(ClientInfo { user_agent, ip })
+    }
+}
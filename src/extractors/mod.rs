@@ -1 +1,4 @@
+pub mod admin_user;
 pub mod auth_user;
+pub mod client_info;
+pub mod scoped_user;
@@ -0,0 +1,79 @@
+use std::marker::PhantomData;
+
+use axum::extract::FromRequestParts;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    api_keys::repo::ApiScope,
+    error::{ApiError, ApiResult},
+    extractors::auth_user::authenticate,
+};
+
+/// A scope an endpoint asks for through [`ScopedUser`].
+pub trait RequiredScope {
+    const SCOPE: ApiScope;
+}
+
+macro_rules! scopes {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, Copy)]
+            pub struct $name;
+
+            impl super::RequiredScope for $name {
+                const SCOPE: super::ApiScope = super::ApiScope::$name;
+            }
+        )*
+    };
+}
+
+/// Marker types for [`ScopedUser`], one per [`ApiScope`].
+pub mod scope {
+    scopes!(
+        VkUsersRead,
+        VkUsersWrite,
+        GroupsRead,
+        GroupsWrite,
+        NotesRead,
+        NotesWrite,
+        SearchesRead,
+        SearchesWrite,
+        WebhooksRead,
+        WebhooksWrite,
+        Ingest,
+    );
+}
+
+/// An interactive session, or an API key that carries the `S` scope. This is
+/// the only extractor that lets API keys in.
+#[derive(Debug, Clone)]
+pub struct ScopedUser<S> {
+    pub id: Uuid,
+    scope: PhantomData<S>,
+}
+
+impl<S: RequiredScope> FromRequestParts<AppState> for ScopedUser<S> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &AppState,
+    ) -> ApiResult<Self> {
+        let caller = authenticate(parts, state).await?;
+        match &caller.api_key_scopes {
+            Some(scopes) if !scopes.contains(&S::SCOPE) => {
+                return Err(ApiError::Forbidden(format!(
+                    "api key lacks the {} scope",
+                    S::SCOPE.as_str()
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(ScopedUser {
+            id: caller.user_id,
+            scope: PhantomData,
+        })
+    }
+}
@@ -7,7 +7,7 @@ use axum::{
 use crate::{
     AppState,
     error::{ApiError, ApiResult},
-    extractors::auth_user::AuthUser,
+    extractors::scoped_user::{ScopedUser, scope},
     groups::repo::NewGroup,
 };
 
@@ -29,7 +29,7 @@ use super::dto::{CreateGroupRequest, GroupDto, GroupsQuery};
     tag = "Groups"
 )]
 pub async fn create_group(
-    user: AuthUser,
+    user: ScopedUser<scope::GroupsWrite>,
     State(state): State<AppState>,
     Json(request): Json<CreateGroupRequest>,
 ) -> ApiResult<(StatusCode, Json<GroupDto>)> {
@@ -86,7 +86,7 @@ pub async fn create_group(
     tag = "Groups"
 )]
 pub async fn list_groups(
-    user: AuthUser,
+    user: ScopedUser<scope::GroupsRead>,
     State(state): State<AppState>,
     Query(q): Query<GroupsQuery>,
 ) -> ApiResult<(StatusCode, Json<Vec<GroupDto>>)> {
@@ -133,7 +133,7 @@ pub async fn list_groups(
     tag = "Groups"
 )]
 pub async fn delete_group(
-    user: AuthUser,
+    user: ScopedUser<scope::GroupsWrite>,
     State(state): State<AppState>,
     Path(group_id): Path<i64>,
 ) -> ApiResult<StatusCode> {
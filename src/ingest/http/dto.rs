@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VkRawIngestQuery {
+    pub method: String,
+    pub group_id: Option<i64>,
+    pub post_id: Option<i64>,
+    pub comment_id: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchVkUserDto {
+    pub vk_user_id: i64,
+    pub sex: Option<i16>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub is_closed: Option<bool>,
+    pub screen_name: Option<String>,
+    pub can_access_closed: Option<bool>,
+    pub about: Option<String>,
+    pub status: Option<String>,
+    pub bdate: Option<String>,
+    pub photo: Option<String>,
+    pub source_group_id: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchVkPostDto {
+    pub post_id: i64,
+    pub group_id: i64,
+    pub from_id: i64,
+    pub created_date: i64,
+    pub post_type: Option<String>,
+    pub post_text: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchVkCommentDto {
+    pub group_id: i64,
+    pub post_id: i64,
+    pub comment_id: i64,
+    pub from_id: i64,
+    pub created_date: i64,
+    pub comment_text: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchVkPostLikeDto {
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub post_id: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchVkCommentLikeDto {
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub post_id: i64,
+    pub comment_id: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct IngestBatchRequest {
+    #[serde(default)]
+    pub users: Vec<BatchVkUserDto>,
+    #[serde(default)]
+    pub posts: Vec<BatchVkPostDto>,
+    #[serde(default)]
+    pub comments: Vec<BatchVkCommentDto>,
+    #[serde(default)]
+    pub post_likes: Vec<BatchVkPostLikeDto>,
+    #[serde(default)]
+    pub comment_likes: Vec<BatchVkCommentLikeDto>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestCountsDto {
+    pub inserted: i64,
+    pub updated: i64,
+    pub rejected: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestBatchResponse {
+    pub users: IngestCountsDto,
+    pub posts: IngestCountsDto,
+    pub comments: IngestCountsDto,
+    pub post_likes: IngestCountsDto,
+    pub comment_likes: IngestCountsDto,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkRawIngestResponse {
+    pub method: &'static str,
+    pub users: IngestCountsDto,
+    pub posts: IngestCountsDto,
+    pub comments: IngestCountsDto,
+    pub post_likes: IngestCountsDto,
+    pub comment_likes: IngestCountsDto,
+}
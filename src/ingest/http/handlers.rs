@@ -0,0 +1,346 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    event_stream::repo::StreamEventKind,
+    extractors::scoped_user::{ScopedUser, scope},
+    ingest::{
+        raw::{VkRawContext, VkRawMethod, parse_vk_raw},
+        repo::{CrawlBatch, IngestBatchResult, IngestEntityResult},
+    },
+    vk_comment_likes::repo::NewVkCommentLike,
+    vk_comments::repo::NewVkComment,
+    vk_post_likes::repo::NewVkPostLike,
+    vk_posts::repo::NewVkPost,
+    vk_users::repo::NewVkUser,
+    webhooks::repo::WebhookEventKind,
+};
+
+use super::dto::{
+    IngestBatchRequest, IngestBatchResponse, IngestCountsDto, VkRawIngestQuery, VkRawIngestResponse,
+};
+
+fn counts(res: IngestEntityResult) -> IngestCountsDto {
+    IngestCountsDto {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected: res.rejected,
+    }
+}
+
+async fn notify_crawl_started(db: &PgPool, user_id: Uuid, job_id: Uuid, source: &str) {
+    if let Err(e) = crate::event_stream::repo::record_event(
+        db,
+        user_id,
+        StreamEventKind::CrawlStarted,
+        json!({ "job_id": job_id, "source": source }),
+    )
+    .await
+    {
+        tracing::error!("failed to record crawl.started event: {e}");
+    }
+}
+
+async fn notify_crawl_finished(
+    db: &PgPool,
+    user_id: Uuid,
+    job_id: Uuid,
+    source: &str,
+    res: &IngestBatchResult,
+) {
+    let entity = |res: &IngestEntityResult| {
+        json!({
+            "inserted": res.inserted,
+            "updated": res.updated,
+            "rejected": res.rejected,
+        })
+    };
+
+    if let Err(e) = crate::event_stream::repo::record_event(
+        db,
+        user_id,
+        StreamEventKind::CrawlFinished,
+        json!({
+            "job_id": job_id,
+            "source": source,
+            "status": "succeeded",
+            "users": entity(&res.users),
+            "posts": entity(&res.posts),
+            "comments": entity(&res.comments),
+            "post_likes": entity(&res.post_likes),
+            "comment_likes": entity(&res.comment_likes),
+        }),
+    )
+    .await
+    {
+        tracing::error!("failed to record crawl.finished event: {e}");
+    }
+}
+
+async fn notify_crawl_failed(db: &PgPool, user_id: Uuid, job_id: Uuid, source: &str, error: &str) {
+    if let Err(e) = crate::event_stream::repo::record_event(
+        db,
+        user_id,
+        StreamEventKind::CrawlFinished,
+        json!({ "job_id": job_id, "source": source, "status": "failed", "error": error }),
+    )
+    .await
+    {
+        tracing::error!("failed to record crawl.finished event: {e}");
+    }
+    if let Err(e) = crate::webhooks::repo::emit_event(
+        db,
+        user_id,
+        WebhookEventKind::CrawlFailed,
+        json!({ "source": source, "error": error }),
+    )
+    .await
+    {
+        tracing::error!("failed to emit crawl.failed webhook: {e}");
+    }
+    if let Err(e) =
+        crate::notifications::sender::enqueue_crawl_failed_email(db, user_id, source, error).await
+    {
+        tracing::error!("failed to enqueue crawl failure email: {e}");
+    }
+}
+
+async fn notify_watched_comments(db: &PgPool, user_id: Uuid, res: &IngestBatchResult) {
+    if res.new_comments.is_empty() {
+        return;
+    }
+
+    let authors: Vec<i64> = res
+        .new_comments
+        .iter()
+        .map(|comment| comment.from_id)
+        .collect();
+
+    let emitted = async {
+        let watched =
+            crate::saved_searches::repo::matched_vk_user_ids(db, user_id, &authors).await?;
+
+        for comment in res
+            .new_comments
+            .iter()
+            .filter(|comment| watched.contains(&comment.from_id))
+        {
+            crate::webhooks::repo::emit_event(
+                db,
+                user_id,
+                WebhookEventKind::VkUserCommented,
+                json!({
+                    "vk_user_id": comment.from_id,
+                    "group_id": comment.group_id,
+                    "post_id": comment.post_id,
+                    "comment_id": comment.comment_id,
+                    "created_date": comment.created_date,
+                    "comment_text": comment.comment_text,
+                }),
+            )
+            .await?;
+        }
+
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+
+    if let Err(e) = emitted {
+        tracing::error!("failed to emit vk_user.commented webhooks: {e}");
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/ingest/batch",
+    request_body = IngestBatchRequest,
+    responses(
+        (status = 200, description = "Batch ingested in one transaction", body = IngestBatchResponse),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Email address is not verified", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Ingest"
+)]
+pub async fn ingest_batch(
+    user: ScopedUser<scope::Ingest>,
+    State(state): State<AppState>,
+    Json(payload): Json<IngestBatchRequest>,
+) -> ApiResult<(StatusCode, Json<IngestBatchResponse>)> {
+    crate::auth::verification::ensure_can_crawl(&state, user.id).await?;
+
+    let job_id = Uuid::new_v4();
+    notify_crawl_started(&state.db, user.id, job_id, "batch").await;
+
+    let seen_at = OffsetDateTime::now_utc();
+
+    let batch = CrawlBatch {
+        users: payload
+            .users
+            .into_iter()
+            .map(|vk_user| NewVkUser {
+                vk_user_id: vk_user.vk_user_id,
+                sex: vk_user.sex,
+                first_name: vk_user.first_name,
+                last_name: vk_user.last_name,
+                city: vk_user.city,
+                seen_at,
+                is_closed: vk_user.is_closed,
+                screen_name: vk_user.screen_name,
+                can_access_closed: vk_user.can_access_closed,
+                about: vk_user.about,
+                status: vk_user.status,
+                bdate: vk_user.bdate,
+                photo: vk_user.photo,
+                source_group_id: vk_user.source_group_id,
+            })
+            .collect(),
+        posts: payload
+            .posts
+            .into_iter()
+            .map(|post| NewVkPost {
+                post_id: post.post_id,
+                group_id: post.group_id,
+                from_id: post.from_id,
+                created_date: post.created_date,
+                post_type: post.post_type,
+                post_text: post.post_text,
+            })
+            .collect(),
+        comments: payload
+            .comments
+            .into_iter()
+            .map(|comment| NewVkComment {
+                group_id: comment.group_id,
+                post_id: comment.post_id,
+                comment_id: comment.comment_id,
+                from_id: comment.from_id,
+                created_date: comment.created_date,
+                comment_text: comment.comment_text,
+            })
+            .collect(),
+        post_likes: payload
+            .post_likes
+            .into_iter()
+            .map(|like| NewVkPostLike {
+                vk_user_id: like.vk_user_id,
+                group_id: like.group_id,
+                post_id: like.post_id,
+                seen_at,
+            })
+            .collect(),
+        comment_likes: payload
+            .comment_likes
+            .into_iter()
+            .map(|like| NewVkCommentLike {
+                vk_user_id: like.vk_user_id,
+                group_id: like.group_id,
+                post_id: like.post_id,
+                comment_id: like.comment_id,
+                seen_at,
+            })
+            .collect(),
+    };
+
+    let res = match crate::ingest::repo::ingest_batch(&state.db, user.id, &batch).await {
+        Ok(res) => res,
+        Err(e) => {
+            notify_crawl_failed(&state.db, user.id, job_id, "batch", &e.to_string()).await;
+            return Err(ApiError::Db(e));
+        }
+    };
+    notify_crawl_finished(&state.db, user.id, job_id, "batch", &res).await;
+    notify_watched_comments(&state.db, user.id, &res).await;
+
+    Ok((
+        StatusCode::OK,
+        Json(IngestBatchResponse {
+            users: counts(res.users),
+            posts: counts(res.posts),
+            comments: counts(res.comments),
+            post_likes: counts(res.post_likes),
+            comment_likes: counts(res.comment_likes),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/ingest/vk-raw",
+    params(VkRawIngestQuery),
+    request_body(content = Object, description = "Raw VK API response of the given method"),
+    responses(
+        (status = 200, description = "Payload ingested", body = VkRawIngestResponse),
+        (status = 400, description = "Invalid method, parameters or payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 403, description = "Email address is not verified", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Ingest"
+)]
+pub async fn ingest_vk_raw(
+    user: ScopedUser<scope::Ingest>,
+    State(state): State<AppState>,
+    Query(q): Query<VkRawIngestQuery>,
+    Json(payload): Json<Value>,
+) -> ApiResult<(StatusCode, Json<VkRawIngestResponse>)> {
+    crate::auth::verification::ensure_can_crawl(&state, user.id).await?;
+
+    let method = VkRawMethod::parse(&q.method).ok_or(ApiError::BadRequest(
+        "method must be one of users.get, wall.get, wall.getComments, likes.getList".to_string(),
+    ))?;
+
+    let job_id = Uuid::new_v4();
+    notify_crawl_started(&state.db, user.id, job_id, method.as_str()).await;
+
+    let ctx = VkRawContext {
+        group_id: q.group_id,
+        post_id: q.post_id,
+        comment_id: q.comment_id,
+        seen_at: OffsetDateTime::now_utc(),
+    };
+    let batch = match parse_vk_raw(method, &ctx, &payload) {
+        Ok(batch) => batch,
+        Err(message) => {
+            notify_crawl_failed(&state.db, user.id, job_id, method.as_str(), &message).await;
+            return Err(ApiError::BadRequest(message));
+        }
+    };
+
+    let res = match crate::ingest::repo::ingest_batch(&state.db, user.id, &batch).await {
+        Ok(res) => res,
+        Err(e) => {
+            notify_crawl_failed(&state.db, user.id, job_id, method.as_str(), &e.to_string()).await;
+            return Err(ApiError::Db(e));
+        }
+    };
+    notify_crawl_finished(&state.db, user.id, job_id, method.as_str(), &res).await;
+    notify_watched_comments(&state.db, user.id, &res).await;
+
+    Ok((
+        StatusCode::OK,
+        Json(VkRawIngestResponse {
+            method: method.as_str(),
+            users: counts(res.users),
+            posts: counts(res.posts),
+            comments: counts(res.comments),
+            post_likes: counts(res.post_likes),
+            comment_likes: counts(res.comment_likes),
+        }),
+    ))
+}
@@ -0,0 +1,18 @@
+use axum::{Router, routing::post};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{
+    BatchVkCommentDto, BatchVkCommentLikeDto, BatchVkPostDto, BatchVkPostLikeDto, BatchVkUserDto,
+    IngestBatchRequest, IngestBatchResponse, IngestCountsDto, VkRawIngestResponse,
+};
+pub use handlers::{ingest_batch, ingest_vk_raw};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/batch", post(ingest_batch))
+        .route("/vk-raw", post(ingest_vk_raw))
+}
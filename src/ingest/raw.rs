@@ -0,0 +1,285 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use crate::{
+    ingest::repo::CrawlBatch, vk_comment_likes::repo::NewVkCommentLike,
+    vk_comments::repo::NewVkComment, vk_post_likes::repo::NewVkPostLike, vk_posts::repo::NewVkPost,
+    vk_users::repo::NewVkUser,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VkRawMethod {
+    UsersGet,
+    WallGet,
+    WallGetComments,
+    LikesGetList,
+}
+
+impl VkRawMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VkRawMethod::UsersGet => "users.get",
+            VkRawMethod::WallGet => "wall.get",
+            VkRawMethod::WallGetComments => "wall.getComments",
+            VkRawMethod::LikesGetList => "likes.getList",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "users.get" => Some(VkRawMethod::UsersGet),
+            "wall.get" => Some(VkRawMethod::WallGet),
+            "wall.getComments" => Some(VkRawMethod::WallGetComments),
+            "likes.getList" => Some(VkRawMethod::LikesGetList),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VkRawContext {
+    pub group_id: Option<i64>,
+    pub post_id: Option<i64>,
+    pub comment_id: Option<i64>,
+    pub seen_at: OffsetDateTime,
+}
+
+#[derive(Deserialize)]
+struct RawVkCity {
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawVkUser {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    sex: Option<i16>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    city: Option<RawVkCity>,
+    is_closed: Option<bool>,
+    screen_name: Option<String>,
+    can_access_closed: Option<bool>,
+    about: Option<String>,
+    status: Option<String>,
+    bdate: Option<String>,
+    photo_max_orig: Option<String>,
+    photo_200: Option<String>,
+    photo_100: Option<String>,
+    photo_50: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawVkPost {
+    id: i64,
+    from_id: i64,
+    date: i64,
+    post_type: Option<String>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawVkThread {
+    #[serde(default)]
+    items: Vec<RawVkComment>,
+}
+
+#[derive(Deserialize)]
+struct RawVkComment {
+    id: i64,
+    from_id: i64,
+    post_id: Option<i64>,
+    date: i64,
+    text: Option<String>,
+    thread: Option<RawVkThread>,
+}
+
+#[derive(Deserialize)]
+struct RawVkItems<T> {
+    #[serde(default = "Vec::new")]
+    items: Vec<T>,
+    #[serde(default)]
+    profiles: Vec<RawVkUser>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawVkLiker {
+    Id(i64),
+    Profile(Box<RawVkUser>),
+}
+
+impl RawVkUser {
+    fn into_new(self, seen_at: OffsetDateTime, source_group_id: Option<i64>) -> NewVkUser {
+        NewVkUser {
+            vk_user_id: self.id,
+            sex: self.sex,
+            first_name: self.first_name,
+            last_name: self.last_name,
+            city: self.city.and_then(|city| city.title),
+            seen_at,
+            is_closed: self.is_closed,
+            screen_name: self.screen_name,
+            can_access_closed: self.can_access_closed,
+            about: self.about,
+            status: self.status,
+            bdate: self.bdate,
+            photo: self
+                .photo_max_orig
+                .or(self.photo_200)
+                .or(self.photo_100)
+                .or(self.photo_50),
+            source_group_id,
+        }
+    }
+}
+
+fn unwrap_response(payload: &Value) -> Result<&Value, String> {
+    if let Some(error) = payload.get("error") {
+        let message = error
+            .get("error_msg")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(format!("payload is a VK API error: {message}"));
+    }
+
+    Ok(payload.get("response").unwrap_or(payload))
+}
+
+fn decode<T: DeserializeOwned>(method: VkRawMethod, value: &Value) -> Result<T, String> {
+    T::deserialize(value).map_err(|e| format!("invalid {} payload: {e}", method.as_str()))
+}
+
+fn require(value: Option<i64>, name: &str, method: VkRawMethod) -> Result<i64, String> {
+    match value {
+        Some(value) if value > 0 => Ok(value),
+        _ => Err(format!(
+            "{name} must be a positive number for {}",
+            method.as_str()
+        )),
+    }
+}
+
+fn flatten_comments(comments: Vec<RawVkComment>, out: &mut Vec<RawVkComment>) {
+    for mut comment in comments {
+        let thread = comment.thread.take();
+        out.push(comment);
+        if let Some(thread) = thread {
+            flatten_comments(thread.items, out);
+        }
+    }
+}
+
+pub fn parse_vk_raw(
+    method: VkRawMethod,
+    ctx: &VkRawContext,
+    payload: &Value,
+) -> Result<CrawlBatch, String> {
+    let response = unwrap_response(payload)?;
+    let mut batch = CrawlBatch::default();
+
+    match method {
+        VkRawMethod::UsersGet => {
+            let users: Vec<RawVkUser> = decode(method, response)?;
+            batch.users = users
+                .into_iter()
+                .map(|user| user.into_new(ctx.seen_at, ctx.group_id))
+                .collect();
+        }
+        VkRawMethod::WallGet => {
+            let group_id = require(ctx.group_id, "group_id", method)?;
+            let wall: RawVkItems<RawVkPost> = decode(method, response)?;
+            batch.users = wall
+                .profiles
+                .into_iter()
+                .map(|user| user.into_new(ctx.seen_at, None))
+                .collect();
+            batch.posts = wall
+                .items
+                .into_iter()
+                .filter(|post| post.from_id > 0)
+                .map(|post| NewVkPost {
+                    post_id: post.id,
+                    group_id,
+                    from_id: post.from_id,
+                    created_date: post.date,
+                    post_type: post.post_type,
+                    post_text: post.text,
+                })
+                .collect();
+        }
+        VkRawMethod::WallGetComments => {
+            let group_id = require(ctx.group_id, "group_id", method)?;
+            let thread: RawVkItems<RawVkComment> = decode(method, response)?;
+            batch.users = thread
+                .profiles
+                .into_iter()
+                .map(|user| user.into_new(ctx.seen_at, None))
+                .collect();
+
+            let mut comments = Vec::new();
+            flatten_comments(thread.items, &mut comments);
+            for comment in comments.into_iter().filter(|c| c.from_id > 0) {
+                let post_id = require(comment.post_id.or(ctx.post_id), "post_id", method)?;
+                batch.comments.push(NewVkComment {
+                    group_id,
+                    post_id,
+                    comment_id: comment.id,
+                    from_id: comment.from_id,
+                    created_date: comment.date,
+                    comment_text: comment.text,
+                });
+            }
+        }
+        VkRawMethod::LikesGetList => {
+            let group_id = require(ctx.group_id, "group_id", method)?;
+            let post_id = require(ctx.post_id, "post_id", method)?;
+            let likes: RawVkItems<RawVkLiker> = decode(method, response)?;
+
+            let mut liker_ids = Vec::with_capacity(likes.items.len());
+            for liker in likes.items {
+                match liker {
+                    RawVkLiker::Id(vk_user_id) => liker_ids.push(vk_user_id),
+                    RawVkLiker::Profile(user)
+                        if user.kind.as_deref().is_some_and(|kind| kind != "profile") => {}
+                    RawVkLiker::Profile(user) => {
+                        liker_ids.push(user.id);
+                        batch.users.push(user.into_new(ctx.seen_at, None));
+                    }
+                }
+            }
+            liker_ids.retain(|vk_user_id| *vk_user_id > 0);
+
+            match ctx.comment_id {
+                Some(comment_id) => {
+                    batch.comment_likes = liker_ids
+                        .into_iter()
+                        .map(|vk_user_id| NewVkCommentLike {
+                            vk_user_id,
+                            group_id,
+                            post_id,
+                            comment_id,
+                            seen_at: ctx.seen_at,
+                        })
+                        .collect();
+                }
+                None => {
+                    batch.post_likes = liker_ids
+                        .into_iter()
+                        .map(|vk_user_id| NewVkPostLike {
+                            vk_user_id,
+                            group_id,
+                            post_id,
+                            seen_at: ctx.seen_at,
+                        })
+                        .collect();
+                }
+            }
+        }
+    }
+
+    Ok(batch)
+}
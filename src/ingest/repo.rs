@@ -0,0 +1,340 @@
+use std::{collections::HashSet, hash::Hash};
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    vk_comment_likes::repo::{NewVkCommentLike, upsert_vk_comment_likes_in},
+    vk_comments::repo::{NewVkComment, upsert_vk_comments_in},
+    vk_post_likes::repo::{NewVkPostLike, upsert_vk_post_likes_in},
+    vk_posts::repo::{NewVkPost, upsert_vk_posts_in},
+    vk_users::repo::{NewVkUser, upsert_vk_users_in},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct CrawlBatch {
+    pub users: Vec<NewVkUser>,
+    pub posts: Vec<NewVkPost>,
+    pub comments: Vec<NewVkComment>,
+    pub post_likes: Vec<NewVkPostLike>,
+    pub comment_likes: Vec<NewVkCommentLike>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IngestEntityResult {
+    pub inserted: i64,
+    pub updated: i64,
+    pub rejected: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IngestBatchResult {
+    pub users: IngestEntityResult,
+    pub posts: IngestEntityResult,
+    pub comments: IngestEntityResult,
+    pub post_likes: IngestEntityResult,
+    pub comment_likes: IngestEntityResult,
+    pub new_comments: Vec<NewVkComment>,
+}
+
+pub async fn ingest_batch(
+    db: &PgPool,
+    user_id: Uuid,
+    batch: &CrawlBatch,
+) -> Result<IngestBatchResult, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let group_ids: Vec<i64> = batch
+        .users
+        .iter()
+        .filter_map(|vk_user| vk_user.source_group_id)
+        .chain(batch.posts.iter().map(|post| post.group_id))
+        .collect();
+    let groups = existing_group_ids(&mut tx, user_id, &group_ids).await?;
+
+    let users: Vec<NewVkUser> = batch
+        .users
+        .iter()
+        .filter(|vk_user| {
+            vk_user
+                .source_group_id
+                .is_none_or(|group_id| groups.contains(&group_id))
+        })
+        .cloned()
+        .collect();
+    let rejected = (batch.users.len() - users.len()) as i64;
+    let users = last_per_key(users, |vk_user| vk_user.vk_user_id);
+    let res = upsert_vk_users_in(&mut tx, user_id, &users).await?;
+    let users_result = IngestEntityResult {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected,
+    };
+
+    let vk_user_ids: Vec<i64> = batch
+        .posts
+        .iter()
+        .map(|post| post.from_id)
+        .chain(batch.comments.iter().map(|comment| comment.from_id))
+        .chain(batch.post_likes.iter().map(|like| like.vk_user_id))
+        .chain(batch.comment_likes.iter().map(|like| like.vk_user_id))
+        .collect();
+    let vk_users = existing_vk_user_ids(&mut tx, user_id, &vk_user_ids).await?;
+
+    let posts: Vec<NewVkPost> = batch
+        .posts
+        .iter()
+        .filter(|post| groups.contains(&post.group_id) && vk_users.contains(&post.from_id))
+        .cloned()
+        .collect();
+    let rejected = (batch.posts.len() - posts.len()) as i64;
+    let posts = last_per_key(posts, |post| (post.group_id, post.post_id));
+    let res = upsert_vk_posts_in(&mut tx, user_id, &posts).await?;
+    let posts_result = IngestEntityResult {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected,
+    };
+
+    let post_keys: Vec<(i64, i64)> = batch
+        .comments
+        .iter()
+        .map(|comment| (comment.group_id, comment.post_id))
+        .chain(
+            batch
+                .post_likes
+                .iter()
+                .map(|like| (like.group_id, like.post_id)),
+        )
+        .collect();
+    let stored_posts = existing_post_keys(&mut tx, user_id, &post_keys).await?;
+
+    let comments: Vec<NewVkComment> = batch
+        .comments
+        .iter()
+        .filter(|comment| {
+            stored_posts.contains(&(comment.group_id, comment.post_id))
+                && vk_users.contains(&comment.from_id)
+        })
+        .cloned()
+        .collect();
+    let rejected = (batch.comments.len() - comments.len()) as i64;
+    let comments = last_per_key(comments, |comment| {
+        (comment.group_id, comment.post_id, comment.comment_id)
+    });
+    let comment_keys: Vec<(i64, i64, i64)> = comments
+        .iter()
+        .map(|comment| (comment.group_id, comment.post_id, comment.comment_id))
+        .collect();
+    let known_comments = existing_comment_keys(&mut tx, user_id, &comment_keys).await?;
+    let new_comments: Vec<NewVkComment> = comments
+        .iter()
+        .filter(|comment| {
+            !known_comments.contains(&(comment.group_id, comment.post_id, comment.comment_id))
+        })
+        .cloned()
+        .collect();
+    let res = upsert_vk_comments_in(&mut tx, user_id, &comments).await?;
+    let comments_result = IngestEntityResult {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected,
+    };
+
+    let post_likes: Vec<NewVkPostLike> = batch
+        .post_likes
+        .iter()
+        .filter(|like| {
+            stored_posts.contains(&(like.group_id, like.post_id))
+                && vk_users.contains(&like.vk_user_id)
+        })
+        .cloned()
+        .collect();
+    let rejected = (batch.post_likes.len() - post_likes.len()) as i64;
+    let post_likes = last_per_key(post_likes, |like| {
+        (like.vk_user_id, like.group_id, like.post_id)
+    });
+    let res = upsert_vk_post_likes_in(&mut tx, user_id, &post_likes).await?;
+    let post_likes_result = IngestEntityResult {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected,
+    };
+
+    let liked_comment_keys: Vec<(i64, i64, i64)> = batch
+        .comment_likes
+        .iter()
+        .map(|like| (like.group_id, like.post_id, like.comment_id))
+        .collect();
+    let stored_comments = existing_comment_keys(&mut tx, user_id, &liked_comment_keys).await?;
+
+    let comment_likes: Vec<NewVkCommentLike> = batch
+        .comment_likes
+        .iter()
+        .filter(|like| {
+            stored_comments.contains(&(like.group_id, like.post_id, like.comment_id))
+                && vk_users.contains(&like.vk_user_id)
+        })
+        .cloned()
+        .collect();
+    let rejected = (batch.comment_likes.len() - comment_likes.len()) as i64;
+    let comment_likes = last_per_key(comment_likes, |like| {
+        (
+            like.vk_user_id,
+            like.group_id,
+            like.post_id,
+            like.comment_id,
+        )
+    });
+    let res = upsert_vk_comment_likes_in(&mut tx, user_id, &comment_likes).await?;
+    let comment_likes_result = IngestEntityResult {
+        inserted: res.inserted,
+        updated: res.updated,
+        rejected,
+    };
+
+    tx.commit().await?;
+
+    Ok(IngestBatchResult {
+        users: users_result,
+        posts: posts_result,
+        comments: comments_result,
+        post_likes: post_likes_result,
+        comment_likes: comment_likes_result,
+        new_comments,
+    })
+}
+
+/// Keeps the last occurrence of every key, in input order. A multi-row
+/// `INSERT ... ON CONFLICT DO UPDATE` cannot touch the same row twice, so
+/// crawlers that report a row twice in one batch get the later copy stored.
+fn last_per_key<T, K: Eq + Hash>(items: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut seen = HashSet::new();
+    let mut kept: Vec<T> = items
+        .into_iter()
+        .rev()
+        .filter(|item| seen.insert(key(item)))
+        .collect();
+    kept.reverse();
+    kept
+}
+
+async fn existing_group_ids(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    group_ids: &[i64],
+) -> Result<HashSet<i64>, sqlx::Error> {
+    if group_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let rows = sqlx::query_scalar!(
+        r#"
+        SELECT group_id
+        FROM groups
+        WHERE user_id = $1 AND group_id = ANY($2)
+        "#,
+        user_id,
+        group_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+async fn existing_vk_user_ids(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<HashSet<i64>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let rows = sqlx::query_scalar!(
+        r#"
+        SELECT vk_user_id
+        FROM vk_users
+        WHERE user_id = $1 AND vk_user_id = ANY($2)
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+async fn existing_post_keys(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    keys: &[(i64, i64)],
+) -> Result<HashSet<(i64, i64)>, sqlx::Error> {
+    if keys.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let (group_ids, post_ids): (Vec<i64>, Vec<i64>) = keys.iter().copied().unzip();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.group_id, p.post_id
+        FROM vk_posts p
+        JOIN UNNEST($2::bigint[], $3::bigint[]) AS k(group_id, post_id)
+            ON p.group_id = k.group_id AND p.post_id = k.post_id
+        WHERE p.user_id = $1
+        "#,
+        user_id,
+        &group_ids,
+        &post_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.group_id, row.post_id))
+        .collect())
+}
+
+async fn existing_comment_keys(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    keys: &[(i64, i64, i64)],
+) -> Result<HashSet<(i64, i64, i64)>, sqlx::Error> {
+    if keys.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let mut group_ids = Vec::with_capacity(keys.len());
+    let mut post_ids = Vec::with_capacity(keys.len());
+    let mut comment_ids = Vec::with_capacity(keys.len());
+    for (group_id, post_id, comment_id) in keys {
+        group_ids.push(*group_id);
+        post_ids.push(*post_id);
+        comment_ids.push(*comment_id);
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.group_id, c.post_id, c.comment_id
+        FROM vk_comments c
+        JOIN UNNEST($2::bigint[], $3::bigint[], $4::bigint[]) AS k(group_id, post_id, comment_id)
+            ON c.group_id = k.group_id AND c.post_id = k.post_id AND c.comment_id = k.comment_id
+        WHERE c.user_id = $1
+        "#,
+        user_id,
+        &group_ids,
+        &post_ids,
+        &comment_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.group_id, row.post_id, row.comment_id))
+        .collect())
+}
@@ -1,21 +1,43 @@
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use uuid::Uuid;
 
+use crate::app::trusted_proxies::TrustedProxies;
+use crate::auth::{
+    login_throttle::LoginThrottlePolicy, session_cache::SessionCache,
+    verification::EmailVerificationPolicy,
+};
+use crate::event_stream::notifier::StreamNotifier;
+use crate::webhooks::sender::WebhookClient;
+
+pub mod admin;
+pub mod api_keys;
 pub mod app;
 pub mod auth;
 pub mod core;
 pub mod error;
+pub mod event_stream;
+pub mod export;
 mod extractors;
 pub mod groups;
+pub mod ingest;
 pub mod notes;
+pub mod notifications;
+pub mod pipeline;
+pub mod saved_searches;
+pub mod scheduler;
+pub mod tags;
 pub mod user_settings;
 pub mod vk_comment_likes;
 pub mod vk_comments;
+pub mod vk_events;
 pub mod vk_post_likes;
 pub mod vk_posts;
 pub mod vk_tokens;
+pub mod vk_user_sources;
 pub mod vk_users;
+pub mod webhooks;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -23,11 +45,19 @@ pub struct AppState {
     pub jwt_enc: EncodingKey,
     pub jwt_dec: DecodingKey,
     pub vk_token_enc_key: String,
+    pub email_verification: EmailVerificationPolicy,
+    pub public_base_url: String,
+    pub sessions: SessionCache,
+    pub login_throttle: LoginThrottlePolicy,
+    pub trusted_proxies: TrustedProxies,
+    pub stream_notifier: StreamNotifier,
+    pub webhooks: WebhookClient,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Claims {
     sub: String, // user_id
+    sid: Uuid,   // refresh token family
     iat: i64,
     exp: i64,
 }
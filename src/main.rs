@@ -1,6 +1,16 @@
 use std::net::SocketAddr;
 
-use find_w::{AppState, app::router::build_router};
+use find_w::{
+    AppState,
+    app::{router::build_router, trusted_proxies::TrustedProxies},
+    auth::{
+        login_throttle::LoginThrottlePolicy, session_cache::SessionCache,
+        verification::EmailVerificationPolicy,
+    },
+    event_stream::notifier::StreamNotifier,
+    notifications::mailer::{Mailer, SmtpConfig},
+    webhooks::{sender::WebhookClient, targets::WebhookTargetPolicy},
+};
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use sqlx::postgres::PgPoolOptions;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -24,15 +34,79 @@ async fn main() {
     let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
     let vk_token_enc_key = std::env::var("VK_TOKEN_ENC_KEY").expect("VK_TOKEN_ENC_KEY must be set");
 
+    let email_verification =
+        EmailVerificationPolicy::from_env().expect("invalid REQUIRE_EMAIL_VERIFICATION");
+
+    let trusted_proxies = TrustedProxies::from_env().expect("invalid TRUSTED_PROXIES");
+
+    let webhooks = WebhookClient::new(
+        WebhookTargetPolicy::from_env().expect("invalid WEBHOOK_ALLOW_PRIVATE_TARGETS"),
+    );
+
+    let public_base_url =
+        std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+
+    let mailer = match SmtpConfig::from_env() {
+        Some(config) => Some(Mailer::new(&config).expect("invalid SMTP configuration")),
+        None => {
+            tracing::warn!("SMTP_HOST is not set, email notifications are disabled");
+            None
+        }
+    };
+    email_verification
+        .check_mailer(mailer.is_some())
+        .expect("invalid REQUIRE_EMAIL_VERIFICATION");
+
+    let admin_emails: Vec<String> = std::env::var("ADMIN_EMAILS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+        .collect();
+    if !admin_emails.is_empty() {
+        let admins = find_w::admin::repo::promote_admins(&db, &admin_emails)
+            .await
+            .expect("failed to promote ADMIN_EMAILS");
+        let unmatched: Vec<&str> = admin_emails
+            .iter()
+            .filter(|email| !admins.iter().any(|admin| admin.eq_ignore_ascii_case(email)))
+            .map(String::as_str)
+            .collect();
+        if !unmatched.is_empty() {
+            tracing::warn!(
+                ?unmatched,
+                "ADMIN_EMAILS entries without a verified account were not promoted"
+            );
+        }
+        tracing::info!(
+            admins = admins.len(),
+            "granted admin role from ADMIN_EMAILS"
+        );
+    }
+
+    find_w::scheduler::spawn(db.clone(), webhooks.clone(), mailer);
+
     let state = AppState {
         db,
         jwt_enc: EncodingKey::from_secret(jwt_secret.as_bytes()),
         jwt_dec: DecodingKey::from_secret(jwt_secret.as_bytes()),
         vk_token_enc_key,
+        email_verification,
+        public_base_url,
+        sessions: SessionCache::default(),
+        login_throttle: LoginThrottlePolicy::default(),
+        trusted_proxies,
+        stream_notifier: StreamNotifier::default(),
+        webhooks,
     };
     let app = build_router(state);
     let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
     tracing::info!("listening on http://{addr}");
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
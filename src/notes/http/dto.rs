@@ -7,6 +7,14 @@ use uuid::Uuid;
 pub struct CreateNoteRequest {
     pub title: String,
     pub body: String,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateNoteRequest {
+    pub title: Option<String>,
+    pub body: Option<String>,
 }
 
 #[derive(Deserialize, IntoParams)]
@@ -14,6 +22,16 @@ pub struct CreateNoteRequest {
 pub struct NotesQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub q: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NoteVersionsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -21,5 +39,17 @@ pub struct NoteDto {
     pub id: Uuid,
     pub title: String,
     pub body: String,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NoteVersionDto {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub title: String,
+    pub body: String,
     pub created_at: OffsetDateTime,
 }
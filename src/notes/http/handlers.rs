@@ -8,10 +8,44 @@ use uuid::Uuid;
 use crate::{
     AppState,
     error::{ApiError, ApiResult},
-    extractors::auth_user::AuthUser,
+    extractors::scoped_user::{ScopedUser, scope},
+    notes::repo::{Note, NoteVersion, NotesFilter},
 };
 
-use super::dto::{CreateNoteRequest, NoteDto, NotesQuery};
+use super::dto::{
+    CreateNoteRequest, NoteDto, NoteVersionDto, NoteVersionsQuery, NotesQuery, UpdateNoteRequest,
+};
+
+pub(crate) fn note_dto(note: Note) -> NoteDto {
+    NoteDto {
+        id: note.id,
+        title: note.title,
+        body: note.body,
+        vk_user_id: note.vk_user_id,
+        group_id: note.group_id,
+        created_at: note.created_at,
+        updated_at: note.updated_at,
+    }
+}
+
+fn note_version_dto(version: NoteVersion) -> NoteVersionDto {
+    NoteVersionDto {
+        id: version.id,
+        note_id: version.note_id,
+        title: version.title,
+        body: version.body,
+        created_at: version.created_at,
+    }
+}
+
+fn validate_link(field: &str, value: Option<i64>) -> ApiResult<()> {
+    if value.is_some_and(|id| id <= 0) {
+        return Err(ApiError::BadRequest(format!(
+            "{field} must be greater than 0"
+        )));
+    }
+    Ok(())
+}
 
 #[utoipa::path(
     post,
@@ -29,7 +63,7 @@ use super::dto::{CreateNoteRequest, NoteDto, NotesQuery};
     tag = "Notes"
 )]
 pub async fn create_note(
-    user: AuthUser,
+    user: ScopedUser<scope::NotesWrite>,
     State(state): State<AppState>,
     Json(request): Json<CreateNoteRequest>,
 ) -> ApiResult<(StatusCode, Json<NoteDto>)> {
@@ -39,20 +73,29 @@ pub async fn create_note(
     if request.body.trim().is_empty() {
         return Err(ApiError::BadRequest("body is required".to_string()));
     }
+    validate_link("vk_user_id", request.vk_user_id)?;
+    validate_link("group_id", request.group_id)?;
 
-    let note = crate::notes::repo::create_note(&state.db, user.id, request.title, request.body)
-        .await
-        .map_err(ApiError::Db)?;
+    let note = crate::notes::repo::create_note(
+        &state.db,
+        user.id,
+        request.title,
+        request.body,
+        request.vk_user_id,
+        request.group_id,
+    )
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some("23503") => {
+            match db_error.constraint() {
+                Some("notes_groups_fk") => ApiError::BadRequest("group not found".to_string()),
+                _ => ApiError::BadRequest("vk user not found".to_string()),
+            }
+        }
+        e => ApiError::Db(e),
+    })?;
 
-    Ok((
-        StatusCode::CREATED,
-        Json(NoteDto {
-            id: note.id,
-            title: note.title,
-            body: note.body,
-            created_at: note.created_at,
-        }),
-    ))
+    Ok((StatusCode::CREATED, Json(note_dto(note))))
 }
 
 #[utoipa::path(
@@ -61,6 +104,7 @@ pub async fn create_note(
     params(NotesQuery),
     responses(
         (status = 200, description = "User notes", body = [NoteDto]),
+        (status = 400, description = "Invalid filter", body = crate::error::ErrorBody),
         (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
         (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
     ),
@@ -70,26 +114,25 @@ pub async fn create_note(
     tag = "Notes"
 )]
 pub async fn list_notes(
-    user: AuthUser,
+    user: ScopedUser<scope::NotesRead>,
     State(state): State<AppState>,
     Query(q): Query<NotesQuery>,
 ) -> ApiResult<(StatusCode, Json<Vec<NoteDto>>)> {
     let limit = q.limit.unwrap_or(50).clamp(1, 100);
     let offset = q.offset.unwrap_or(0).max(0);
+    validate_link("vk_user_id", q.vk_user_id)?;
+    validate_link("group_id", q.group_id)?;
+    let filter = NotesFilter {
+        vk_user_id: q.vk_user_id,
+        group_id: q.group_id,
+        q: q.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+    };
 
-    let rows = crate::notes::repo::list_notes(&state.db, user.id, limit, offset)
+    let rows = crate::notes::repo::list_notes(&state.db, user.id, &filter, limit, offset)
         .await
         .map_err(ApiError::Db)?;
 
-    let notes = rows
-        .into_iter()
-        .map(|r| NoteDto {
-            id: r.id,
-            title: r.title,
-            body: r.body,
-            created_at: r.created_at,
-        })
-        .collect();
+    let notes = rows.into_iter().map(note_dto).collect();
 
     Ok((StatusCode::OK, Json(notes)))
 }
@@ -112,7 +155,7 @@ pub async fn list_notes(
     tag = "Notes"
 )]
 pub async fn get_note(
-    user: AuthUser,
+    user: ScopedUser<scope::NotesRead>,
     State(state): State<AppState>,
     Path(note_id): Path<Uuid>,
 ) -> ApiResult<(StatusCode, Json<NoteDto>)> {
@@ -120,15 +163,133 @@ pub async fn get_note(
         .await
         .map_err(ApiError::Db)?;
     let note = note.ok_or(ApiError::NotFound)?;
-    Ok((
-        StatusCode::OK,
-        Json(NoteDto {
-            id: note.id,
-            title: note.title,
-            body: note.body,
-            created_at: note.created_at,
-        }),
-    ))
+    Ok((StatusCode::OK, Json(note_dto(note))))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/notes/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Note id")
+    ),
+    request_body = UpdateNoteRequest,
+    responses(
+        (status = 200, description = "Note updated; the previous content is kept as a version", body = NoteDto),
+        (status = 400, description = "Invalid note payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Note not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Notes"
+)]
+pub async fn update_note(
+    user: ScopedUser<scope::NotesWrite>,
+    State(state): State<AppState>,
+    Path(note_id): Path<Uuid>,
+    Json(request): Json<UpdateNoteRequest>,
+) -> ApiResult<(StatusCode, Json<NoteDto>)> {
+    if request.title.is_none() && request.body.is_none() {
+        return Err(ApiError::BadRequest(
+            "title or body is required".to_string(),
+        ));
+    }
+    if request.title.as_ref().is_some_and(|t| t.trim().is_empty()) {
+        return Err(ApiError::BadRequest("title is required".to_string()));
+    }
+    if request.body.as_ref().is_some_and(|b| b.trim().is_empty()) {
+        return Err(ApiError::BadRequest("body is required".to_string()));
+    }
+
+    let note = crate::notes::repo::update_note_owned(
+        &state.db,
+        user.id,
+        note_id,
+        request.title.as_deref(),
+        request.body.as_deref(),
+    )
+    .await
+    .map_err(ApiError::Db)?
+    .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(note_dto(note))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/notes/{id}/versions",
+    params(
+        ("id" = Uuid, Path, description = "Note id"),
+        NoteVersionsQuery
+    ),
+    responses(
+        (status = 200, description = "Previous versions of the note, newest first", body = [NoteVersionDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Note not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Notes"
+)]
+pub async fn list_note_versions(
+    user: ScopedUser<scope::NotesRead>,
+    State(state): State<AppState>,
+    Path(note_id): Path<Uuid>,
+    Query(q): Query<NoteVersionsQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<NoteVersionDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    crate::notes::repo::get_note_owned(&state.db, user.id, note_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let versions =
+        crate::notes::repo::list_note_versions(&state.db, user.id, note_id, limit, offset)
+            .await
+            .map_err(ApiError::Db)?
+            .into_iter()
+            .map(note_version_dto)
+            .collect();
+
+    Ok((StatusCode::OK, Json(versions)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/notes/{id}/versions/{version_id}/restore",
+    params(
+        ("id" = Uuid, Path, description = "Note id"),
+        ("version_id" = Uuid, Path, description = "Note version id")
+    ),
+    responses(
+        (status = 200, description = "Note restored; the replaced content is kept as a version", body = NoteDto),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Note or version not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Notes"
+)]
+pub async fn restore_note_version(
+    user: ScopedUser<scope::NotesWrite>,
+    State(state): State<AppState>,
+    Path((note_id, version_id)): Path<(Uuid, Uuid)>,
+) -> ApiResult<(StatusCode, Json<NoteDto>)> {
+    let note =
+        crate::notes::repo::restore_note_version_owned(&state.db, user.id, note_id, version_id)
+            .await
+            .map_err(ApiError::Db)?
+            .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(note_dto(note))))
 }
 
 #[utoipa::path(
@@ -149,7 +310,7 @@ pub async fn get_note(
     tag = "Notes"
 )]
 pub async fn delete_note(
-    user: AuthUser,
+    user: ScopedUser<scope::NotesWrite>,
     State(state): State<AppState>,
     Path(note_id): Path<Uuid>,
 ) -> ApiResult<StatusCode> {
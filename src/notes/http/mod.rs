@@ -7,11 +7,22 @@ use crate::AppState;
 mod dto;
 pub(crate) mod handlers;
 
-pub use dto::{CreateNoteRequest, NoteDto};
-pub use handlers::{create_note, delete_note, get_note, list_notes};
+pub use dto::{CreateNoteRequest, NoteDto, NoteVersionDto, UpdateNoteRequest};
+pub use handlers::{
+    create_note, delete_note, get_note, list_note_versions, list_notes, restore_note_version,
+    update_note,
+};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", post(create_note).get(list_notes))
-        .route("/{id}", get(get_note).delete(delete_note))
+        .route(
+            "/{id}",
+            get(get_note).patch(update_note).delete(delete_note),
+        )
+        .route("/{id}/versions", get(list_note_versions))
+        .route(
+            "/{id}/versions/{version_id}/restore",
+            post(restore_note_version),
+        )
 }
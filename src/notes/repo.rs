@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use sqlx::PgPool;
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -9,24 +11,47 @@ pub struct Note {
     pub user_id: Uuid,
     pub title: String,
     pub body: String,
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct NoteVersion {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub title: String,
+    pub body: String,
     pub created_at: OffsetDateTime,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct NotesFilter {
+    pub vk_user_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub q: Option<String>,
+}
+
 pub async fn create_note(
     db: &PgPool,
     user_id: Uuid,
     title: String,
     body: String,
+    vk_user_id: Option<i64>,
+    group_id: Option<i64>,
 ) -> Result<Note, sqlx::Error> {
     let row = sqlx::query!(
         r#"
-        INSERT INTO notes (user_id, title, body)
-        VALUES ($1, $2, $3)
-        RETURNING id, user_id, title, body, created_at
+        INSERT INTO notes (user_id, title, body, vk_user_id, group_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, title, body, vk_user_id, group_id, created_at, updated_at
         "#,
         user_id,
         title,
-        body
+        body,
+        vk_user_id,
+        group_id
     )
     .fetch_one(db)
     .await?;
@@ -36,7 +61,10 @@ pub async fn create_note(
         user_id: row.user_id,
         title: row.title,
         body: row.body,
+        vk_user_id: row.vk_user_id,
+        group_id: row.group_id,
         created_at: row.created_at,
+        updated_at: row.updated_at,
     })
 }
 
@@ -47,7 +75,7 @@ pub async fn get_note_owned(
 ) -> Result<Option<Note>, sqlx::Error> {
     let may_be_recodrd = sqlx::query!(
         r#"
-        SELECT id, user_id ,title, body, created_at
+        SELECT id, user_id, title, body, vk_user_id, group_id, created_at, updated_at
         FROM notes
         WHERE id = $1 AND user_id = $2
         "#,
@@ -62,27 +90,42 @@ pub async fn get_note_owned(
         user_id: r.user_id,
         title: r.title,
         body: r.body,
+        vk_user_id: r.vk_user_id,
+        group_id: r.group_id,
         created_at: r.created_at,
+        updated_at: r.updated_at,
     }))
 }
 
 pub async fn list_notes(
     db: &PgPool,
     user_id: Uuid,
+    filter: &NotesFilter,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<Note>, sqlx::Error> {
     let rows = sqlx::query!(
         r#"
-        SELECT id, user_id, title, body, created_at
+        SELECT id, user_id, title, body, vk_user_id, group_id, created_at, updated_at
         FROM notes
         WHERE user_id = $1
-        ORDER BY created_at DESC
+          AND ($4::bigint IS NULL OR vk_user_id = $4)
+          AND ($5::bigint IS NULL OR group_id = $5)
+          AND ($6::text IS NULL OR search @@ websearch_to_tsquery('simple', $6))
+        ORDER BY
+            CASE
+                WHEN $6::text IS NULL THEN 0
+                ELSE ts_rank(search, websearch_to_tsquery('simple', $6))
+            END DESC,
+            created_at DESC
         LIMIT $2 OFFSET $3
         "#,
         user_id,
         limit,
-        offset
+        offset,
+        filter.vk_user_id,
+        filter.group_id,
+        filter.q
     )
     .fetch_all(db)
     .await?;
@@ -94,11 +137,225 @@ pub async fn list_notes(
             user_id: r.user_id,
             title: r.title,
             body: r.body,
+            vk_user_id: r.vk_user_id,
+            group_id: r.group_id,
             created_at: r.created_at,
+            updated_at: r.updated_at,
         })
         .collect())
 }
 
+pub async fn count_notes_by_vk_user(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<HashMap<i64, i64>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT vk_user_id AS "vk_user_id!", COUNT(*) AS "count!"
+        FROM notes
+        WHERE user_id = $1 AND vk_user_id = ANY($2)
+        GROUP BY vk_user_id
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.vk_user_id, r.count)).collect())
+}
+
+struct LockedNote {
+    title: String,
+    body: String,
+}
+
+async fn lock_note(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    note_id: Uuid,
+) -> Result<Option<LockedNote>, sqlx::Error> {
+    sqlx::query_as!(
+        LockedNote,
+        r#"
+        SELECT title, body
+        FROM notes
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        note_id,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+}
+
+/// Saves `current`, the row locked by [`lock_note`], as a version when the
+/// content changes and writes the new title and body.
+async fn replace_note_content(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    note_id: Uuid,
+    current: &LockedNote,
+    title: &str,
+    body: &str,
+) -> Result<Note, sqlx::Error> {
+    if current.title != title || current.body != body {
+        sqlx::query!(
+            r#"
+            INSERT INTO note_versions (note_id, title, body)
+            VALUES ($1, $2, $3)
+            "#,
+            note_id,
+            current.title,
+            current.body
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE notes
+        SET title = $3,
+            body = $4,
+            updated_at = CASE
+                WHEN title = $3 AND body = $4 THEN updated_at
+                ELSE now()
+            END
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, user_id, title, body, vk_user_id, group_id, created_at, updated_at
+        "#,
+        note_id,
+        user_id,
+        title,
+        body
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(Note {
+        id: row.id,
+        user_id: row.user_id,
+        title: row.title,
+        body: row.body,
+        vk_user_id: row.vk_user_id,
+        group_id: row.group_id,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    })
+}
+
+pub async fn update_note_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    note_id: Uuid,
+    title: Option<&str>,
+    body: Option<&str>,
+) -> Result<Option<Note>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let Some(current) = lock_note(&mut tx, user_id, note_id).await? else {
+        return Ok(None);
+    };
+
+    let note = replace_note_content(
+        &mut tx,
+        user_id,
+        note_id,
+        &current,
+        title.unwrap_or(&current.title),
+        body.unwrap_or(&current.body),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(note))
+}
+
+pub async fn list_note_versions(
+    db: &PgPool,
+    user_id: Uuid,
+    note_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<NoteVersion>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT v.id, v.note_id, v.title, v.body, v.created_at
+        FROM note_versions v
+        JOIN notes n ON n.id = v.note_id
+        WHERE v.note_id = $1 AND n.user_id = $2
+        ORDER BY v.created_at DESC, v.id
+        LIMIT $3 OFFSET $4
+        "#,
+        note_id,
+        user_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| NoteVersion {
+            id: r.id,
+            note_id: r.note_id,
+            title: r.title,
+            body: r.body,
+            created_at: r.created_at,
+        })
+        .collect())
+}
+
+pub async fn restore_note_version_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    note_id: Uuid,
+    version_id: Uuid,
+) -> Result<Option<Note>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let version = sqlx::query!(
+        r#"
+        SELECT v.title, v.body
+        FROM note_versions v
+        JOIN notes n ON n.id = v.note_id
+        WHERE v.id = $1 AND v.note_id = $2 AND n.user_id = $3
+        "#,
+        version_id,
+        note_id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(version) = version else {
+        return Ok(None);
+    };
+    let Some(current) = lock_note(&mut tx, user_id, note_id).await? else {
+        return Ok(None);
+    };
+
+    let note = replace_note_content(
+        &mut tx,
+        user_id,
+        note_id,
+        &current,
+        &version.title,
+        &version.body,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(note))
+}
+
 pub async fn delete_note_owned(
     db: &PgPool,
     user_id: Uuid,
@@ -0,0 +1,3 @@
+pub mod mailer;
+pub mod repo;
+pub mod sender;
@@ -0,0 +1,287 @@
+use sqlx::{PgExecutor, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::user_settings::repo::EmailDigestFrequency;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailKind {
+    Match,
+    CrawlFailed,
+    Digest,
+    Verification,
+    PasswordReset,
+    EmailChange,
+    SecurityAlert,
+}
+
+impl EmailKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailKind::Match => "match",
+            EmailKind::CrawlFailed => "crawl_failed",
+            EmailKind::Digest => "digest",
+            EmailKind::Verification => "verification",
+            EmailKind::PasswordReset => "password_reset",
+            EmailKind::EmailChange => "email_change",
+            EmailKind::SecurityAlert => "security_alert",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+impl EmailStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailStatus::Pending => "pending",
+            EmailStatus::Sent => "sent",
+            EmailStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DueEmail {
+    pub id: Uuid,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub attempts: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct DueDigest {
+    pub user_id: Uuid,
+    pub frequency: EmailDigestFrequency,
+    pub since: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestGroupCount {
+    pub group_id: i64,
+    pub group_name: Option<String>,
+    pub new_vk_users: i64,
+}
+
+pub async fn enqueue_email<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    kind: EmailKind,
+    subject: &str,
+    body: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let res = sqlx::query!(
+        r#"
+        INSERT INTO email_outbox (user_id, kind, subject, body)
+        SELECT s.user_id, $2::text, $3, $4
+        FROM user_settings AS s
+        WHERE s.user_id = $1
+          AND CASE $2::text
+                  WHEN 'match' THEN s.email_notify_matches
+                  WHEN 'crawl_failed' THEN s.email_notify_crawl_failures
+                  WHEN 'security_alert' THEN s.email_notify_security
+                  ELSE true
+              END
+        "#,
+        user_id,
+        kind.as_str(),
+        subject,
+        body
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn enqueue_email_to(
+    db: &PgPool,
+    user_id: Uuid,
+    to_email: &str,
+    kind: EmailKind,
+    subject: &str,
+    body: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_outbox (user_id, to_email, kind, subject, body)
+        VALUES ($1, $2::text, $3::text, $4, $5)
+        "#,
+        user_id,
+        to_email,
+        kind.as_str(),
+        subject,
+        body
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn claim_due_emails(db: &PgPool, limit: i64) -> Result<Vec<DueEmail>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        UPDATE email_outbox AS o
+        SET next_attempt_at = now() + interval '5 minutes'
+        FROM users AS u
+        WHERE u.id = o.user_id
+          AND o.id IN (
+              SELECT id
+              FROM email_outbox
+              WHERE status = 'pending' AND next_attempt_at <= now()
+              ORDER BY next_attempt_at
+              LIMIT $1
+              FOR UPDATE SKIP LOCKED
+          )
+        RETURNING o.id, COALESCE(o.to_email, u.email) AS "email!", o.subject, o.body, o.attempts
+        "#,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| DueEmail {
+            id: row.id,
+            to: row.email,
+            subject: row.subject,
+            body: row.body,
+            attempts: row.attempts,
+        })
+        .collect())
+}
+
+pub async fn record_email_attempt(
+    db: &PgPool,
+    email_id: Uuid,
+    status: EmailStatus,
+    next_attempt_at: OffsetDateTime,
+    error: Option<String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE email_outbox
+        SET status = $2::text,
+            attempts = attempts + 1,
+            next_attempt_at = $3,
+            last_error = $4,
+            sent_at = CASE WHEN $2::text = 'sent' THEN now() ELSE sent_at END
+        WHERE id = $1
+        "#,
+        email_id,
+        status.as_str(),
+        next_attempt_at,
+        error
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Claims one due digest, skipping the users in `skip`. The claim only sticks
+/// if the transaction that queues the digest commits.
+pub async fn claim_due_digest<'e, E>(
+    executor: E,
+    skip: &[Uuid],
+) -> Result<Option<DueDigest>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let row = sqlx::query!(
+        r#"
+        WITH due AS (
+            SELECT
+                user_id,
+                COALESCE(
+                    last_digest_at,
+                    now() - CASE email_digest
+                                WHEN 'weekly' THEN interval '7 days'
+                                ELSE interval '1 day'
+                            END
+                ) AS since
+            FROM user_settings
+            WHERE email_digest <> 'off'
+              AND user_id <> ALL($1)
+              AND (
+                  last_digest_at IS NULL
+                  OR last_digest_at + CASE email_digest
+                                          WHEN 'weekly' THEN interval '7 days'
+                                          ELSE interval '1 day'
+                                      END <= now()
+              )
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE user_settings AS s
+        SET last_digest_at = now()
+        FROM due
+        WHERE s.user_id = due.user_id
+        RETURNING s.user_id, s.email_digest, due.since AS "since!"
+        "#,
+        skip
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.map(|row| {
+        Ok(DueDigest {
+            user_id: row.user_id,
+            frequency: EmailDigestFrequency::parse(&row.email_digest).ok_or_else(|| {
+                sqlx::Error::Decode(format!("unknown email_digest: {}", row.email_digest).into())
+            })?,
+            since: row.since,
+        })
+    })
+    .transpose()
+}
+
+pub async fn digest_group_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    since: OffsetDateTime,
+) -> Result<Vec<DigestGroupCount>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            g.group_id,
+            g.group_name,
+            COUNT(DISTINCT u.vk_user_id) AS "new_vk_users!"
+        FROM vk_users AS u
+        JOIN vk_user_sources AS src
+          ON src.user_id = u.user_id AND src.vk_user_id = u.vk_user_id
+        JOIN groups AS g
+          ON g.user_id = src.user_id AND g.group_id = src.group_id
+        WHERE u.user_id = $1 AND u.first_seen_at > $2 AND NOT u.is_hidden
+        GROUP BY g.group_id, g.group_name
+        ORDER BY 3 DESC, g.group_id
+        "#,
+        user_id,
+        since
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| DigestGroupCount {
+            group_id: row.group_id,
+            group_name: row.group_name,
+            new_vk_users: row.new_vk_users,
+        })
+        .collect())
+}
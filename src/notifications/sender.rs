@@ -0,0 +1,164 @@
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    notifications::{
+        mailer::Mailer,
+        repo::{DueDigest, DueEmail, EmailKind, EmailStatus, record_email_attempt},
+    },
+    saved_searches::repo::NewSavedSearchMatch,
+    user_settings::repo::EmailDigestFrequency,
+};
+
+pub const MAX_ATTEMPTS: i32 = 5;
+
+const BATCH_SIZE: i64 = 50;
+
+pub fn retry_delay(attempts: i32) -> Duration {
+    Duration::minutes(1) * 2_i32.pow(attempts.clamp(1, 10) as u32 - 1)
+}
+
+pub async fn enqueue_match_email(
+    db: &PgPool,
+    user_id: Uuid,
+    matches: &[NewSavedSearchMatch],
+) -> Result<bool, sqlx::Error> {
+    if matches.is_empty() {
+        return Ok(false);
+    }
+
+    let subject = format!("{} new saved search matches", matches.len());
+    let mut body = String::from("New VK users matched your saved searches:\n\n");
+    for m in matches {
+        body.push_str(&format!(
+            "- https://vk.com/id{} ({})\n",
+            m.vk_user_id, m.search_name
+        ));
+    }
+
+    crate::notifications::repo::enqueue_email(db, user_id, EmailKind::Match, &subject, &body).await
+}
+
+pub async fn enqueue_crawl_failed_email(
+    db: &PgPool,
+    user_id: Uuid,
+    source: &str,
+    error: &str,
+) -> Result<bool, sqlx::Error> {
+    let subject = format!("Crawl failed: {source}");
+    let body = format!("A crawl ingestion ({source}) failed:\n\n{error}\n");
+
+    crate::notifications::repo::enqueue_email(db, user_id, EmailKind::CrawlFailed, &subject, &body)
+        .await
+}
+
+/// Claims and queues each due digest in its own transaction, so a failure
+/// leaves that user's `last_digest_at` untouched for the next tick without
+/// holding back anyone else's digest.
+pub async fn enqueue_due_digests(db: &PgPool) -> Result<i64, sqlx::Error> {
+    let mut enqueued = 0;
+    let mut failed = Vec::new();
+
+    loop {
+        let mut tx = db.begin().await?;
+        let Some(digest) = crate::notifications::repo::claim_due_digest(&mut *tx, &failed).await?
+        else {
+            break;
+        };
+
+        match enqueue_digest(&mut tx, &digest).await {
+            Ok(queued) => {
+                tx.commit().await?;
+                if queued {
+                    enqueued += 1;
+                }
+            }
+            Err(e) => {
+                tracing::error!("user {}: failed to queue digest: {e}", digest.user_id);
+                failed.push(digest.user_id);
+            }
+        }
+    }
+
+    Ok(enqueued)
+}
+
+async fn enqueue_digest(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    digest: &DueDigest,
+) -> Result<bool, sqlx::Error> {
+    let groups =
+        crate::notifications::repo::digest_group_counts(&mut **tx, digest.user_id, digest.since)
+            .await?;
+    if groups.is_empty() {
+        return Ok(false);
+    }
+
+    let total: i64 = groups.iter().map(|g| g.new_vk_users).sum();
+    let period = match digest.frequency {
+        EmailDigestFrequency::Weekly => "Weekly",
+        _ => "Daily",
+    };
+    let subject = format!("{period} digest: {total} new VK users");
+    let mut body = format!(
+        "New VK users found per group since {}:\n\n",
+        digest.since.date()
+    );
+    for group in &groups {
+        let name = group.group_name.as_deref().unwrap_or("unnamed group");
+        body.push_str(&format!(
+            "- {name} (club{}): {}\n",
+            group.group_id, group.new_vk_users
+        ));
+    }
+
+    crate::notifications::repo::enqueue_email(
+        &mut **tx,
+        digest.user_id,
+        EmailKind::Digest,
+        &subject,
+        &body,
+    )
+    .await
+}
+
+pub async fn attempt_send(
+    db: &PgPool,
+    mailer: &Mailer,
+    email: &DueEmail,
+) -> Result<EmailStatus, sqlx::Error> {
+    let error = mailer
+        .send(&email.to, &email.subject, &email.body)
+        .await
+        .err();
+
+    let attempts = email.attempts + 1;
+    let now = OffsetDateTime::now_utc();
+    let (status, next_attempt_at) = match &error {
+        None => (EmailStatus::Sent, now),
+        Some(_) if attempts >= MAX_ATTEMPTS => (EmailStatus::Failed, now),
+        Some(_) => (EmailStatus::Pending, now + retry_delay(attempts)),
+    };
+
+    match &error {
+        None => tracing::info!("email {} sent on attempt {attempts}", email.id),
+        Some(error) => tracing::warn!("email {} attempt {attempts} failed: {error}", email.id),
+    }
+
+    record_email_attempt(db, email.id, status, next_attempt_at, error).await?;
+
+    Ok(status)
+}
+
+pub async fn send_due(db: &PgPool, mailer: &Mailer) -> Result<i64, sqlx::Error> {
+    let mut sent = 0;
+
+    for email in crate::notifications::repo::claim_due_emails(db, BATCH_SIZE).await? {
+        if attempt_send(db, mailer, &email).await? == EmailStatus::Sent {
+            sent += 1;
+        }
+    }
+
+    Ok(sent)
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateStageRequest {
+    pub name: String,
+    pub position: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateStageRequest {
+    pub name: Option<String>,
+    pub position: Option<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PipelineStageDto {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StageCountDto {
+    pub stage_id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PipelineCountsDto {
+    pub stages: Vec<StageCountDto>,
+    pub unstaged: i64,
+}
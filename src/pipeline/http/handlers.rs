@@ -0,0 +1,225 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::scoped_user::{ScopedUser, scope},
+    pipeline::repo::PipelineStage,
+};
+
+use super::dto::{
+    CreateStageRequest, PipelineCountsDto, PipelineStageDto, StageCountDto, UpdateStageRequest,
+};
+
+const MAX_NAME_LEN: usize = 64;
+
+fn stage_dto(stage: PipelineStage) -> PipelineStageDto {
+    PipelineStageDto {
+        id: stage.id,
+        name: stage.name,
+        position: stage.position,
+        created_at: stage.created_at,
+    }
+}
+
+fn validate_name(name: &str) -> ApiResult<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "name must be 1 to {MAX_NAME_LEN} characters"
+        )));
+    }
+    Ok(name)
+}
+
+fn validate_position(position: Option<i32>) -> ApiResult<Option<i32>> {
+    if position.is_some_and(|position| position < 0) {
+        return Err(ApiError::BadRequest(
+            "position must not be negative".to_string(),
+        ));
+    }
+    Ok(position)
+}
+
+fn map_stage_error(e: sqlx::Error) -> ApiError {
+    match e {
+        sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some("23505") => {
+            ApiError::Conflict("stage with this name already exists".to_string())
+        }
+        e => ApiError::Db(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/pipeline/stages",
+    request_body = CreateStageRequest,
+    responses(
+        (status = 201, description = "Stage created; without a position it goes last", body = PipelineStageDto),
+        (status = 400, description = "Invalid stage payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 409, description = "Stage name already used", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Pipeline"
+)]
+pub async fn create_stage(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateStageRequest>,
+) -> ApiResult<(StatusCode, Json<PipelineStageDto>)> {
+    let name = validate_name(&req.name)?;
+    let position = validate_position(req.position)?;
+
+    let stage = crate::pipeline::repo::create_stage(&state.db, user.id, name, position)
+        .await
+        .map_err(map_stage_error)?;
+
+    Ok((StatusCode::CREATED, Json(stage_dto(stage))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/pipeline/stages",
+    responses(
+        (status = 200, description = "Pipeline stages in order", body = [PipelineStageDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Pipeline"
+)]
+pub async fn list_stages(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<PipelineStageDto>>)> {
+    let stages = crate::pipeline::repo::list_stages(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .into_iter()
+        .map(stage_dto)
+        .collect();
+
+    Ok((StatusCode::OK, Json(stages)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/pipeline/stages/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Stage id")
+    ),
+    request_body = UpdateStageRequest,
+    responses(
+        (status = 200, description = "Stage renamed or moved", body = PipelineStageDto),
+        (status = 400, description = "Invalid stage payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Stage not found", body = crate::error::ErrorBody),
+        (status = 409, description = "Stage name already used", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Pipeline"
+)]
+pub async fn update_stage(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateStageRequest>,
+) -> ApiResult<(StatusCode, Json<PipelineStageDto>)> {
+    if req.name.is_none() && req.position.is_none() {
+        return Err(ApiError::BadRequest(
+            "name or position is required".to_string(),
+        ));
+    }
+
+    let name = req.name.as_deref().map(validate_name).transpose()?;
+    let position = validate_position(req.position)?;
+
+    let stage = crate::pipeline::repo::update_stage_owned(&state.db, user.id, id, name, position)
+        .await
+        .map_err(map_stage_error)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(stage_dto(stage))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/pipeline/stages/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Stage id")
+    ),
+    responses(
+        (status = 204, description = "Stage deleted; its VK users leave the pipeline"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Stage not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Pipeline"
+)]
+pub async fn delete_stage(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let deleted = crate::pipeline::repo::delete_stage_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/pipeline/counts",
+    responses(
+        (status = 200, description = "Number of visible VK users per stage", body = PipelineCountsDto),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Pipeline"
+)]
+pub async fn get_counts(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<PipelineCountsDto>)> {
+    let stages = crate::pipeline::repo::count_by_stage(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .into_iter()
+        .map(|row| StageCountDto {
+            stage_id: row.stage.id,
+            name: row.stage.name,
+            position: row.stage.position,
+            count: row.count,
+        })
+        .collect();
+    let unstaged = crate::pipeline::repo::count_unstaged(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((StatusCode::OK, Json(PipelineCountsDto { stages, unstaged })))
+}
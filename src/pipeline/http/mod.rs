@@ -0,0 +1,21 @@
+use axum::{
+    Router,
+    routing::{get, patch, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{
+    CreateStageRequest, PipelineCountsDto, PipelineStageDto, StageCountDto, UpdateStageRequest,
+};
+pub use handlers::{create_stage, delete_stage, get_counts, list_stages, update_stage};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/stages", post(create_stage).get(list_stages))
+        .route("/stages/{id}", patch(update_stage).delete(delete_stage))
+        .route("/counts", get(get_counts))
+}
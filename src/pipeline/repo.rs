@@ -0,0 +1,431 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct PipelineStage {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageCount {
+    pub stage: PipelineStage,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct VkUserStage {
+    pub vk_user_id: i64,
+    pub stage: PipelineStage,
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageChange {
+    pub id: Uuid,
+    pub from_stage_id: Option<Uuid>,
+    pub from_stage_name: Option<String>,
+    pub to_stage_id: Option<Uuid>,
+    pub to_stage_name: Option<String>,
+    pub changed_at: OffsetDateTime,
+}
+
+pub async fn create_stage(
+    db: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    position: Option<i32>,
+) -> Result<PipelineStage, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO pipeline_stages (user_id, name, position)
+        SELECT $1, $2, COALESCE($3, MAX(position) + 1, 1)
+        FROM pipeline_stages
+        WHERE user_id = $1
+        RETURNING id, name, position, created_at
+        "#,
+        user_id,
+        name,
+        position
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(PipelineStage {
+        id: row.id,
+        name: row.name,
+        position: row.position,
+        created_at: row.created_at,
+    })
+}
+
+pub async fn list_stages(db: &PgPool, user_id: Uuid) -> Result<Vec<PipelineStage>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, name, position, created_at
+        FROM pipeline_stages
+        WHERE user_id = $1
+        ORDER BY position, name
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| PipelineStage {
+            id: row.id,
+            name: row.name,
+            position: row.position,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+pub async fn get_stage_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    stage_id: Uuid,
+) -> Result<Option<PipelineStage>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, name, position, created_at
+        FROM pipeline_stages
+        WHERE id = $1 AND user_id = $2
+        "#,
+        stage_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| PipelineStage {
+        id: row.id,
+        name: row.name,
+        position: row.position,
+        created_at: row.created_at,
+    }))
+}
+
+pub async fn update_stage_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    stage_id: Uuid,
+    name: Option<&str>,
+    position: Option<i32>,
+) -> Result<Option<PipelineStage>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE pipeline_stages
+        SET name = COALESCE($3, name),
+            position = COALESCE($4, position)
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, name, position, created_at
+        "#,
+        stage_id,
+        user_id,
+        name,
+        position
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| PipelineStage {
+        id: row.id,
+        name: row.name,
+        position: row.position,
+        created_at: row.created_at,
+    }))
+}
+
+/// Deletes the stage and records every contact it held as unassigned, so their
+/// stage history does not end on a stage that no longer exists.
+pub async fn delete_stage_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    stage_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    // Locking the stage keeps contacts from being moved into it meanwhile.
+    let stage_name = sqlx::query_scalar!(
+        r#"
+        SELECT name
+        FROM pipeline_stages
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        stage_id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(stage_name) = stage_name else {
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO vk_user_stage_changes (user_id, vk_user_id, from_stage_id, from_stage_name)
+        SELECT user_id, vk_user_id, stage_id, $3
+        FROM vk_user_stages
+        WHERE user_id = $2 AND stage_id = $1
+        "#,
+        stage_id,
+        user_id,
+        stage_name
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM pipeline_stages
+        WHERE id = $1 AND user_id = $2
+        "#,
+        stage_id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn count_by_stage(db: &PgPool, user_id: Uuid) -> Result<Vec<StageCount>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.id, p.name, p.position, p.created_at, COUNT(v.vk_user_id) AS "count!"
+        FROM pipeline_stages AS p
+        LEFT JOIN vk_user_stages AS s ON s.stage_id = p.id
+        LEFT JOIN vk_users AS v
+            ON v.user_id = s.user_id
+           AND v.vk_user_id = s.vk_user_id
+           AND NOT v.is_hidden
+        WHERE p.user_id = $1
+        GROUP BY p.id
+        ORDER BY p.position, p.name
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| StageCount {
+            stage: PipelineStage {
+                id: row.id,
+                name: row.name,
+                position: row.position,
+                created_at: row.created_at,
+            },
+            count: row.count,
+        })
+        .collect())
+}
+
+pub async fn count_unstaged(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM vk_users AS v
+        WHERE v.user_id = $1
+          AND NOT v.is_hidden
+          AND NOT EXISTS (
+              SELECT 1
+              FROM vk_user_stages AS s
+              WHERE s.user_id = v.user_id AND s.vk_user_id = v.vk_user_id
+          )
+        "#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn list_vk_user_stages(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<Vec<VkUserStage>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT s.vk_user_id, s.updated_at, p.id, p.name, p.position, p.created_at
+        FROM vk_user_stages AS s
+        JOIN pipeline_stages AS p ON p.id = s.stage_id
+        WHERE s.user_id = $1 AND s.vk_user_id = ANY($2)
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VkUserStage {
+            vk_user_id: row.vk_user_id,
+            updated_at: row.updated_at,
+            stage: PipelineStage {
+                id: row.id,
+                name: row.name,
+                position: row.position,
+                created_at: row.created_at,
+            },
+        })
+        .collect())
+}
+
+pub async fn set_vk_user_stage(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_id: i64,
+    stage_id: Option<Uuid>,
+) -> Result<Option<bool>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let vk_user = sqlx::query!(
+        r#"
+        SELECT vk_user_id
+        FROM vk_users
+        WHERE user_id = $1 AND vk_user_id = $2
+        FOR UPDATE
+        "#,
+        user_id,
+        vk_user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if vk_user.is_none() {
+        return Ok(None);
+    }
+
+    let current = sqlx::query!(
+        r#"
+        SELECT p.id, p.name
+        FROM vk_user_stages AS s
+        JOIN pipeline_stages AS p ON p.id = s.stage_id
+        WHERE s.user_id = $1 AND s.vk_user_id = $2
+        "#,
+        user_id,
+        vk_user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if current.as_ref().map(|current| current.id) == stage_id {
+        return Ok(Some(false));
+    }
+
+    let to_stage_name = match stage_id {
+        Some(stage_id) => {
+            let name = sqlx::query_scalar!(
+                r#"
+                WITH stage AS (
+                    SELECT id, name
+                    FROM pipeline_stages
+                    WHERE id = $3 AND user_id = $1
+                ),
+                upserted AS (
+                    INSERT INTO vk_user_stages (user_id, vk_user_id, stage_id)
+                    SELECT $1, $2, id
+                    FROM stage
+                    ON CONFLICT (user_id, vk_user_id)
+                    DO UPDATE SET stage_id = EXCLUDED.stage_id, updated_at = now()
+                    RETURNING stage_id
+                )
+                SELECT stage.name
+                FROM stage
+                JOIN upserted ON upserted.stage_id = stage.id
+                "#,
+                user_id,
+                vk_user_id,
+                stage_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            if name.is_none() {
+                return Ok(None);
+            }
+            name
+        }
+        None => {
+            sqlx::query!(
+                r#"
+                DELETE FROM vk_user_stages
+                WHERE user_id = $1 AND vk_user_id = $2
+                "#,
+                user_id,
+                vk_user_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            None
+        }
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO vk_user_stage_changes
+            (user_id, vk_user_id, from_stage_id, from_stage_name, to_stage_id, to_stage_name)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        user_id,
+        vk_user_id,
+        current.as_ref().map(|current| current.id),
+        current.as_ref().map(|current| current.name.as_str()),
+        stage_id,
+        to_stage_name
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(true))
+}
+
+pub async fn list_stage_changes(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_id: i64,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<StageChange>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, from_stage_id, from_stage_name, to_stage_id, to_stage_name, changed_at
+        FROM vk_user_stage_changes
+        WHERE user_id = $1 AND vk_user_id = $2
+        ORDER BY changed_at DESC, id
+        LIMIT $3 OFFSET $4
+        "#,
+        user_id,
+        vk_user_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| StageChange {
+            id: row.id,
+            from_stage_id: row.from_stage_id,
+            from_stage_name: row.from_stage_name,
+            to_stage_id: row.to_stage_id,
+            to_stage_name: row.to_stage_name,
+            changed_at: row.changed_at,
+        })
+        .collect())
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::vk_users::http::VkUserDto;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateSavedSearchRequest {
+    pub name: String,
+    pub sex: Option<i16>,
+    pub city: Option<String>,
+    pub age_min: Option<i16>,
+    pub age_max: Option<i16>,
+    pub open_profile: Option<bool>,
+    pub min_groups: Option<i32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SavedSearchResultsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SavedSearchDto {
+    pub id: Uuid,
+    pub name: String,
+    pub sex: Option<i16>,
+    pub city: Option<String>,
+    pub age_min: Option<i16>,
+    pub age_max: Option<i16>,
+    pub open_profile: Option<bool>,
+    pub min_groups: Option<i32>,
+    pub checked_at: OffsetDateTime,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SavedSearchMatchDto {
+    pub matched_at: OffsetDateTime,
+    pub vk_user: VkUserDto,
+}
@@ -0,0 +1,266 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::scoped_user::{ScopedUser, scope},
+    saved_searches::repo::{SavedSearch, SavedSearchCriteria},
+    vk_users::http::handlers::vk_user_dtos,
+};
+
+use super::dto::{
+    CreateSavedSearchRequest, SavedSearchDto, SavedSearchMatchDto, SavedSearchResultsQuery,
+};
+
+fn to_dto(search: SavedSearch) -> SavedSearchDto {
+    SavedSearchDto {
+        id: search.id,
+        name: search.name,
+        sex: search.criteria.sex,
+        city: search.criteria.city,
+        age_min: search.criteria.age_min,
+        age_max: search.criteria.age_max,
+        open_profile: search.criteria.open_profile,
+        min_groups: search.criteria.min_groups,
+        checked_at: search.checked_at,
+        created_at: search.created_at,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/saved-searches",
+    request_body = CreateSavedSearchRequest,
+    responses(
+        (status = 201, description = "Saved search created", body = SavedSearchDto),
+        (status = 400, description = "Invalid search criteria", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Saved Searches"
+)]
+pub async fn create_saved_search(
+    user: ScopedUser<scope::SearchesWrite>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateSavedSearchRequest>,
+) -> ApiResult<(StatusCode, Json<SavedSearchDto>)> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 128 {
+        return Err(ApiError::BadRequest(
+            "name must be between 1 and 128 characters".to_string(),
+        ));
+    }
+    if request.sex.is_some_and(|sex| sex != 1 && sex != 2) {
+        return Err(ApiError::BadRequest(
+            "sex must be 1 (female) or 2 (male)".to_string(),
+        ));
+    }
+    if request
+        .age_min
+        .into_iter()
+        .chain(request.age_max)
+        .any(|age| !(0..=150).contains(&age))
+    {
+        return Err(ApiError::BadRequest(
+            "age_min and age_max must be between 0 and 150".to_string(),
+        ));
+    }
+    if let (Some(age_min), Some(age_max)) = (request.age_min, request.age_max)
+        && age_min > age_max
+    {
+        return Err(ApiError::BadRequest(
+            "age_min must not be greater than age_max".to_string(),
+        ));
+    }
+    if request.min_groups.is_some_and(|min_groups| min_groups < 1) {
+        return Err(ApiError::BadRequest(
+            "min_groups must be at least 1".to_string(),
+        ));
+    }
+
+    let criteria = SavedSearchCriteria {
+        sex: request.sex,
+        city: request
+            .city
+            .map(|city| city.trim().to_string())
+            .filter(|city| !city.is_empty()),
+        age_min: request.age_min,
+        age_max: request.age_max,
+        open_profile: request.open_profile,
+        min_groups: request.min_groups,
+    };
+
+    let search =
+        crate::saved_searches::repo::create_saved_search(&state.db, user.id, name, criteria)
+            .await
+            .map_err(ApiError::Db)?;
+
+    Ok((StatusCode::CREATED, Json(to_dto(search))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/saved-searches",
+    responses(
+        (status = 200, description = "User saved searches", body = [SavedSearchDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Saved Searches"
+)]
+pub async fn list_saved_searches(
+    user: ScopedUser<scope::SearchesRead>,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<SavedSearchDto>>)> {
+    let rows = crate::saved_searches::repo::list_saved_searches(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((StatusCode::OK, Json(rows.into_iter().map(to_dto).collect())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/saved-searches/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Saved search id")
+    ),
+    responses(
+        (status = 204, description = "Saved search deleted"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Saved search not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Saved Searches"
+)]
+pub async fn delete_saved_search(
+    user: ScopedUser<scope::SearchesWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let deleted = crate::saved_searches::repo::delete_saved_search_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/saved-searches/{id}/results",
+    params(
+        ("id" = Uuid, Path, description = "Saved search id"),
+        SavedSearchResultsQuery
+    ),
+    responses(
+        (status = 200, description = "VK users matching the saved search", body = [crate::vk_users::http::VkUserDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Saved search not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Saved Searches"
+)]
+pub async fn run_saved_search(
+    user: ScopedUser<scope::SearchesRead>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<SavedSearchResultsQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<crate::vk_users::http::VkUserDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let exists = crate::saved_searches::repo::saved_search_exists(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !exists {
+        return Err(ApiError::NotFound);
+    }
+
+    let rows = crate::saved_searches::repo::run_saved_search(&state.db, user.id, id, limit, offset)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(vk_user_dtos(&state.db, user.id, rows).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/saved-searches/{id}/matches",
+    params(
+        ("id" = Uuid, Path, description = "Saved search id"),
+        SavedSearchResultsQuery
+    ),
+    responses(
+        (status = 200, description = "New finds marked by the scheduler, newest first", body = [SavedSearchMatchDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Saved search not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Saved Searches"
+)]
+pub async fn list_saved_search_matches(
+    user: ScopedUser<scope::SearchesRead>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<SavedSearchResultsQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<SavedSearchMatchDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let exists = crate::saved_searches::repo::saved_search_exists(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+    if !exists {
+        return Err(ApiError::NotFound);
+    }
+
+    let rows = crate::saved_searches::repo::list_saved_search_matches(
+        &state.db, user.id, id, limit, offset,
+    )
+    .await
+    .map_err(ApiError::Db)?;
+
+    let (matched_at, vk_users): (Vec<_>, Vec<_>) = rows
+        .into_iter()
+        .map(|row| (row.matched_at, row.vk_user))
+        .unzip();
+    let vk_users = vk_user_dtos(&state.db, user.id, vk_users).await?;
+
+    let matches = matched_at
+        .into_iter()
+        .zip(vk_users)
+        .map(|(matched_at, vk_user)| SavedSearchMatchDto {
+            matched_at,
+            vk_user,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(matches)))
+}
@@ -0,0 +1,23 @@
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{CreateSavedSearchRequest, SavedSearchDto, SavedSearchMatchDto};
+pub use handlers::{
+    create_saved_search, delete_saved_search, list_saved_search_matches, list_saved_searches,
+    run_saved_search,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_saved_search).get(list_saved_searches))
+        .route("/{id}", delete(delete_saved_search))
+        .route("/{id}/results", get(run_saved_search))
+        .route("/{id}/matches", get(list_saved_search_matches))
+}
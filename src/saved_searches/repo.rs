@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::vk_users::repo::VkUser;
+
+#[derive(Debug, Clone, Default)]
+pub struct SavedSearchCriteria {
+    pub sex: Option<i16>,
+    pub city: Option<String>,
+    pub age_min: Option<i16>,
+    pub age_max: Option<i16>,
+    pub open_profile: Option<bool>,
+    pub min_groups: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    #[allow(dead_code)]
+    pub user_id: Uuid,
+    pub name: String,
+    pub criteria: SavedSearchCriteria,
+    pub checked_at: OffsetDateTime,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewSavedSearchMatch {
+    pub search_id: Uuid,
+    pub search_name: String,
+    pub vk_user_id: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedSearchMatch {
+    pub vk_user: VkUser,
+    pub matched_at: OffsetDateTime,
+}
+
+pub async fn create_saved_search(
+    db: &PgPool,
+    user_id: Uuid,
+    name: String,
+    criteria: SavedSearchCriteria,
+) -> Result<SavedSearch, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO saved_searches (
+            user_id,
+            name,
+            sex,
+            city,
+            age_min,
+            age_max,
+            open_profile,
+            min_groups
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING
+            id,
+            user_id,
+            name,
+            sex,
+            city,
+            age_min,
+            age_max,
+            open_profile,
+            min_groups,
+            checked_at,
+            created_at
+        "#,
+        user_id,
+        name,
+        criteria.sex,
+        criteria.city,
+        criteria.age_min,
+        criteria.age_max,
+        criteria.open_profile,
+        criteria.min_groups
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(SavedSearch {
+        id: row.id,
+        user_id: row.user_id,
+        name: row.name,
+        criteria: SavedSearchCriteria {
+            sex: row.sex,
+            city: row.city,
+            age_min: row.age_min,
+            age_max: row.age_max,
+            open_profile: row.open_profile,
+            min_groups: row.min_groups,
+        },
+        checked_at: row.checked_at,
+        created_at: row.created_at,
+    })
+}
+
+pub async fn list_saved_searches(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<SavedSearch>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            user_id,
+            name,
+            sex,
+            city,
+            age_min,
+            age_max,
+            open_profile,
+            min_groups,
+            checked_at,
+            created_at
+        FROM saved_searches
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SavedSearch {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            criteria: SavedSearchCriteria {
+                sex: row.sex,
+                city: row.city,
+                age_min: row.age_min,
+                age_max: row.age_max,
+                open_profile: row.open_profile,
+                min_groups: row.min_groups,
+            },
+            checked_at: row.checked_at,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+pub async fn saved_search_exists(
+    db: &PgPool,
+    user_id: Uuid,
+    search_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM saved_searches WHERE id = $1 AND user_id = $2
+        ) AS "exists!"
+        "#,
+        search_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(exists)
+}
+
+pub async fn delete_saved_search_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    search_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM saved_searches
+        WHERE id = $1 AND user_id = $2
+        "#,
+        search_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+pub async fn run_saved_search(
+    db: &PgPool,
+    user_id: Uuid,
+    search_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<VkUser>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            v.user_id,
+            v.vk_user_id,
+            v.sex,
+            v.first_name,
+            v.last_name,
+            v.city,
+            v.first_seen_at,
+            v.last_seen_at,
+            v.is_closed,
+            v.screen_name,
+            v.can_access_closed,
+            v.about,
+            v.status,
+            v.bdate,
+            v.photo,
+            v.is_favorite,
+            v.is_hidden
+        FROM saved_searches AS s
+        JOIN vk_users AS v ON v.user_id = s.user_id
+        WHERE s.id = $2
+          AND s.user_id = $1
+          AND NOT v.is_hidden
+          AND (s.sex IS NULL OR v.sex = s.sex)
+          AND (s.city IS NULL OR lower(v.city) = lower(s.city))
+          AND (s.age_min IS NULL OR vk_bdate_age(v.bdate) >= s.age_min)
+          AND (s.age_max IS NULL OR vk_bdate_age(v.bdate) <= s.age_max)
+          AND (s.open_profile IS NULL OR v.is_closed = NOT s.open_profile)
+          AND (
+              s.min_groups IS NULL
+              OR (
+                  SELECT COUNT(DISTINCT src.group_id)
+                  FROM vk_user_sources AS src
+                  WHERE src.user_id = v.user_id AND src.vk_user_id = v.vk_user_id
+              ) >= s.min_groups
+          )
+        ORDER BY v.last_seen_at DESC, v.vk_user_id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        user_id,
+        search_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VkUser {
+            user_id: row.user_id,
+            vk_user_id: row.vk_user_id,
+            sex: row.sex,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            city: row.city,
+            first_seen_at: row.first_seen_at,
+            last_seen_at: row.last_seen_at,
+            is_closed: row.is_closed,
+            screen_name: row.screen_name,
+            can_access_closed: row.can_access_closed,
+            about: row.about,
+            status: row.status,
+            bdate: row.bdate,
+            photo: row.photo,
+            is_favorite: row.is_favorite,
+            is_hidden: row.is_hidden,
+        })
+        .collect())
+}
+
+pub async fn mark_new_matches(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<NewSavedSearchMatch>, sqlx::Error> {
+    // Rows are picked by the transaction that last saw them, not by
+    // `last_seen_at`: a crawl can commit long after a newer one, with an older
+    // timestamp. Only transactions below the snapshot's xmin have certainly
+    // finished, so the cursor stops there and anything still in flight is
+    // picked up by a later run. Everything runs in one statement so the
+    // matches and the cursor come from the same snapshot.
+    let rows = sqlx::query!(
+        r#"
+        WITH horizon AS (
+            SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint AS xmin
+        ),
+        inserted AS (
+            INSERT INTO saved_search_matches (search_id, user_id, vk_user_id)
+            SELECT s.id, v.user_id, v.vk_user_id
+            FROM saved_searches AS s
+            CROSS JOIN horizon AS h
+            JOIN vk_users AS v
+                ON v.user_id = s.user_id
+               AND v.seen_xact >= s.checked_xact
+               AND v.seen_xact < h.xmin
+            WHERE s.user_id = $1
+              AND NOT v.is_hidden
+              AND (s.sex IS NULL OR v.sex = s.sex)
+              AND (s.city IS NULL OR lower(v.city) = lower(s.city))
+              AND (s.age_min IS NULL OR vk_bdate_age(v.bdate) >= s.age_min)
+              AND (s.age_max IS NULL OR vk_bdate_age(v.bdate) <= s.age_max)
+              AND (s.open_profile IS NULL OR v.is_closed = NOT s.open_profile)
+              AND (
+                  s.min_groups IS NULL
+                  OR (
+                      SELECT COUNT(DISTINCT src.group_id)
+                      FROM vk_user_sources AS src
+                      WHERE src.user_id = v.user_id AND src.vk_user_id = v.vk_user_id
+                  ) >= s.min_groups
+              )
+            ON CONFLICT (search_id, vk_user_id) DO NOTHING
+            RETURNING search_id, vk_user_id
+        ),
+        advanced AS (
+            UPDATE saved_searches AS s
+            SET checked_xact = GREATEST(s.checked_xact, h.xmin),
+                checked_at = now()
+            FROM horizon AS h
+            WHERE s.user_id = $1
+        )
+        SELECT i.search_id, s.name AS search_name, i.vk_user_id
+        FROM inserted AS i
+        JOIN saved_searches AS s ON s.id = i.search_id
+        ORDER BY i.search_id, i.vk_user_id
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| NewSavedSearchMatch {
+            search_id: row.search_id,
+            search_name: row.search_name,
+            vk_user_id: row.vk_user_id,
+        })
+        .collect())
+}
+
+pub async fn matched_vk_user_ids(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<HashSet<i64>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let rows = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT m.vk_user_id
+        FROM saved_search_matches AS m
+        JOIN vk_users AS v ON v.user_id = m.user_id AND v.vk_user_id = m.vk_user_id
+        WHERE m.user_id = $1 AND m.vk_user_id = ANY($2) AND NOT v.is_hidden
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+pub async fn list_saved_search_matches(
+    db: &PgPool,
+    user_id: Uuid,
+    search_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SavedSearchMatch>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            m.matched_at,
+            v.user_id,
+            v.vk_user_id,
+            v.sex,
+            v.first_name,
+            v.last_name,
+            v.city,
+            v.first_seen_at,
+            v.last_seen_at,
+            v.is_closed,
+            v.screen_name,
+            v.can_access_closed,
+            v.about,
+            v.status,
+            v.bdate,
+            v.photo,
+            v.is_favorite,
+            v.is_hidden
+        FROM saved_search_matches AS m
+        JOIN vk_users AS v ON v.user_id = m.user_id AND v.vk_user_id = m.vk_user_id
+        WHERE m.search_id = $2 AND m.user_id = $1 AND NOT v.is_hidden
+        ORDER BY m.matched_at DESC, v.vk_user_id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        user_id,
+        search_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SavedSearchMatch {
+            matched_at: row.matched_at,
+            vk_user: VkUser {
+                user_id: row.user_id,
+                vk_user_id: row.vk_user_id,
+                sex: row.sex,
+                first_name: row.first_name,
+                last_name: row.last_name,
+                city: row.city,
+                first_seen_at: row.first_seen_at,
+                last_seen_at: row.last_seen_at,
+                is_closed: row.is_closed,
+                screen_name: row.screen_name,
+                can_access_closed: row.can_access_closed,
+                about: row.about,
+                status: row.status,
+                bdate: row.bdate,
+                photo: row.photo,
+                is_favorite: row.is_favorite,
+                is_hidden: row.is_hidden,
+            },
+        })
+        .collect())
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use crate::{
+    notifications::mailer::Mailer,
+    webhooks::{repo::WebhookEventKind, sender::WebhookClient},
+};
+
+const TICK: Duration = Duration::from_secs(60);
+const STREAM_EVENTS_RETENTION: time::Duration = time::Duration::days(7);
+const LOGIN_THROTTLE_RETENTION: time::Duration = time::Duration::days(1);
+
+pub async fn run_due_searches(db: &PgPool) -> Result<i64, sqlx::Error> {
+    let mut matched = 0;
+
+    for user_id in crate::user_settings::repo::claim_due_search_runs(db).await? {
+        let new_matches = match crate::saved_searches::repo::mark_new_matches(db, user_id).await {
+            Ok(new_matches) => new_matches,
+            Err(e) => {
+                tracing::error!("user {user_id}: saved search run failed: {e}");
+                continue;
+            }
+        };
+        if !new_matches.is_empty() {
+            tracing::info!(
+                "user {user_id}: {} new saved search matches",
+                new_matches.len()
+            );
+        }
+
+        if let Err(e) =
+            crate::notifications::sender::enqueue_match_email(db, user_id, &new_matches).await
+        {
+            tracing::error!("user {user_id}: failed to queue match email: {e}");
+        }
+
+        for new_match in &new_matches {
+            if let Err(e) = crate::webhooks::repo::emit_event(
+                db,
+                user_id,
+                WebhookEventKind::VkUserMatched,
+                json!({
+                    "search_id": new_match.search_id,
+                    "search_name": new_match.search_name,
+                    "vk_user_id": new_match.vk_user_id,
+                }),
+            )
+            .await
+            {
+                tracing::error!("user {user_id}: failed to emit match webhook: {e}");
+            }
+        }
+
+        matched += new_matches.len() as i64;
+    }
+
+    Ok(matched)
+}
+
+async fn tick(db: &PgPool, client: &WebhookClient, mailer: Option<&Mailer>) {
+    if let Err(e) = run_due_searches(db).await {
+        tracing::error!("scheduler search run failed: {e}");
+    }
+    if let Err(e) = crate::webhooks::sender::deliver_due(db, client).await {
+        tracing::error!("scheduler webhook delivery failed: {e}");
+    }
+    let cutoff = time::OffsetDateTime::now_utc() - STREAM_EVENTS_RETENTION;
+    if let Err(e) = crate::event_stream::repo::prune_events(db, cutoff).await {
+        tracing::error!("scheduler stream event pruning failed: {e}");
+    }
+    let cutoff = time::OffsetDateTime::now_utc() - LOGIN_THROTTLE_RETENTION;
+    if let Err(e) = crate::auth::repo::prune_login_throttles(db, cutoff).await {
+        tracing::error!("scheduler login throttle pruning failed: {e}");
+    }
+    if let Err(e) = crate::auth::repo::prune_login_challenges(db, cutoff).await {
+        tracing::error!("scheduler login challenge pruning failed: {e}");
+    }
+    if let Some(mailer) = mailer {
+        if let Err(e) = crate::notifications::sender::enqueue_due_digests(db).await {
+            tracing::error!("scheduler digest run failed: {e}");
+        }
+        if let Err(e) = crate::notifications::sender::send_due(db, mailer).await {
+            tracing::error!("scheduler email delivery failed: {e}");
+        }
+    }
+}
+
+pub fn spawn(db: PgPool, client: WebhookClient, mailer: Option<Mailer>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            tick(&db, &client, mailer.as_ref()).await;
+        }
+    })
+}
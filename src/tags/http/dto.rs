@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TagDto {
+    pub id: Uuid,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: OffsetDateTime,
+}
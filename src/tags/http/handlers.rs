@@ -0,0 +1,201 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::scoped_user::{ScopedUser, scope},
+    tags::repo::Tag,
+};
+
+use super::dto::{CreateTagRequest, TagDto, UpdateTagRequest};
+
+const MAX_NAME_LEN: usize = 64;
+
+pub(crate) fn tag_dto(tag: Tag) -> TagDto {
+    TagDto {
+        id: tag.id,
+        name: tag.name,
+        color: tag.color,
+        created_at: tag.created_at,
+    }
+}
+
+fn validate_name(name: &str) -> ApiResult<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "name must be 1 to {MAX_NAME_LEN} characters"
+        )));
+    }
+    Ok(name)
+}
+
+fn validate_color(color: &str) -> ApiResult<String> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(ApiError::BadRequest(
+            "color must be a hex color like #ff8800".to_string(),
+        ));
+    }
+    Ok(color.to_ascii_lowercase())
+}
+
+fn map_tag_error(e: sqlx::Error) -> ApiError {
+    match e {
+        sqlx::Error::Database(db_error) if db_error.code().as_deref() == Some("23505") => {
+            ApiError::Conflict("tag with this name already exists".to_string())
+        }
+        e => ApiError::Db(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags",
+    request_body = CreateTagRequest,
+    responses(
+        (status = 201, description = "Tag created", body = TagDto),
+        (status = 400, description = "Invalid tag payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 409, description = "Tag name already used", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Tags"
+)]
+pub async fn create_tag(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateTagRequest>,
+) -> ApiResult<(StatusCode, Json<TagDto>)> {
+    let name = validate_name(&req.name)?;
+    let color = req.color.as_deref().map(validate_color).transpose()?;
+
+    let tag = crate::tags::repo::create_tag(&state.db, user.id, name, color.as_deref())
+        .await
+        .map_err(map_tag_error)?;
+
+    Ok((StatusCode::CREATED, Json(tag_dto(tag))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    responses(
+        (status = 200, description = "Current user tags", body = [TagDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Tags"
+)]
+pub async fn list_tags(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<TagDto>>)> {
+    let tags = crate::tags::repo::list_tags(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?
+        .into_iter()
+        .map(tag_dto)
+        .collect();
+
+    Ok((StatusCode::OK, Json(tags)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/tags/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Tag id")
+    ),
+    request_body = UpdateTagRequest,
+    responses(
+        (status = 200, description = "Tag renamed or recolored; an empty color clears it", body = TagDto),
+        (status = 400, description = "Invalid tag payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Tag not found", body = crate::error::ErrorBody),
+        (status = 409, description = "Tag name already used", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Tags"
+)]
+pub async fn update_tag(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateTagRequest>,
+) -> ApiResult<(StatusCode, Json<TagDto>)> {
+    if req.name.is_none() && req.color.is_none() {
+        return Err(ApiError::BadRequest(
+            "name or color is required".to_string(),
+        ));
+    }
+
+    let name = req.name.as_deref().map(validate_name).transpose()?;
+    let color = match req.color.as_deref() {
+        None => None,
+        Some("") => Some(None),
+        Some(color) => Some(Some(validate_color(color)?)),
+    };
+
+    let tag = crate::tags::repo::update_tag_owned(
+        &state.db,
+        user.id,
+        id,
+        name,
+        color.as_ref().map(Option::as_deref),
+    )
+    .await
+    .map_err(map_tag_error)?
+    .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(tag_dto(tag))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Tag id")
+    ),
+    responses(
+        (status = 204, description = "Tag deleted and unassigned"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Tag not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Tags"
+)]
+pub async fn delete_tag(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let deleted = crate::tags::repo::delete_tag_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
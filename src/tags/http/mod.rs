@@ -0,0 +1,18 @@
+use axum::{
+    Router,
+    routing::{patch, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{CreateTagRequest, TagDto, UpdateTagRequest};
+pub use handlers::{create_tag, delete_tag, list_tags, update_tag};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_tag).get(list_tags))
+        .route("/{id}", patch(update_tag).delete(delete_tag))
+}
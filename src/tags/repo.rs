@@ -0,0 +1,204 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct VkUserTag {
+    pub vk_user_id: i64,
+    pub tag: Tag,
+}
+
+pub async fn create_tag(
+    db: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    color: Option<&str>,
+) -> Result<Tag, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO vk_user_tags (user_id, name, color)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, color, created_at
+        "#,
+        user_id,
+        name,
+        color
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(Tag {
+        id: row.id,
+        name: row.name,
+        color: row.color,
+        created_at: row.created_at,
+    })
+}
+
+pub async fn list_tags(db: &PgPool, user_id: Uuid) -> Result<Vec<Tag>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, name, color, created_at
+        FROM vk_user_tags
+        WHERE user_id = $1
+        ORDER BY name
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Tag {
+            id: row.id,
+            name: row.name,
+            color: row.color,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+pub async fn update_tag_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    tag_id: Uuid,
+    name: Option<&str>,
+    color: Option<Option<&str>>,
+) -> Result<Option<Tag>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE vk_user_tags
+        SET name = COALESCE($3, name),
+            color = CASE WHEN $4 THEN $5 ELSE color END
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, name, color, created_at
+        "#,
+        tag_id,
+        user_id,
+        name,
+        color.is_some(),
+        color.flatten()
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| Tag {
+        id: row.id,
+        name: row.name,
+        color: row.color,
+        created_at: row.created_at,
+    }))
+}
+
+pub async fn delete_tag_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    tag_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM vk_user_tags
+        WHERE id = $1 AND user_id = $2
+        "#,
+        tag_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn assign_tag(
+    db: &PgPool,
+    user_id: Uuid,
+    tag_id: Uuid,
+    vk_user_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query_scalar!(
+        r#"
+        INSERT INTO vk_user_tag_assignments (tag_id, user_id, vk_user_id)
+        SELECT t.id, v.user_id, v.vk_user_id
+        FROM vk_user_tags AS t
+        JOIN vk_users AS v ON v.user_id = t.user_id
+        WHERE t.id = $1 AND t.user_id = $2 AND v.vk_user_id = $3
+        ON CONFLICT (tag_id, vk_user_id)
+        DO UPDATE SET created_at = vk_user_tag_assignments.created_at
+        RETURNING tag_id
+        "#,
+        tag_id,
+        user_id,
+        vk_user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.is_some())
+}
+
+pub async fn unassign_tag(
+    db: &PgPool,
+    user_id: Uuid,
+    tag_id: Uuid,
+    vk_user_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM vk_user_tag_assignments
+        WHERE tag_id = $1 AND user_id = $2 AND vk_user_id = $3
+        "#,
+        tag_id,
+        user_id,
+        vk_user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn list_vk_user_tags(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<Vec<VkUserTag>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT a.vk_user_id, t.id, t.name, t.color, t.created_at
+        FROM vk_user_tag_assignments AS a
+        JOIN vk_user_tags AS t ON t.id = a.tag_id
+        WHERE a.user_id = $1 AND a.vk_user_id = ANY($2)
+        ORDER BY a.vk_user_id, t.name
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VkUserTag {
+            vk_user_id: row.vk_user_id,
+            tag: Tag {
+                id: row.id,
+                name: row.name,
+                color: row.color,
+                created_at: row.created_at,
+            },
+        })
+        .collect())
+}
@@ -5,10 +5,18 @@ use utoipa::ToSchema;
 #[derive(Serialize, ToSchema)]
 pub struct UserSettingsDto {
     pub search_interval_minutes: i32,
+    pub email_notify_matches: bool,
+    pub email_notify_crawl_failures: bool,
+    pub email_notify_security: bool,
+    pub email_digest: &'static str,
     pub updated_at: OffsetDateTime,
 }
 
 #[derive(Deserialize, ToSchema)]
 pub struct UpdateUserSettingsRequest {
     pub search_interval_minutes: Option<i32>,
+    pub email_notify_matches: Option<bool>,
+    pub email_notify_crawl_failures: Option<bool>,
+    pub email_notify_security: Option<bool>,
+    pub email_digest: Option<String>,
 }
@@ -4,10 +4,22 @@ use crate::{
     AppState,
     error::{ApiError, ApiResult},
     extractors::auth_user::AuthUser,
+    user_settings::repo::{EmailDigestFrequency, UserSettings, UserSettingsUpdate},
 };
 
 use super::dto::{UpdateUserSettingsRequest, UserSettingsDto};
 
+fn settings_dto(settings: UserSettings) -> UserSettingsDto {
+    UserSettingsDto {
+        search_interval_minutes: settings.search_interval_minutes,
+        email_notify_matches: settings.email_notify_matches,
+        email_notify_crawl_failures: settings.email_notify_crawl_failures,
+        email_notify_security: settings.email_notify_security,
+        email_digest: settings.email_digest.as_str(),
+        updated_at: settings.updated_at,
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/settings",
@@ -29,13 +41,7 @@ pub async fn get_user_settings(
         .await
         .map_err(ApiError::Db)?;
 
-    Ok((
-        StatusCode::OK,
-        Json(UserSettingsDto {
-            search_interval_minutes: settings.search_interval_minutes,
-            updated_at: settings.updated_at,
-        }),
-    ))
+    Ok((StatusCode::OK, Json(settings_dto(settings))))
 }
 
 #[utoipa::path(
@@ -58,29 +64,47 @@ pub async fn update_user_settings(
     State(state): State<AppState>,
     Json(req): Json<UpdateUserSettingsRequest>,
 ) -> ApiResult<(StatusCode, Json<UserSettingsDto>)> {
-    let search_interval_minutes = req.search_interval_minutes.ok_or(ApiError::BadRequest(
-        "search_interval_minutes is required".to_string(),
-    ))?;
+    if req.search_interval_minutes.is_none()
+        && req.email_notify_matches.is_none()
+        && req.email_notify_crawl_failures.is_none()
+        && req.email_notify_security.is_none()
+        && req.email_digest.is_none()
+    {
+        return Err(ApiError::BadRequest(
+            "at least one setting is required".to_string(),
+        ));
+    }
 
-    if search_interval_minutes < 30 {
+    if req
+        .search_interval_minutes
+        .is_some_and(|minutes| minutes < 30)
+    {
         return Err(ApiError::BadRequest(
             "search_interval_minutes must be at least 30".to_string(),
         ));
     }
 
-    let settings = crate::user_settings::repo::update_user_settings(
-        &state.db,
-        user.id,
-        search_interval_minutes,
-    )
-    .await
-    .map_err(ApiError::Db)?;
+    let email_digest = req
+        .email_digest
+        .as_deref()
+        .map(|value| {
+            EmailDigestFrequency::parse(value).ok_or(ApiError::BadRequest(
+                "email_digest must be one of off, daily, weekly".to_string(),
+            ))
+        })
+        .transpose()?;
+
+    let update = UserSettingsUpdate {
+        search_interval_minutes: req.search_interval_minutes,
+        email_notify_matches: req.email_notify_matches,
+        email_notify_crawl_failures: req.email_notify_crawl_failures,
+        email_notify_security: req.email_notify_security,
+        email_digest,
+    };
+
+    let settings = crate::user_settings::repo::update_user_settings(&state.db, user.id, &update)
+        .await
+        .map_err(ApiError::Db)?;
 
-    Ok((
-        StatusCode::OK,
-        Json(UserSettingsDto {
-            search_interval_minutes: settings.search_interval_minutes,
-            updated_at: settings.updated_at,
-        }),
-    ))
+    Ok((StatusCode::OK, Json(settings_dto(settings))))
 }
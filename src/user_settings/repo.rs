@@ -2,14 +2,58 @@ use sqlx::PgPool;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailDigestFrequency {
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl EmailDigestFrequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailDigestFrequency::Off => "off",
+            EmailDigestFrequency::Daily => "daily",
+            EmailDigestFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(EmailDigestFrequency::Off),
+            "daily" => Some(EmailDigestFrequency::Daily),
+            "weekly" => Some(EmailDigestFrequency::Weekly),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UserSettings {
     #[allow(dead_code)]
     pub user_id: Uuid,
     pub search_interval_minutes: i32,
+    pub email_notify_matches: bool,
+    pub email_notify_crawl_failures: bool,
+    pub email_notify_security: bool,
+    pub email_digest: EmailDigestFrequency,
     pub updated_at: OffsetDateTime,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct UserSettingsUpdate {
+    pub search_interval_minutes: Option<i32>,
+    pub email_notify_matches: Option<bool>,
+    pub email_notify_crawl_failures: Option<bool>,
+    pub email_notify_security: Option<bool>,
+    pub email_digest: Option<EmailDigestFrequency>,
+}
+
+fn parse_digest(value: &str) -> Result<EmailDigestFrequency, sqlx::Error> {
+    EmailDigestFrequency::parse(value)
+        .ok_or_else(|| sqlx::Error::Decode(format!("unknown email_digest: {value}").into()))
+}
+
 pub async fn get_user_settings(db: &PgPool, user_id: Uuid) -> Result<UserSettings, sqlx::Error> {
     let _ = sqlx::query!(
         r#"
@@ -24,7 +68,14 @@ pub async fn get_user_settings(db: &PgPool, user_id: Uuid) -> Result<UserSetting
 
     let row = sqlx::query!(
         r#"
-        SELECT user_id, search_interval_minutes, updated_at
+        SELECT
+            user_id,
+            search_interval_minutes,
+            email_notify_matches,
+            email_notify_crawl_failures,
+            email_notify_security,
+            email_digest,
+            updated_at
         FROM user_settings
         WHERE user_id = $1
         "#,
@@ -36,6 +87,10 @@ pub async fn get_user_settings(db: &PgPool, user_id: Uuid) -> Result<UserSetting
     Ok(UserSettings {
         user_id: row.user_id,
         search_interval_minutes: row.search_interval_minutes,
+        email_notify_matches: row.email_notify_matches,
+        email_notify_crawl_failures: row.email_notify_crawl_failures,
+        email_notify_security: row.email_notify_security,
+        email_digest: parse_digest(&row.email_digest)?,
         updated_at: row.updated_at,
     })
 }
@@ -43,20 +98,49 @@ pub async fn get_user_settings(db: &PgPool, user_id: Uuid) -> Result<UserSetting
 pub async fn update_user_settings(
     db: &PgPool,
     user_id: Uuid,
-    search_interval_minutes: i32,
+    update: &UserSettingsUpdate,
 ) -> Result<UserSettings, sqlx::Error> {
     let row = sqlx::query!(
         r#"
-        INSERT INTO user_settings (user_id, search_interval_minutes)
-        VALUES ($1, $2)
+        INSERT INTO user_settings (
+            user_id,
+            search_interval_minutes,
+            email_notify_matches,
+            email_notify_crawl_failures,
+            email_notify_security,
+            email_digest
+        )
+        VALUES (
+            $1,
+            COALESCE($2, 60),
+            COALESCE($3, false),
+            COALESCE($4, false),
+            COALESCE($5, true),
+            COALESCE($6, 'off')
+        )
         ON CONFLICT (user_id)
         DO UPDATE SET
-            search_interval_minutes = EXCLUDED.search_interval_minutes,
+            search_interval_minutes = COALESCE($2, user_settings.search_interval_minutes),
+            email_notify_matches = COALESCE($3, user_settings.email_notify_matches),
+            email_notify_crawl_failures = COALESCE($4, user_settings.email_notify_crawl_failures),
+            email_notify_security = COALESCE($5, user_settings.email_notify_security),
+            email_digest = COALESCE($6, user_settings.email_digest),
             updated_at = now()
-        RETURNING user_id, search_interval_minutes, updated_at
+        RETURNING
+            user_id,
+            search_interval_minutes,
+            email_notify_matches,
+            email_notify_crawl_failures,
+            email_notify_security,
+            email_digest,
+            updated_at
         "#,
         user_id,
-        search_interval_minutes
+        update.search_interval_minutes,
+        update.email_notify_matches,
+        update.email_notify_crawl_failures,
+        update.email_notify_security,
+        update.email_digest.map(EmailDigestFrequency::as_str)
     )
     .fetch_one(db)
     .await?;
@@ -64,6 +148,26 @@ pub async fn update_user_settings(
     Ok(UserSettings {
         user_id: row.user_id,
         search_interval_minutes: row.search_interval_minutes,
+        email_notify_matches: row.email_notify_matches,
+        email_notify_crawl_failures: row.email_notify_crawl_failures,
+        email_notify_security: row.email_notify_security,
+        email_digest: parse_digest(&row.email_digest)?,
         updated_at: row.updated_at,
     })
 }
+
+pub async fn claim_due_search_runs(db: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows = sqlx::query_scalar!(
+        r#"
+        UPDATE user_settings
+        SET last_search_run_at = now()
+        WHERE last_search_run_at IS NULL
+           OR last_search_run_at + make_interval(mins => search_interval_minutes) <= now()
+        RETURNING user_id
+        "#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows)
+}
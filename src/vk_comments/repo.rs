@@ -1,12 +1,25 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::vk_posts::repo::VkPostKey;
+use crate::vk_user_sources::repo::{
+    NewVkUserSource, VkUserSourceKind, comment_ref, record_vk_user_sources,
+};
+
 #[derive(Debug, Clone)]
 pub struct UpsertVkCommentsResult {
     pub inserted: i64,
     pub updated: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReconcileVkCommentsResult {
+    pub inserted: i64,
+    pub updated: i64,
+    pub deleted: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct NewVkComment {
     pub group_id: i64,
@@ -34,12 +47,25 @@ pub struct VkComment {
     pub from_id: i64,
     pub created_date: i64,
     pub comment_text: Option<String>,
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
 pub async fn upsert_vk_comments(
     db: &PgPool,
     user_id: Uuid,
     comments: &[NewVkComment],
+) -> Result<UpsertVkCommentsResult, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let result = upsert_vk_comments_in(&mut tx, user_id, comments).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+pub(crate) async fn upsert_vk_comments_in(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    comments: &[NewVkComment],
 ) -> Result<UpsertVkCommentsResult, sqlx::Error> {
     if comments.is_empty() {
         return Ok(UpsertVkCommentsResult {
@@ -95,7 +121,8 @@ pub async fn upsert_vk_comments(
         DO UPDATE SET
             from_id = EXCLUDED.from_id,
             created_date = EXCLUDED.created_date,
-            comment_text = EXCLUDED.comment_text
+            comment_text = EXCLUDED.comment_text,
+            deleted_at = NULL
         RETURNING (xmax = 0) AS "inserted!"
         "#,
         user_id,
@@ -106,15 +133,70 @@ pub async fn upsert_vk_comments(
         &created_dates,
         &comment_texts as &[Option<String>]
     )
-    .fetch_all(db)
+    .fetch_all(&mut *conn)
     .await?;
 
+    let seen_at = OffsetDateTime::now_utc();
+    let sources: Vec<NewVkUserSource> = comments
+        .iter()
+        .map(|comment| NewVkUserSource {
+            vk_user_id: comment.from_id,
+            group_id: comment.group_id,
+            source_kind: VkUserSourceKind::Commenter,
+            source_ref: comment_ref(comment.post_id, comment.comment_id),
+            seen_at,
+        })
+        .collect();
+    record_vk_user_sources(&mut *conn, user_id, &sources).await?;
+
     let inserted = rows.iter().filter(|row| row.inserted).count() as i64;
     let updated = rows.len() as i64 - inserted;
 
     Ok(UpsertVkCommentsResult { inserted, updated })
 }
 
+pub async fn reconcile_vk_comments(
+    db: &PgPool,
+    user_id: Uuid,
+    post: &VkPostKey,
+    comments: &[NewVkComment],
+) -> Result<ReconcileVkCommentsResult, sqlx::Error> {
+    let seen_comment_ids: Vec<i64> = comments
+        .iter()
+        .filter(|comment| comment.group_id == post.group_id && comment.post_id == post.post_id)
+        .map(|comment| comment.comment_id)
+        .collect();
+
+    let mut tx = db.begin().await?;
+    let upserted = upsert_vk_comments_in(&mut tx, user_id, comments).await?;
+
+    let deleted = sqlx::query!(
+        r#"
+        UPDATE vk_comments
+        SET deleted_at = now()
+        WHERE user_id = $1
+          AND group_id = $2
+          AND post_id = $3
+          AND deleted_at IS NULL
+          AND NOT (comment_id = ANY($4::bigint[]))
+        "#,
+        user_id,
+        post.group_id,
+        post.post_id,
+        &seen_comment_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(ReconcileVkCommentsResult {
+        inserted: upserted.inserted,
+        updated: upserted.updated,
+        deleted: deleted.rows_affected() as i64,
+    })
+}
+
 pub async fn delete_vk_comments(
     db: &PgPool,
     user_id: Uuid,
@@ -170,7 +252,8 @@ pub async fn list_vk_comments(
             comment_id,
             from_id,
             created_date,
-            comment_text
+            comment_text,
+            deleted_at
         FROM vk_comments
         WHERE user_id = $1
         ORDER BY created_date DESC, comment_id DESC
@@ -193,6 +276,7 @@ pub async fn list_vk_comments(
             from_id: row.from_id,
             created_date: row.created_date,
             comment_text: row.comment_text,
+            deleted_at: row.deleted_at,
         })
         .collect())
 }
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VkEventsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub kind: Option<String>,
+    pub vk_user_id: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkEventDto {
+    pub kind: &'static str,
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub post_id: i64,
+    pub comment_id: Option<i64>,
+    pub occurred_at: OffsetDateTime,
+}
@@ -0,0 +1,71 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::scoped_user::{ScopedUser, scope},
+    vk_events::repo::{VkEventKind, VkEventsFilter},
+};
+
+use super::dto::{VkEventDto, VkEventsQuery};
+
+#[utoipa::path(
+    get,
+    path = "/vk-events",
+    params(VkEventsQuery),
+    responses(
+        (status = 200, description = "Deleted posts and comments, removed likes", body = [VkEventDto]),
+        (status = 400, description = "Invalid filter", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Events"
+)]
+pub async fn list_vk_events(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    Query(q): Query<VkEventsQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<VkEventDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+    let kind = q
+        .kind
+        .as_deref()
+        .map(|value| {
+            VkEventKind::parse(value).ok_or(ApiError::BadRequest(
+                "kind must be one of post_deleted, comment_deleted, post_like_removed, comment_like_removed"
+                    .to_string(),
+            ))
+        })
+        .transpose()?;
+
+    let filter = VkEventsFilter {
+        kind,
+        vk_user_id: q.vk_user_id,
+    };
+
+    let rows = crate::vk_events::repo::list_vk_events(&state.db, user.id, &filter, limit, offset)
+        .await
+        .map_err(ApiError::Db)?;
+
+    let events = rows
+        .into_iter()
+        .map(|row| VkEventDto {
+            kind: row.kind.as_str(),
+            vk_user_id: row.vk_user_id,
+            group_id: row.group_id,
+            post_id: row.post_id,
+            comment_id: row.comment_id,
+            occurred_at: row.occurred_at,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(events)))
+}
@@ -0,0 +1,13 @@
+use axum::{Router, routing::get};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::VkEventDto;
+pub use handlers::list_vk_events;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(list_vk_events))
+}
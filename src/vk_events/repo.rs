@@ -0,0 +1,137 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VkEventKind {
+    PostDeleted,
+    CommentDeleted,
+    PostLikeRemoved,
+    CommentLikeRemoved,
+}
+
+impl VkEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VkEventKind::PostDeleted => "post_deleted",
+            VkEventKind::CommentDeleted => "comment_deleted",
+            VkEventKind::PostLikeRemoved => "post_like_removed",
+            VkEventKind::CommentLikeRemoved => "comment_like_removed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "post_deleted" => Some(VkEventKind::PostDeleted),
+            "comment_deleted" => Some(VkEventKind::CommentDeleted),
+            "post_like_removed" => Some(VkEventKind::PostLikeRemoved),
+            "comment_like_removed" => Some(VkEventKind::CommentLikeRemoved),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VkEventsFilter {
+    pub kind: Option<VkEventKind>,
+    pub vk_user_id: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VkEvent {
+    pub kind: VkEventKind,
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub post_id: i64,
+    pub comment_id: Option<i64>,
+    pub occurred_at: OffsetDateTime,
+}
+
+pub async fn list_vk_events(
+    db: &PgPool,
+    user_id: Uuid,
+    filter: &VkEventsFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<VkEvent>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            kind AS "kind!",
+            vk_user_id AS "vk_user_id!",
+            group_id AS "group_id!",
+            post_id AS "post_id!",
+            comment_id,
+            occurred_at AS "occurred_at!"
+        FROM (
+            SELECT
+                'post_deleted' AS kind,
+                from_id AS vk_user_id,
+                group_id,
+                post_id,
+                NULL::bigint AS comment_id,
+                deleted_at AS occurred_at
+            FROM vk_posts
+            WHERE user_id = $1 AND deleted_at IS NOT NULL
+            UNION ALL
+            SELECT
+                'comment_deleted',
+                from_id,
+                group_id,
+                post_id,
+                comment_id,
+                deleted_at
+            FROM vk_comments
+            WHERE user_id = $1 AND deleted_at IS NOT NULL
+            UNION ALL
+            SELECT
+                'post_like_removed',
+                vk_user_id,
+                group_id,
+                post_id,
+                NULL::bigint,
+                removed_at
+            FROM vk_post_likes
+            WHERE user_id = $1 AND removed_at IS NOT NULL
+            UNION ALL
+            SELECT
+                'comment_like_removed',
+                vk_user_id,
+                group_id,
+                post_id,
+                comment_id,
+                removed_at
+            FROM vk_comment_likes
+            WHERE user_id = $1 AND removed_at IS NOT NULL
+        ) AS events
+        WHERE ($4::text IS NULL OR kind = $4)
+          AND ($5::bigint IS NULL OR vk_user_id = $5)
+        ORDER BY occurred_at DESC, group_id DESC, post_id DESC, comment_id DESC NULLS LAST
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        limit,
+        offset,
+        filter.kind.map(VkEventKind::as_str),
+        filter.vk_user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let kind = VkEventKind::parse(&row.kind).ok_or_else(|| {
+                sqlx::Error::Decode(format!("unknown event kind: {}", row.kind).into())
+            })?;
+
+            Ok(VkEvent {
+                kind,
+                vk_user_id: row.vk_user_id,
+                group_id: row.group_id,
+                post_id: row.post_id,
+                comment_id: row.comment_id,
+                occurred_at: row.occurred_at,
+            })
+        })
+        .collect()
+}
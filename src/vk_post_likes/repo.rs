@@ -1,19 +1,31 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::vk_posts::repo::VkPostKey;
+use crate::vk_user_sources::repo::{
+    NewVkUserSource, VkUserSourceKind, post_ref, record_vk_user_sources,
+};
+
 #[derive(Debug, Clone)]
 pub struct UpsertVkPostLikesResult {
     pub inserted: i64,
     pub updated: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReconcileVkPostLikesResult {
+    pub inserted: i64,
+    pub updated: i64,
+    pub removed: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct NewVkPostLike {
     pub vk_user_id: i64,
     pub group_id: i64,
     pub post_id: i64,
-    pub found_date: OffsetDateTime,
+    pub seen_at: OffsetDateTime,
 }
 
 #[derive(Debug, Clone)]
@@ -30,13 +42,27 @@ pub struct VkPostLike {
     pub vk_user_id: i64,
     pub group_id: i64,
     pub post_id: i64,
-    pub found_date: OffsetDateTime,
+    pub first_seen_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+    pub removed_at: Option<OffsetDateTime>,
 }
 
 pub async fn upsert_vk_post_likes(
     db: &PgPool,
     user_id: Uuid,
     likes: &[NewVkPostLike],
+) -> Result<UpsertVkPostLikesResult, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let result = upsert_vk_post_likes_in(&mut tx, user_id, likes).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+pub(crate) async fn upsert_vk_post_likes_in(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    likes: &[NewVkPostLike],
 ) -> Result<UpsertVkPostLikesResult, sqlx::Error> {
     if likes.is_empty() {
         return Ok(UpsertVkPostLikesResult {
@@ -48,13 +74,13 @@ pub async fn upsert_vk_post_likes(
     let mut vk_user_ids = Vec::with_capacity(likes.len());
     let mut group_ids = Vec::with_capacity(likes.len());
     let mut post_ids = Vec::with_capacity(likes.len());
-    let mut found_dates = Vec::with_capacity(likes.len());
+    let mut seen_dates = Vec::with_capacity(likes.len());
 
     for like in likes {
         vk_user_ids.push(like.vk_user_id);
         group_ids.push(like.group_id);
         post_ids.push(like.post_id);
-        found_dates.push(like.found_date);
+        seen_dates.push(like.seen_at);
     }
 
     let rows = sqlx::query!(
@@ -64,40 +90,98 @@ pub async fn upsert_vk_post_likes(
             vk_user_id,
             group_id,
             post_id,
-            found_date
+            first_seen_at,
+            last_seen_at
         )
         SELECT
             $1::uuid,
             src.vk_user_id,
             src.group_id,
             src.post_id,
-            src.found_date
+            src.seen_at,
+            src.seen_at
         FROM UNNEST(
             $2::bigint[],
             $3::bigint[],
             $4::bigint[],
             $5::timestamptz[]
-        ) AS src(vk_user_id, group_id, post_id, found_date)
+        ) AS src(vk_user_id, group_id, post_id, seen_at)
         ON CONFLICT (user_id, vk_user_id, group_id, post_id)
         DO UPDATE SET
-            found_date = EXCLUDED.found_date
+            first_seen_at = LEAST(vk_post_likes.first_seen_at, EXCLUDED.first_seen_at),
+            last_seen_at = GREATEST(vk_post_likes.last_seen_at, EXCLUDED.last_seen_at),
+            removed_at = NULL
         RETURNING (xmax = 0) AS "inserted!"
         "#,
         user_id,
         &vk_user_ids,
         &group_ids,
         &post_ids,
-        &found_dates
+        &seen_dates
     )
-    .fetch_all(db)
+    .fetch_all(&mut *conn)
     .await?;
 
+    let sources: Vec<NewVkUserSource> = likes
+        .iter()
+        .map(|like| NewVkUserSource {
+            vk_user_id: like.vk_user_id,
+            group_id: like.group_id,
+            source_kind: VkUserSourceKind::Liker,
+            source_ref: post_ref(like.post_id),
+            seen_at: like.seen_at,
+        })
+        .collect();
+    record_vk_user_sources(&mut *conn, user_id, &sources).await?;
+
     let inserted = rows.iter().filter(|row| row.inserted).count() as i64;
     let updated = rows.len() as i64 - inserted;
 
     Ok(UpsertVkPostLikesResult { inserted, updated })
 }
 
+pub async fn reconcile_vk_post_likes(
+    db: &PgPool,
+    user_id: Uuid,
+    post: &VkPostKey,
+    likes: &[NewVkPostLike],
+) -> Result<ReconcileVkPostLikesResult, sqlx::Error> {
+    let seen_vk_user_ids: Vec<i64> = likes
+        .iter()
+        .filter(|like| like.group_id == post.group_id && like.post_id == post.post_id)
+        .map(|like| like.vk_user_id)
+        .collect();
+
+    let mut tx = db.begin().await?;
+    let upserted = upsert_vk_post_likes_in(&mut tx, user_id, likes).await?;
+
+    let removed = sqlx::query!(
+        r#"
+        UPDATE vk_post_likes
+        SET removed_at = now()
+        WHERE user_id = $1
+          AND group_id = $2
+          AND post_id = $3
+          AND removed_at IS NULL
+          AND NOT (vk_user_id = ANY($4::bigint[]))
+        "#,
+        user_id,
+        post.group_id,
+        post.post_id,
+        &seen_vk_user_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(ReconcileVkPostLikesResult {
+        inserted: upserted.inserted,
+        updated: upserted.updated,
+        removed: removed.rows_affected() as i64,
+    })
+}
+
 pub async fn delete_vk_post_likes(
     db: &PgPool,
     user_id: Uuid,
@@ -151,10 +235,12 @@ pub async fn list_vk_post_likes(
             vk_user_id,
             group_id,
             post_id,
-            found_date
+            first_seen_at,
+            last_seen_at,
+            removed_at
         FROM vk_post_likes
         WHERE user_id = $1
-        ORDER BY found_date DESC, post_id DESC
+        ORDER BY last_seen_at DESC, post_id DESC
         LIMIT $2 OFFSET $3
         "#,
         user_id,
@@ -171,7 +257,9 @@ pub async fn list_vk_post_likes(
             vk_user_id: row.vk_user_id,
             group_id: row.group_id,
             post_id: row.post_id,
-            found_date: row.found_date,
+            first_seen_at: row.first_seen_at,
+            last_seen_at: row.last_seen_at,
+            removed_at: row.removed_at,
         })
         .collect())
 }
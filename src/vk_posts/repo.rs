@@ -1,12 +1,24 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::vk_user_sources::repo::{
+    NewVkUserSource, VkUserSourceKind, post_ref, record_vk_user_sources,
+};
+
 #[derive(Debug, Clone)]
 pub struct UpsertVkPostsResult {
     pub inserted: i64,
     pub updated: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReconcileVkPostsResult {
+    pub inserted: i64,
+    pub updated: i64,
+    pub deleted: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct NewVkPost {
     pub post_id: i64,
@@ -33,12 +45,25 @@ pub struct VkPost {
     pub created_date: i64,
     pub post_type: Option<String>,
     pub post_text: Option<String>,
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
 pub async fn upsert_vk_posts(
     db: &PgPool,
     user_id: Uuid,
     posts: &[NewVkPost],
+) -> Result<UpsertVkPostsResult, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let result = upsert_vk_posts_in(&mut tx, user_id, posts).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+pub(crate) async fn upsert_vk_posts_in(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    posts: &[NewVkPost],
 ) -> Result<UpsertVkPostsResult, sqlx::Error> {
     if posts.is_empty() {
         return Ok(UpsertVkPostsResult {
@@ -102,7 +127,8 @@ pub async fn upsert_vk_posts(
             from_id = EXCLUDED.from_id,
             created_date = EXCLUDED.created_date,
             post_type = EXCLUDED.post_type,
-            post_text = EXCLUDED.post_text
+            post_text = EXCLUDED.post_text,
+            deleted_at = NULL
         RETURNING (xmax = 0) AS "inserted!"
         "#,
         user_id,
@@ -113,15 +139,68 @@ pub async fn upsert_vk_posts(
         &post_types as &[Option<String>],
         &post_texts as &[Option<String>]
     )
-    .fetch_all(db)
+    .fetch_all(&mut *conn)
     .await?;
 
+    let seen_at = OffsetDateTime::now_utc();
+    let sources: Vec<NewVkUserSource> = posts
+        .iter()
+        .map(|post| NewVkUserSource {
+            vk_user_id: post.from_id,
+            group_id: post.group_id,
+            source_kind: VkUserSourceKind::Author,
+            source_ref: post_ref(post.post_id),
+            seen_at,
+        })
+        .collect();
+    record_vk_user_sources(&mut *conn, user_id, &sources).await?;
+
     let inserted = rows.iter().filter(|row| row.inserted).count() as i64;
     let updated = rows.len() as i64 - inserted;
 
     Ok(UpsertVkPostsResult { inserted, updated })
 }
 
+pub async fn reconcile_vk_posts(
+    db: &PgPool,
+    user_id: Uuid,
+    group_id: i64,
+    posts: &[NewVkPost],
+) -> Result<ReconcileVkPostsResult, sqlx::Error> {
+    let seen_post_ids: Vec<i64> = posts
+        .iter()
+        .filter(|post| post.group_id == group_id)
+        .map(|post| post.post_id)
+        .collect();
+
+    let mut tx = db.begin().await?;
+    let upserted = upsert_vk_posts_in(&mut tx, user_id, posts).await?;
+
+    let deleted = sqlx::query!(
+        r#"
+        UPDATE vk_posts
+        SET deleted_at = now()
+        WHERE user_id = $1
+          AND group_id = $2
+          AND deleted_at IS NULL
+          AND NOT (post_id = ANY($3::bigint[]))
+        "#,
+        user_id,
+        group_id,
+        &seen_post_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(ReconcileVkPostsResult {
+        inserted: upserted.inserted,
+        updated: upserted.updated,
+        deleted: deleted.rows_affected() as i64,
+    })
+}
+
 pub async fn delete_vk_posts(
     db: &PgPool,
     user_id: Uuid,
@@ -173,7 +252,8 @@ pub async fn list_vk_posts(
             from_id,
             created_date,
             post_type,
-            post_text
+            post_text,
+            deleted_at
         FROM vk_posts
         WHERE user_id = $1
         ORDER BY created_date DESC, post_id DESC
@@ -196,6 +276,7 @@ pub async fn list_vk_posts(
             created_date: row.created_date,
             post_type: row.post_type,
             post_text: row.post_text,
+            deleted_at: row.deleted_at,
         })
         .collect())
 }
@@ -0,0 +1,170 @@
+use sqlx::{PgExecutor, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VkUserSourceKind {
+    Member,
+    Liker,
+    Commenter,
+    Author,
+}
+
+impl VkUserSourceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VkUserSourceKind::Member => "member",
+            VkUserSourceKind::Liker => "liker",
+            VkUserSourceKind::Commenter => "commenter",
+            VkUserSourceKind::Author => "author",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "member" => Some(VkUserSourceKind::Member),
+            "liker" => Some(VkUserSourceKind::Liker),
+            "commenter" => Some(VkUserSourceKind::Commenter),
+            "author" => Some(VkUserSourceKind::Author),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewVkUserSource {
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub source_kind: VkUserSourceKind,
+    pub source_ref: String,
+    pub seen_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct VkUserSource {
+    pub vk_user_id: i64,
+    pub group_id: i64,
+    pub source_kind: VkUserSourceKind,
+    pub source_ref: String,
+    pub first_seen_at: OffsetDateTime,
+}
+
+pub fn post_ref(post_id: i64) -> String {
+    post_id.to_string()
+}
+
+pub fn comment_ref(post_id: i64, comment_id: i64) -> String {
+    format!("{post_id}:{comment_id}")
+}
+
+pub async fn record_vk_user_sources<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    sources: &[NewVkUserSource],
+) -> Result<(), sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    let mut vk_user_ids = Vec::with_capacity(sources.len());
+    let mut group_ids = Vec::with_capacity(sources.len());
+    let mut source_kinds = Vec::with_capacity(sources.len());
+    let mut source_refs = Vec::with_capacity(sources.len());
+    let mut seen_dates = Vec::with_capacity(sources.len());
+
+    for source in sources {
+        vk_user_ids.push(source.vk_user_id);
+        group_ids.push(source.group_id);
+        source_kinds.push(source.source_kind.as_str().to_string());
+        source_refs.push(source.source_ref.clone());
+        seen_dates.push(source.seen_at);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO vk_user_sources (
+            user_id,
+            vk_user_id,
+            group_id,
+            source_kind,
+            source_ref,
+            first_seen_at
+        )
+        SELECT
+            $1::uuid,
+            src.vk_user_id,
+            src.group_id,
+            src.source_kind,
+            src.source_ref,
+            MIN(src.seen_at)
+        FROM UNNEST(
+            $2::bigint[],
+            $3::bigint[],
+            $4::text[],
+            $5::text[],
+            $6::timestamptz[]
+        ) AS src(vk_user_id, group_id, source_kind, source_ref, seen_at)
+        GROUP BY src.vk_user_id, src.group_id, src.source_kind, src.source_ref
+        ON CONFLICT (user_id, vk_user_id, group_id, source_kind, source_ref)
+        DO UPDATE SET
+            first_seen_at = LEAST(vk_user_sources.first_seen_at, EXCLUDED.first_seen_at)
+        "#,
+        user_id,
+        &vk_user_ids,
+        &group_ids,
+        &source_kinds,
+        &source_refs,
+        &seen_dates
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_vk_user_sources(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_ids: &[i64],
+) -> Result<Vec<VkUserSource>, sqlx::Error> {
+    if vk_user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            vk_user_id,
+            group_id,
+            source_kind,
+            source_ref,
+            first_seen_at
+        FROM vk_user_sources
+        WHERE user_id = $1 AND vk_user_id = ANY($2::bigint[])
+        ORDER BY first_seen_at ASC, group_id ASC
+        "#,
+        user_id,
+        vk_user_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let source_kind = VkUserSourceKind::parse(&row.source_kind).ok_or_else(|| {
+                sqlx::Error::Decode(format!("unknown source_kind: {}", row.source_kind).into())
+            })?;
+
+            Ok(VkUserSource {
+                vk_user_id: row.vk_user_id,
+                group_id: row.group_id,
+                source_kind,
+                source_ref: row.source_ref,
+                first_seen_at: row.first_seen_at,
+            })
+        })
+        .collect()
+}
@@ -1,12 +1,43 @@
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::tags::http::TagDto;
 
 #[derive(Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct VkUsersQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    pub group_id: Option<i64>,
+    pub source_kind: Option<String>,
+    pub tags: Option<String>,
+    pub favorite: Option<bool>,
+    pub include_hidden: Option<bool>,
+    pub stage: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VkUserNotesQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VkUserStageHistoryQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkUserSourceDto {
+    pub group_id: i64,
+    pub source_kind: &'static str,
+    pub source_ref: String,
+    pub first_seen_at: OffsetDateTime,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -16,7 +47,11 @@ pub struct VkUserDto {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub city: Option<String>,
+    /// Same value as `first_seen_at`, kept for clients written before it.
+    #[schema(deprecated)]
     pub finded_date: OffsetDateTime,
+    pub first_seen_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
     pub is_closed: Option<bool>,
     pub screen_name: Option<String>,
     pub can_access_closed: Option<bool>,
@@ -24,4 +59,51 @@ pub struct VkUserDto {
     pub status: Option<String>,
     pub bdate: Option<String>,
     pub photo: Option<String>,
+    pub is_favorite: bool,
+    pub is_hidden: bool,
+    pub tags: Vec<TagDto>,
+    pub stage: Option<VkUserStageDto>,
+    pub notes_count: i64,
+    pub sources: Vec<VkUserSourceDto>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkUserStageDto {
+    pub id: Uuid,
+    pub name: String,
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetVkUserStageRequest {
+    pub stage_id: Option<Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkUserStageChangeDto {
+    pub id: Uuid,
+    pub from_stage_id: Option<Uuid>,
+    pub from_stage: Option<String>,
+    pub to_stage_id: Option<Uuid>,
+    pub to_stage: Option<String>,
+    pub changed_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateVkUserRequest {
+    pub is_favorite: Option<bool>,
+    pub is_hidden: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkUserFieldChangeDto {
+    pub field: &'static str,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VkUserHistoryEntryDto {
+    pub changed_at: OffsetDateTime,
+    pub changes: Vec<VkUserFieldChangeDto>,
 }
@@ -1,16 +1,192 @@
+use std::collections::HashMap;
+
 use axum::{
     Json,
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
     AppState,
     error::{ApiError, ApiResult},
-    extractors::auth_user::AuthUser,
+    extractors::scoped_user::{ScopedUser, scope},
+    notes::{
+        http::{NoteDto, handlers::note_dto},
+        repo::NotesFilter,
+    },
+    tags::{http::TagDto, http::handlers::tag_dto},
+    vk_user_sources::repo::VkUserSourceKind,
+    vk_users::repo::{VkUser, VkUserSnapshot, VkUsersFilter},
+};
+
+use super::dto::{
+    SetVkUserStageRequest, UpdateVkUserRequest, VkUserDto, VkUserFieldChangeDto,
+    VkUserHistoryEntryDto, VkUserNotesQuery, VkUserSourceDto, VkUserStageChangeDto, VkUserStageDto,
+    VkUserStageHistoryQuery, VkUsersQuery,
 };
 
-use super::dto::{VkUserDto, VkUsersQuery};
+pub(crate) fn parse_filter(
+    group_id: Option<i64>,
+    source_kind: Option<&str>,
+    tags: Option<&str>,
+    favorite: Option<bool>,
+    include_hidden: Option<bool>,
+    stage: Option<&str>,
+) -> ApiResult<VkUsersFilter> {
+    if group_id.is_some_and(|group_id| group_id <= 0) {
+        return Err(ApiError::BadRequest(
+            "group_id must be greater than 0".to_string(),
+        ));
+    }
+
+    let source_kind = source_kind
+        .map(|value| {
+            VkUserSourceKind::parse(value).ok_or(ApiError::BadRequest(
+                "source_kind must be one of member, liker, commenter, author".to_string(),
+            ))
+        })
+        .transpose()?;
+
+    let tag_ids = tags
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            Uuid::parse_str(value).map_err(|_| {
+                ApiError::BadRequest("tags must be a comma-separated list of tag ids".to_string())
+            })
+        })
+        .collect::<ApiResult<Vec<Uuid>>>()?;
+
+    let (stage_id, unstaged) = match stage.map(str::trim) {
+        None | Some("") => (None, false),
+        Some("none") => (None, true),
+        Some(value) => (
+            Some(Uuid::parse_str(value).map_err(|_| {
+                ApiError::BadRequest("stage must be a stage id or none".to_string())
+            })?),
+            false,
+        ),
+    };
+
+    Ok(VkUsersFilter {
+        group_id,
+        source_kind,
+        tag_ids,
+        favorite,
+        include_hidden: include_hidden.unwrap_or(false),
+        stage_id,
+        unstaged,
+    })
+}
+
+type TrackedFields = [(&'static str, Value); 7];
+
+fn snapshot_fields(snapshot: &VkUserSnapshot) -> TrackedFields {
+    [
+        ("first_name", json!(snapshot.first_name)),
+        ("last_name", json!(snapshot.last_name)),
+        ("city", json!(snapshot.city)),
+        ("is_closed", json!(snapshot.is_closed)),
+        ("about", json!(snapshot.about)),
+        ("status", json!(snapshot.status)),
+        ("photo", json!(snapshot.photo)),
+    ]
+}
+
+fn vk_user_fields(vk_user: &VkUser) -> TrackedFields {
+    [
+        ("first_name", json!(vk_user.first_name)),
+        ("last_name", json!(vk_user.last_name)),
+        ("city", json!(vk_user.city)),
+        ("is_closed", json!(vk_user.is_closed)),
+        ("about", json!(vk_user.about)),
+        ("status", json!(vk_user.status)),
+        ("photo", json!(vk_user.photo)),
+    ]
+}
+
+pub(crate) async fn vk_user_dtos(
+    db: &PgPool,
+    user_id: Uuid,
+    rows: Vec<VkUser>,
+) -> ApiResult<Vec<VkUserDto>> {
+    let vk_user_ids: Vec<i64> = rows.iter().map(|row| row.vk_user_id).collect();
+    let mut tags: HashMap<i64, Vec<TagDto>> = HashMap::new();
+    for assignment in crate::tags::repo::list_vk_user_tags(db, user_id, &vk_user_ids)
+        .await
+        .map_err(ApiError::Db)?
+    {
+        tags.entry(assignment.vk_user_id)
+            .or_default()
+            .push(tag_dto(assignment.tag));
+    }
+    let mut stages: HashMap<i64, VkUserStageDto> =
+        crate::pipeline::repo::list_vk_user_stages(db, user_id, &vk_user_ids)
+            .await
+            .map_err(ApiError::Db)?
+            .into_iter()
+            .map(|stage| {
+                (
+                    stage.vk_user_id,
+                    VkUserStageDto {
+                        id: stage.stage.id,
+                        name: stage.stage.name,
+                        updated_at: stage.updated_at,
+                    },
+                )
+            })
+            .collect();
+    let mut notes_counts = crate::notes::repo::count_notes_by_vk_user(db, user_id, &vk_user_ids)
+        .await
+        .map_err(ApiError::Db)?;
+    let mut sources: HashMap<i64, Vec<VkUserSourceDto>> = HashMap::new();
+    for source in crate::vk_user_sources::repo::list_vk_user_sources(db, user_id, &vk_user_ids)
+        .await
+        .map_err(ApiError::Db)?
+    {
+        sources
+            .entry(source.vk_user_id)
+            .or_default()
+            .push(VkUserSourceDto {
+                group_id: source.group_id,
+                source_kind: source.source_kind.as_str(),
+                source_ref: source.source_ref,
+                first_seen_at: source.first_seen_at,
+            });
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VkUserDto {
+            tags: tags.remove(&row.vk_user_id).unwrap_or_default(),
+            stage: stages.remove(&row.vk_user_id),
+            notes_count: notes_counts.remove(&row.vk_user_id).unwrap_or_default(),
+            sources: sources.remove(&row.vk_user_id).unwrap_or_default(),
+            vk_user_id: row.vk_user_id,
+            sex: row.sex,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            city: row.city,
+            finded_date: row.first_seen_at,
+            first_seen_at: row.first_seen_at,
+            last_seen_at: row.last_seen_at,
+            is_closed: row.is_closed,
+            screen_name: row.screen_name,
+            can_access_closed: row.can_access_closed,
+            about: row.about,
+            status: row.status,
+            bdate: row.bdate,
+            photo: row.photo,
+            is_favorite: row.is_favorite,
+            is_hidden: row.is_hidden,
+        })
+        .collect())
+}
 
 #[utoipa::path(
     get,
@@ -18,6 +194,7 @@ use super::dto::{VkUserDto, VkUsersQuery};
     params(VkUsersQuery),
     responses(
         (status = 200, description = "User VK users", body = [VkUserDto]),
+        (status = 400, description = "Invalid filter", body = crate::error::ErrorBody),
         (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
         (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
     ),
@@ -27,35 +204,342 @@ use super::dto::{VkUserDto, VkUsersQuery};
     tag = "VK Users"
 )]
 pub async fn list_vk_users(
-    user: AuthUser,
+    user: ScopedUser<scope::VkUsersRead>,
     State(state): State<AppState>,
     Query(q): Query<VkUsersQuery>,
 ) -> ApiResult<(StatusCode, Json<Vec<VkUserDto>>)> {
     let limit = q.limit.unwrap_or(50).clamp(1, 100);
     let offset = q.offset.unwrap_or(0).max(0);
+    let filter = parse_filter(
+        q.group_id,
+        q.source_kind.as_deref(),
+        q.tags.as_deref(),
+        q.favorite,
+        q.include_hidden,
+        q.stage.as_deref(),
+    )?;
 
-    let rows = crate::vk_users::repo::list_vk_users(&state.db, user.id, limit, offset)
+    let rows = crate::vk_users::repo::list_vk_users(&state.db, user.id, &filter, limit, offset)
         .await
         .map_err(ApiError::Db)?;
 
-    let vk_users = rows
-        .into_iter()
-        .map(|row| VkUserDto {
-            vk_user_id: row.vk_user_id,
-            sex: row.sex,
-            first_name: row.first_name,
-            last_name: row.last_name,
-            city: row.city,
-            finded_date: row.finded_date,
-            is_closed: row.is_closed,
-            screen_name: row.screen_name,
-            can_access_closed: row.can_access_closed,
-            about: row.about,
-            status: row.status,
-            bdate: row.bdate,
-            photo: row.photo,
+    let vk_users = vk_user_dtos(&state.db, user.id, rows).await?;
+
+    Ok((StatusCode::OK, Json(vk_users)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/vk-users/{vk_user_id}/history",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id")
+    ),
+    responses(
+        (status = 200, description = "VK user profile changes, newest first", body = [VkUserHistoryEntryDto]),
+        (status = 400, description = "Invalid VK user id", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn get_vk_user_history(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    Path(vk_user_id): Path<i64>,
+) -> ApiResult<(StatusCode, Json<Vec<VkUserHistoryEntryDto>>)> {
+    if vk_user_id <= 0 {
+        return Err(ApiError::BadRequest(
+            "vk_user_id must be greater than 0".to_string(),
+        ));
+    }
+
+    let vk_user = crate::vk_users::repo::get_vk_user(&state.db, user.id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let snapshots = crate::vk_users::repo::list_vk_user_snapshots(&state.db, user.id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    // Each snapshot holds the values right before a change, so the values
+    // after it come from the next snapshot or, for the last one, the current row.
+    let mut states: Vec<TrackedFields> = snapshots.iter().map(snapshot_fields).collect();
+    states.push(vk_user_fields(&vk_user));
+
+    let mut history: Vec<VkUserHistoryEntryDto> = snapshots
+        .iter()
+        .zip(states.windows(2))
+        .map(|(snapshot, pair)| VkUserHistoryEntryDto {
+            changed_at: snapshot.changed_at,
+            changes: pair[0]
+                .iter()
+                .zip(pair[1].iter())
+                .filter(|(old, new)| old.1 != new.1)
+                .map(
+                    |((field, old_value), (_, new_value))| VkUserFieldChangeDto {
+                        field,
+                        old_value: old_value.clone(),
+                        new_value: new_value.clone(),
+                    },
+                )
+                .collect(),
         })
         .collect();
+    history.reverse();
 
-    Ok((StatusCode::OK, Json(vk_users)))
+    Ok((StatusCode::OK, Json(history)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/vk-users/{vk_user_id}",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id")
+    ),
+    request_body = UpdateVkUserRequest,
+    responses(
+        (status = 200, description = "Favorite or hidden flag updated", body = VkUserDto),
+        (status = 400, description = "Invalid request payload", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn update_vk_user(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(vk_user_id): Path<i64>,
+    Json(req): Json<UpdateVkUserRequest>,
+) -> ApiResult<(StatusCode, Json<VkUserDto>)> {
+    if req.is_favorite.is_none() && req.is_hidden.is_none() {
+        return Err(ApiError::BadRequest(
+            "is_favorite or is_hidden is required".to_string(),
+        ));
+    }
+
+    let vk_user = crate::vk_users::repo::update_vk_user_flags(
+        &state.db,
+        user.id,
+        vk_user_id,
+        req.is_favorite,
+        req.is_hidden,
+    )
+    .await
+    .map_err(ApiError::Db)?
+    .ok_or(ApiError::NotFound)?;
+
+    let mut dtos = vk_user_dtos(&state.db, user.id, vec![vk_user]).await?;
+
+    Ok((StatusCode::OK, Json(dtos.remove(0))))
+}
+
+#[utoipa::path(
+    put,
+    path = "/vk-users/{vk_user_id}/tags/{tag_id}",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id"),
+        ("tag_id" = Uuid, Path, description = "Tag id")
+    ),
+    responses(
+        (status = 204, description = "Tag assigned"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user or tag not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn assign_vk_user_tag(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path((vk_user_id, tag_id)): Path<(i64, Uuid)>,
+) -> ApiResult<StatusCode> {
+    let assigned = crate::tags::repo::assign_tag(&state.db, user.id, tag_id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !assigned {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/vk-users/{vk_user_id}/tags/{tag_id}",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id"),
+        ("tag_id" = Uuid, Path, description = "Tag id")
+    ),
+    responses(
+        (status = 204, description = "Tag unassigned"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Tag is not assigned to this VK user", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn unassign_vk_user_tag(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path((vk_user_id, tag_id)): Path<(i64, Uuid)>,
+) -> ApiResult<StatusCode> {
+    let removed = crate::tags::repo::unassign_tag(&state.db, user.id, tag_id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/vk-users/{vk_user_id}/notes",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id"),
+        VkUserNotesQuery
+    ),
+    responses(
+        (status = 200, description = "Notes attached to the VK user", body = [NoteDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn list_vk_user_notes(
+    user: ScopedUser<scope::NotesRead>,
+    State(state): State<AppState>,
+    Path(vk_user_id): Path<i64>,
+    Query(q): Query<VkUserNotesQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<NoteDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    crate::vk_users::repo::get_vk_user(&state.db, user.id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let filter = NotesFilter {
+        vk_user_id: Some(vk_user_id),
+        ..NotesFilter::default()
+    };
+    let notes = crate::notes::repo::list_notes(&state.db, user.id, &filter, limit, offset)
+        .await
+        .map_err(ApiError::Db)?
+        .into_iter()
+        .map(note_dto)
+        .collect();
+
+    Ok((StatusCode::OK, Json(notes)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/vk-users/{vk_user_id}/stage",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id")
+    ),
+    request_body = SetVkUserStageRequest,
+    responses(
+        (status = 200, description = "Pipeline stage set; a null stage_id removes the VK user from the pipeline", body = VkUserDto),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user or stage not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn set_vk_user_stage(
+    user: ScopedUser<scope::VkUsersWrite>,
+    State(state): State<AppState>,
+    Path(vk_user_id): Path<i64>,
+    Json(req): Json<SetVkUserStageRequest>,
+) -> ApiResult<(StatusCode, Json<VkUserDto>)> {
+    crate::pipeline::repo::set_vk_user_stage(&state.db, user.id, vk_user_id, req.stage_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let vk_user = crate::vk_users::repo::get_vk_user(&state.db, user.id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+    let mut dtos = vk_user_dtos(&state.db, user.id, vec![vk_user]).await?;
+
+    Ok((StatusCode::OK, Json(dtos.remove(0))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/vk-users/{vk_user_id}/stage-history",
+    params(
+        ("vk_user_id" = i64, Path, description = "VK user id"),
+        VkUserStageHistoryQuery
+    ),
+    responses(
+        (status = 200, description = "Pipeline stage changes, newest first", body = [VkUserStageChangeDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "VK user not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "VK Users"
+)]
+pub async fn list_vk_user_stage_history(
+    user: ScopedUser<scope::VkUsersRead>,
+    State(state): State<AppState>,
+    Path(vk_user_id): Path<i64>,
+    Query(q): Query<VkUserStageHistoryQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<VkUserStageChangeDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    crate::vk_users::repo::get_vk_user(&state.db, user.id, vk_user_id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let changes =
+        crate::pipeline::repo::list_stage_changes(&state.db, user.id, vk_user_id, limit, offset)
+            .await
+            .map_err(ApiError::Db)?
+            .into_iter()
+            .map(|change| VkUserStageChangeDto {
+                id: change.id,
+                from_stage_id: change.from_stage_id,
+                from_stage: change.from_stage_name,
+                to_stage_id: change.to_stage_id,
+                to_stage: change.to_stage_name,
+                changed_at: change.changed_at,
+            })
+            .collect();
+
+    Ok((StatusCode::OK, Json(changes)))
 }
@@ -1,13 +1,35 @@
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    routing::{get, patch, put},
+};
 
 use crate::AppState;
 
 mod dto;
 pub(crate) mod handlers;
 
-pub use dto::VkUserDto;
-pub use handlers::list_vk_users;
+pub use dto::{
+    SetVkUserStageRequest, UpdateVkUserRequest, VkUserDto, VkUserFieldChangeDto,
+    VkUserHistoryEntryDto, VkUserSourceDto, VkUserStageChangeDto, VkUserStageDto,
+};
+pub use handlers::{
+    assign_vk_user_tag, get_vk_user_history, list_vk_user_notes, list_vk_user_stage_history,
+    list_vk_users, set_vk_user_stage, unassign_vk_user_tag, update_vk_user,
+};
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/", get(list_vk_users))
+    Router::new()
+        .route("/", get(list_vk_users))
+        .route("/{vk_user_id}", patch(update_vk_user))
+        .route("/{vk_user_id}/history", get(get_vk_user_history))
+        .route("/{vk_user_id}/notes", get(list_vk_user_notes))
+        .route("/{vk_user_id}/stage", put(set_vk_user_stage))
+        .route(
+            "/{vk_user_id}/stage-history",
+            get(list_vk_user_stage_history),
+        )
+        .route(
+            "/{vk_user_id}/tags/{tag_id}",
+            put(assign_vk_user_tag).delete(unassign_vk_user_tag),
+        )
 }
@@ -1,7 +1,9 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::vk_user_sources::repo::{NewVkUserSource, VkUserSourceKind, record_vk_user_sources};
+
 #[derive(Debug, Clone)]
 pub struct UpsertVkUsersResult {
     pub inserted: i64,
@@ -15,7 +17,7 @@ pub struct NewVkUser {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub city: Option<String>,
-    pub finded_date: OffsetDateTime,
+    pub seen_at: OffsetDateTime,
     pub is_closed: Option<bool>,
     pub screen_name: Option<String>,
     pub can_access_closed: Option<bool>,
@@ -23,6 +25,7 @@ pub struct NewVkUser {
     pub status: Option<String>,
     pub bdate: Option<String>,
     pub photo: Option<String>,
+    pub source_group_id: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,7 +37,8 @@ pub struct VkUser {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub city: Option<String>,
-    pub finded_date: OffsetDateTime,
+    pub first_seen_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
     pub is_closed: Option<bool>,
     pub screen_name: Option<String>,
     pub can_access_closed: Option<bool>,
@@ -42,12 +46,49 @@ pub struct VkUser {
     pub status: Option<String>,
     pub bdate: Option<String>,
     pub photo: Option<String>,
+    pub is_favorite: bool,
+    pub is_hidden: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct VkUserSnapshot {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub is_closed: Option<bool>,
+    pub about: Option<String>,
+    pub status: Option<String>,
+    pub photo: Option<String>,
+    pub changed_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VkUsersFilter {
+    pub group_id: Option<i64>,
+    pub source_kind: Option<VkUserSourceKind>,
+    pub tag_ids: Vec<Uuid>,
+    pub favorite: Option<bool>,
+    pub include_hidden: bool,
+    pub stage_id: Option<Uuid>,
+    pub unstaged: bool,
 }
 
 pub async fn upsert_vk_users(
     db: &PgPool,
     user_id: Uuid,
     vk_users: &[NewVkUser],
+) -> Result<UpsertVkUsersResult, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let result = upsert_vk_users_in(&mut tx, user_id, vk_users).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+pub(crate) async fn upsert_vk_users_in(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    vk_users: &[NewVkUser],
 ) -> Result<UpsertVkUsersResult, sqlx::Error> {
     if vk_users.is_empty() {
         return Ok(UpsertVkUsersResult {
@@ -61,7 +102,7 @@ pub async fn upsert_vk_users(
     let mut first_names = Vec::with_capacity(vk_users.len());
     let mut last_names = Vec::with_capacity(vk_users.len());
     let mut cities = Vec::with_capacity(vk_users.len());
-    let mut finded_dates = Vec::with_capacity(vk_users.len());
+    let mut seen_dates = Vec::with_capacity(vk_users.len());
     let mut is_closed_values = Vec::with_capacity(vk_users.len());
     let mut screen_names = Vec::with_capacity(vk_users.len());
     let mut can_access_closed_values = Vec::with_capacity(vk_users.len());
@@ -76,7 +117,7 @@ pub async fn upsert_vk_users(
         first_names.push(vk_user.first_name.clone());
         last_names.push(vk_user.last_name.clone());
         cities.push(vk_user.city.clone());
-        finded_dates.push(vk_user.finded_date);
+        seen_dates.push(vk_user.seen_at);
         is_closed_values.push(vk_user.is_closed);
         screen_names.push(vk_user.screen_name.clone());
         can_access_closed_values.push(vk_user.can_access_closed);
@@ -88,6 +129,67 @@ pub async fn upsert_vk_users(
 
     let rows = sqlx::query!(
         r#"
+        WITH src AS (
+            SELECT *
+            FROM UNNEST(
+                $2::bigint[],
+                $3::smallint[],
+                $4::text[],
+                $5::text[],
+                $6::text[],
+                $7::timestamptz[],
+                $8::boolean[],
+                $9::text[],
+                $10::boolean[],
+                $11::text[],
+                $12::text[],
+                $13::text[],
+                $14::text[]
+            ) AS src(
+                vk_user_id,
+                sex,
+                first_name,
+                last_name,
+                city,
+                seen_at,
+                is_closed,
+                screen_name,
+                can_access_closed,
+                about,
+                status,
+                bdate,
+                photo
+            )
+        ),
+        snapshots AS (
+            INSERT INTO vk_user_snapshots (
+                user_id,
+                vk_user_id,
+                first_name,
+                last_name,
+                city,
+                is_closed,
+                about,
+                status,
+                photo
+            )
+            SELECT
+                v.user_id,
+                v.vk_user_id,
+                v.first_name,
+                v.last_name,
+                v.city,
+                v.is_closed,
+                v.about,
+                v.status,
+                v.photo
+            FROM vk_users AS v
+            JOIN src ON src.vk_user_id = v.vk_user_id
+            WHERE v.user_id = $1
+              AND (v.first_name, v.last_name, v.city, v.is_closed, v.about, v.status, v.photo)
+                  IS DISTINCT FROM
+                  (src.first_name, src.last_name, src.city, src.is_closed, src.about, src.status, src.photo)
+        )
         INSERT INTO vk_users (
             user_id,
             vk_user_id,
@@ -95,7 +197,8 @@ pub async fn upsert_vk_users(
             first_name,
             last_name,
             city,
-            finded_date,
+            first_seen_at,
+            last_seen_at,
             is_closed,
             screen_name,
             can_access_closed,
@@ -111,7 +214,8 @@ pub async fn upsert_vk_users(
             src.first_name,
             src.last_name,
             src.city,
-            src.finded_date,
+            src.seen_at,
+            src.seen_at,
             src.is_closed,
             src.screen_name,
             src.can_access_closed,
@@ -119,42 +223,16 @@ pub async fn upsert_vk_users(
             src.status,
             src.bdate,
             src.photo
-        FROM UNNEST(
-            $2::bigint[],
-            $3::smallint[],
-            $4::text[],
-            $5::text[],
-            $6::text[],
-            $7::timestamptz[],
-            $8::boolean[],
-            $9::text[],
-            $10::boolean[],
-            $11::text[],
-            $12::text[],
-            $13::text[],
-            $14::text[]
-        ) AS src(
-            vk_user_id,
-            sex,
-            first_name,
-            last_name,
-            city,
-            finded_date,
-            is_closed,
-            screen_name,
-            can_access_closed,
-            about,
-            status,
-            bdate,
-            photo
-        )
+        FROM src
         ON CONFLICT (user_id, vk_user_id)
         DO UPDATE SET
             sex = EXCLUDED.sex,
             first_name = EXCLUDED.first_name,
             last_name = EXCLUDED.last_name,
             city = EXCLUDED.city,
-            finded_date = EXCLUDED.finded_date,
+            first_seen_at = LEAST(vk_users.first_seen_at, EXCLUDED.first_seen_at),
+            last_seen_at = GREATEST(vk_users.last_seen_at, EXCLUDED.last_seen_at),
+            seen_xact = EXCLUDED.seen_xact,
             is_closed = EXCLUDED.is_closed,
             screen_name = EXCLUDED.screen_name,
             can_access_closed = EXCLUDED.can_access_closed,
@@ -170,7 +248,7 @@ pub async fn upsert_vk_users(
         &first_names as &[Option<String>],
         &last_names as &[Option<String>],
         &cities as &[Option<String>],
-        &finded_dates,
+        &seen_dates,
         &is_closed_values as &[Option<bool>],
         &screen_names as &[Option<String>],
         &can_access_closed_values as &[Option<bool>],
@@ -179,9 +257,23 @@ pub async fn upsert_vk_users(
         &bdates as &[Option<String>],
         &photos as &[Option<String>]
     )
-    .fetch_all(db)
+    .fetch_all(&mut *conn)
     .await?;
 
+    let sources: Vec<NewVkUserSource> = vk_users
+        .iter()
+        .filter_map(|vk_user| {
+            vk_user.source_group_id.map(|group_id| NewVkUserSource {
+                vk_user_id: vk_user.vk_user_id,
+                group_id,
+                source_kind: VkUserSourceKind::Member,
+                source_ref: String::new(),
+                seen_at: vk_user.seen_at,
+            })
+        })
+        .collect();
+    record_vk_user_sources(&mut *conn, user_id, &sources).await?;
+
     let inserted = rows.iter().filter(|row| row.inserted).count() as i64;
     let updated = rows.len() as i64 - inserted;
 
@@ -214,10 +306,176 @@ pub async fn delete_vk_users(
 pub async fn list_vk_users(
     db: &PgPool,
     user_id: Uuid,
+    filter: &VkUsersFilter,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<VkUser>, sqlx::Error> {
     let rows = sqlx::query!(
+        r#"
+        SELECT
+            v.user_id,
+            v.vk_user_id,
+            v.sex,
+            v.first_name,
+            v.last_name,
+            v.city,
+            v.first_seen_at,
+            v.last_seen_at,
+            v.is_closed,
+            v.screen_name,
+            v.can_access_closed,
+            v.about,
+            v.status,
+            v.bdate,
+            v.photo,
+            v.is_favorite,
+            v.is_hidden
+        FROM vk_users AS v
+        WHERE v.user_id = $1
+          AND (
+              ($4::bigint IS NULL AND $5::text IS NULL)
+              OR EXISTS (
+                  SELECT 1
+                  FROM vk_user_sources AS s
+                  WHERE s.user_id = v.user_id
+                    AND s.vk_user_id = v.vk_user_id
+                    AND ($4::bigint IS NULL OR s.group_id = $4)
+                    AND ($5::text IS NULL OR s.source_kind = $5)
+              )
+          )
+          AND ($6 OR NOT v.is_hidden)
+          AND ($7::bool IS NULL OR v.is_favorite = $7)
+          AND (
+              cardinality($8::uuid[]) = 0
+              OR EXISTS (
+                  SELECT 1
+                  FROM vk_user_tag_assignments AS a
+                  WHERE a.user_id = v.user_id
+                    AND a.vk_user_id = v.vk_user_id
+                    AND a.tag_id = ANY($8)
+              )
+          )
+          AND ($9::uuid IS NULL OR EXISTS (
+              SELECT 1
+              FROM vk_user_stages AS st
+              WHERE st.user_id = v.user_id
+                AND st.vk_user_id = v.vk_user_id
+                AND st.stage_id = $9
+          ))
+          AND (NOT $10 OR NOT EXISTS (
+              SELECT 1
+              FROM vk_user_stages AS st
+              WHERE st.user_id = v.user_id
+                AND st.vk_user_id = v.vk_user_id
+          ))
+        ORDER BY v.last_seen_at DESC, v.vk_user_id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        limit,
+        offset,
+        filter.group_id,
+        filter.source_kind.map(VkUserSourceKind::as_str),
+        filter.include_hidden,
+        filter.favorite,
+        &filter.tag_ids,
+        filter.stage_id,
+        filter.unstaged
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VkUser {
+            user_id: row.user_id,
+            vk_user_id: row.vk_user_id,
+            sex: row.sex,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            city: row.city,
+            first_seen_at: row.first_seen_at,
+            last_seen_at: row.last_seen_at,
+            is_closed: row.is_closed,
+            screen_name: row.screen_name,
+            can_access_closed: row.can_access_closed,
+            about: row.about,
+            status: row.status,
+            bdate: row.bdate,
+            photo: row.photo,
+            is_favorite: row.is_favorite,
+            is_hidden: row.is_hidden,
+        })
+        .collect())
+}
+
+pub async fn update_vk_user_flags(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_id: i64,
+    is_favorite: Option<bool>,
+    is_hidden: Option<bool>,
+) -> Result<Option<VkUser>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE vk_users
+        SET is_favorite = COALESCE($3, is_favorite),
+            is_hidden = COALESCE($4, is_hidden)
+        WHERE user_id = $1 AND vk_user_id = $2
+        RETURNING
+            user_id,
+            vk_user_id,
+            sex,
+            first_name,
+            last_name,
+            city,
+            first_seen_at,
+            last_seen_at,
+            is_closed,
+            screen_name,
+            can_access_closed,
+            about,
+            status,
+            bdate,
+            photo,
+            is_favorite,
+            is_hidden
+        "#,
+        user_id,
+        vk_user_id,
+        is_favorite,
+        is_hidden
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| VkUser {
+        user_id: row.user_id,
+        vk_user_id: row.vk_user_id,
+        sex: row.sex,
+        first_name: row.first_name,
+        last_name: row.last_name,
+        city: row.city,
+        first_seen_at: row.first_seen_at,
+        last_seen_at: row.last_seen_at,
+        is_closed: row.is_closed,
+        screen_name: row.screen_name,
+        can_access_closed: row.can_access_closed,
+        about: row.about,
+        status: row.status,
+        bdate: row.bdate,
+        photo: row.photo,
+        is_favorite: row.is_favorite,
+        is_hidden: row.is_hidden,
+    }))
+}
+
+pub async fn get_vk_user(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_id: i64,
+) -> Result<Option<VkUser>, sqlx::Error> {
+    let row = sqlx::query!(
         r#"
         SELECT
             user_id,
@@ -226,43 +484,84 @@ pub async fn list_vk_users(
             first_name,
             last_name,
             city,
-            finded_date,
+            first_seen_at,
+            last_seen_at,
             is_closed,
             screen_name,
             can_access_closed,
             about,
             status,
             bdate,
-            photo
+            photo,
+            is_favorite,
+            is_hidden
         FROM vk_users
-        WHERE user_id = $1
-        ORDER BY finded_date DESC, vk_user_id DESC
-        LIMIT $2 OFFSET $3
+        WHERE user_id = $1 AND vk_user_id = $2
         "#,
         user_id,
-        limit,
-        offset
+        vk_user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| VkUser {
+        user_id: row.user_id,
+        vk_user_id: row.vk_user_id,
+        sex: row.sex,
+        first_name: row.first_name,
+        last_name: row.last_name,
+        city: row.city,
+        first_seen_at: row.first_seen_at,
+        last_seen_at: row.last_seen_at,
+        is_closed: row.is_closed,
+        screen_name: row.screen_name,
+        can_access_closed: row.can_access_closed,
+        about: row.about,
+        status: row.status,
+        bdate: row.bdate,
+        photo: row.photo,
+        is_favorite: row.is_favorite,
+        is_hidden: row.is_hidden,
+    }))
+}
+
+pub async fn list_vk_user_snapshots(
+    db: &PgPool,
+    user_id: Uuid,
+    vk_user_id: i64,
+) -> Result<Vec<VkUserSnapshot>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            first_name,
+            last_name,
+            city,
+            is_closed,
+            about,
+            status,
+            photo,
+            changed_at
+        FROM vk_user_snapshots
+        WHERE user_id = $1 AND vk_user_id = $2
+        ORDER BY seq ASC
+        "#,
+        user_id,
+        vk_user_id
     )
     .fetch_all(db)
     .await?;
 
     Ok(rows
         .into_iter()
-        .map(|row| VkUser {
-            user_id: row.user_id,
-            vk_user_id: row.vk_user_id,
-            sex: row.sex,
+        .map(|row| VkUserSnapshot {
             first_name: row.first_name,
             last_name: row.last_name,
             city: row.city,
-            finded_date: row.finded_date,
             is_closed: row.is_closed,
-            screen_name: row.screen_name,
-            can_access_closed: row.can_access_closed,
             about: row.about,
             status: row.status,
-            bdate: row.bdate,
             photo: row.photo,
+            changed_at: row.changed_at,
         })
         .collect())
 }
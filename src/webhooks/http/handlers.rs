@@ -0,0 +1,267 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::scoped_user::{ScopedUser, scope},
+    webhooks::repo::{WebhookDelivery, WebhookEventKind},
+};
+
+use super::dto::{
+    CreateWebhookRequest, CreateWebhookResponse, WebhookDeliveriesQuery, WebhookDeliveryDto,
+    WebhookDto,
+};
+
+fn new_webhook_secret() -> String {
+    let mut bytes = [0_u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn delivery_dto(delivery: WebhookDelivery) -> WebhookDeliveryDto {
+    WebhookDeliveryDto {
+        id: delivery.id,
+        event_id: delivery.event_id,
+        event_type: delivery.event_type,
+        status: delivery.status.as_str(),
+        attempts: delivery.attempts,
+        next_attempt_at: delivery.next_attempt_at,
+        last_status_code: delivery.last_status_code,
+        last_error: delivery.last_error,
+        delivered_at: delivery.delivered_at,
+        created_at: delivery.created_at,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook subscription created", body = CreateWebhookResponse),
+        (status = 400, description = "Invalid subscription payload or a url that resolves to a non-public address", body = crate::error::ErrorBody),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+pub async fn create_webhook(
+    user: ScopedUser<scope::WebhooksWrite>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> ApiResult<(StatusCode, Json<CreateWebhookResponse>)> {
+    let url = request.url.trim().to_string();
+
+    let mut event_types = Vec::with_capacity(request.event_types.len());
+    for value in &request.event_types {
+        match WebhookEventKind::parse(value) {
+            Some(kind) if kind != WebhookEventKind::Test => {
+                if !event_types.contains(&kind) {
+                    event_types.push(kind);
+                }
+            }
+            _ => {
+                return Err(ApiError::BadRequest(
+                    "event_types must contain only vk_user.matched, vk_user.commented, crawl.failed"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+    if event_types.is_empty() {
+        return Err(ApiError::BadRequest(
+            "event_types must not be empty".to_string(),
+        ));
+    }
+
+    let secret = match request.secret {
+        Some(secret) if secret.len() < 16 => {
+            return Err(ApiError::BadRequest(
+                "secret must be at least 16 characters".to_string(),
+            ));
+        }
+        Some(secret) => secret,
+        None => new_webhook_secret(),
+    };
+
+    crate::webhooks::targets::check_url(&url, state.webhooks.policy())
+        .await
+        .map_err(ApiError::BadRequest)?;
+
+    let subscription =
+        crate::webhooks::repo::create_subscription(&state.db, user.id, url, &event_types, secret)
+            .await
+            .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateWebhookResponse {
+            id: subscription.id,
+            url: subscription.url,
+            event_types: subscription.event_types,
+            secret: subscription.secret,
+            created_at: subscription.created_at,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "User webhook subscriptions", body = [WebhookDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+pub async fn list_webhooks(
+    user: ScopedUser<scope::WebhooksRead>,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<Vec<WebhookDto>>)> {
+    let rows = crate::webhooks::repo::list_subscriptions(&state.db, user.id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    let webhooks = rows
+        .into_iter()
+        .map(|subscription| WebhookDto {
+            id: subscription.id,
+            url: subscription.url,
+            event_types: subscription.event_types,
+            created_at: subscription.created_at,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(webhooks)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Webhook subscription id")
+    ),
+    responses(
+        (status = 204, description = "Webhook subscription deleted"),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Webhook subscription not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+pub async fn delete_webhook(
+    user: ScopedUser<scope::WebhooksWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    let deleted = crate::webhooks::repo::delete_subscription_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?;
+
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    params(
+        ("id" = Uuid, Path, description = "Webhook subscription id"),
+        WebhookDeliveriesQuery
+    ),
+    responses(
+        (status = 200, description = "Delivery log, newest first", body = [WebhookDeliveryDto]),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Webhook subscription not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+pub async fn list_webhook_deliveries(
+    user: ScopedUser<scope::WebhooksRead>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<WebhookDeliveriesQuery>,
+) -> ApiResult<(StatusCode, Json<Vec<WebhookDeliveryDto>>)> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    crate::webhooks::repo::get_subscription_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let rows = crate::webhooks::repo::list_deliveries(&state.db, user.id, id, limit, offset)
+        .await
+        .map_err(ApiError::Db)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(rows.into_iter().map(delivery_dto).collect()),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/test",
+    params(
+        ("id" = Uuid, Path, description = "Webhook subscription id")
+    ),
+    responses(
+        (status = 200, description = "Test event sent, failed attempts are retried", body = WebhookDeliveryDto),
+        (status = 401, description = "Unauthorized", body = crate::error::ErrorBody),
+        (status = 404, description = "Webhook subscription not found", body = crate::error::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::error::ErrorBody)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+pub async fn send_test_webhook(
+    user: ScopedUser<scope::WebhooksWrite>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<(StatusCode, Json<WebhookDeliveryDto>)> {
+    let subscription = crate::webhooks::repo::get_subscription_owned(&state.db, user.id, id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    let delivery = crate::webhooks::repo::enqueue_test_delivery(&state.db, &subscription)
+        .await
+        .map_err(ApiError::Db)?;
+
+    crate::webhooks::sender::attempt_delivery(&state.db, &state.webhooks, &delivery)
+        .await
+        .map_err(ApiError::Db)?;
+
+    let delivery = crate::webhooks::repo::get_delivery(&state.db, user.id, delivery.id)
+        .await
+        .map_err(ApiError::Db)?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(delivery_dto(delivery))))
+}
@@ -0,0 +1,22 @@
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+
+use crate::AppState;
+
+mod dto;
+pub(crate) mod handlers;
+
+pub use dto::{CreateWebhookRequest, CreateWebhookResponse, WebhookDeliveryDto, WebhookDto};
+pub use handlers::{
+    create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks, send_test_webhook,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_webhook).get(list_webhooks))
+        .route("/{id}", delete(delete_webhook))
+        .route("/{id}/deliveries", get(list_webhook_deliveries))
+        .route("/{id}/test", post(send_test_webhook))
+}
@@ -0,0 +1,4 @@
+pub mod http;
+pub mod repo;
+pub mod sender;
+pub mod targets;
@@ -0,0 +1,464 @@
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEventKind {
+    VkUserMatched,
+    VkUserCommented,
+    CrawlFailed,
+    Test,
+}
+
+impl WebhookEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEventKind::VkUserMatched => "vk_user.matched",
+            WebhookEventKind::VkUserCommented => "vk_user.commented",
+            WebhookEventKind::CrawlFailed => "crawl.failed",
+            WebhookEventKind::Test => "test",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vk_user.matched" => Some(WebhookEventKind::VkUserMatched),
+            "vk_user.commented" => Some(WebhookEventKind::VkUserCommented),
+            "crawl.failed" => Some(WebhookEventKind::CrawlFailed),
+            "test" => Some(WebhookEventKind::Test),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(WebhookDeliveryStatus::Pending),
+            "delivered" => Some(WebhookDeliveryStatus::Delivered),
+            "failed" => Some(WebhookDeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    #[allow(dead_code)]
+    pub user_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub secret: String,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: OffsetDateTime,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct DueWebhookDelivery {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub url: String,
+    pub secret: String,
+    pub payload: Value,
+    pub attempts: i32,
+}
+
+pub async fn create_subscription(
+    db: &PgPool,
+    user_id: Uuid,
+    url: String,
+    event_types: &[WebhookEventKind],
+    secret: String,
+) -> Result<WebhookSubscription, sqlx::Error> {
+    let event_types: Vec<String> = event_types
+        .iter()
+        .map(|kind| kind.as_str().to_string())
+        .collect();
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO webhook_subscriptions (user_id, url, event_types, secret)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, url, event_types, secret, created_at
+        "#,
+        user_id,
+        url,
+        &event_types,
+        secret
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(WebhookSubscription {
+        id: row.id,
+        user_id: row.user_id,
+        url: row.url,
+        event_types: row.event_types,
+        secret: row.secret,
+        created_at: row.created_at,
+    })
+}
+
+pub async fn list_subscriptions(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<WebhookSubscription>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, user_id, url, event_types, secret, created_at
+        FROM webhook_subscriptions
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| WebhookSubscription {
+            id: row.id,
+            user_id: row.user_id,
+            url: row.url,
+            event_types: row.event_types,
+            secret: row.secret,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+pub async fn get_subscription_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    subscription_id: Uuid,
+) -> Result<Option<WebhookSubscription>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, user_id, url, event_types, secret, created_at
+        FROM webhook_subscriptions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        subscription_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| WebhookSubscription {
+        id: row.id,
+        user_id: row.user_id,
+        url: row.url,
+        event_types: row.event_types,
+        secret: row.secret,
+        created_at: row.created_at,
+    }))
+}
+
+pub async fn delete_subscription_owned(
+    db: &PgPool,
+    user_id: Uuid,
+    subscription_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query!(
+        r#"
+        DELETE FROM webhook_subscriptions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        subscription_id,
+        user_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected() == 1)
+}
+
+fn event_payload(event_id: Uuid, kind: WebhookEventKind, data: Value) -> Value {
+    json!({
+        "id": event_id,
+        "type": kind.as_str(),
+        "created_at": OffsetDateTime::now_utc().unix_timestamp(),
+        "data": data,
+    })
+}
+
+pub async fn emit_event(
+    db: &PgPool,
+    user_id: Uuid,
+    kind: WebhookEventKind,
+    data: Value,
+) -> Result<u64, sqlx::Error> {
+    let event_id = Uuid::new_v4();
+
+    let res = sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (subscription_id, user_id, event_id, event_type, payload)
+        SELECT id, user_id, $3, $2::text, $4
+        FROM webhook_subscriptions
+        WHERE user_id = $1 AND $2::text = ANY(event_types)
+        "#,
+        user_id,
+        kind.as_str(),
+        event_id,
+        event_payload(event_id, kind, data)
+    )
+    .execute(db)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn enqueue_test_delivery(
+    db: &PgPool,
+    subscription: &WebhookSubscription,
+) -> Result<DueWebhookDelivery, sqlx::Error> {
+    let event_id = Uuid::new_v4();
+    let kind = WebhookEventKind::Test;
+    let payload = event_payload(
+        event_id,
+        kind,
+        json!({ "subscription_id": subscription.id }),
+    );
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO webhook_deliveries (
+            subscription_id,
+            user_id,
+            event_id,
+            event_type,
+            payload,
+            next_attempt_at
+        )
+        VALUES ($1, $2, $3, $4, $5, now() + interval '5 minutes')
+        RETURNING id
+        "#,
+        subscription.id,
+        subscription.user_id,
+        event_id,
+        kind.as_str(),
+        payload
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(DueWebhookDelivery {
+        id,
+        event_id,
+        event_type: kind.as_str().to_string(),
+        url: subscription.url.clone(),
+        secret: subscription.secret.clone(),
+        payload,
+        attempts: 0,
+    })
+}
+
+pub async fn claim_due_deliveries(
+    db: &PgPool,
+    limit: i64,
+) -> Result<Vec<DueWebhookDelivery>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries AS d
+        SET next_attempt_at = now() + interval '5 minutes'
+        FROM webhook_subscriptions AS s
+        WHERE s.id = d.subscription_id
+          AND d.id IN (
+              SELECT id
+              FROM webhook_deliveries
+              WHERE status = 'pending' AND next_attempt_at <= now()
+              ORDER BY next_attempt_at
+              LIMIT $1
+              FOR UPDATE SKIP LOCKED
+          )
+        RETURNING d.id, d.event_id, d.event_type, d.payload, d.attempts, s.url, s.secret
+        "#,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| DueWebhookDelivery {
+            id: row.id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            url: row.url,
+            secret: row.secret,
+            payload: row.payload,
+            attempts: row.attempts,
+        })
+        .collect())
+}
+
+pub async fn record_delivery_attempt(
+    db: &PgPool,
+    delivery_id: Uuid,
+    status: WebhookDeliveryStatus,
+    next_attempt_at: OffsetDateTime,
+    status_code: Option<i32>,
+    error: Option<String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2::text,
+            attempts = attempts + 1,
+            next_attempt_at = $3,
+            last_status_code = $4,
+            last_error = $5,
+            delivered_at = CASE WHEN $2::text = 'delivered' THEN now() ELSE delivered_at END
+        WHERE id = $1
+        "#,
+        delivery_id,
+        status.as_str(),
+        next_attempt_at,
+        status_code,
+        error
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    db: &PgPool,
+    user_id: Uuid,
+    subscription_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            subscription_id,
+            event_id,
+            event_type,
+            status,
+            attempts,
+            next_attempt_at,
+            last_status_code,
+            last_error,
+            delivered_at,
+            created_at
+        FROM webhook_deliveries
+        WHERE user_id = $1 AND subscription_id = $2
+        ORDER BY created_at DESC, id
+        LIMIT $3 OFFSET $4
+        "#,
+        user_id,
+        subscription_id,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let status = WebhookDeliveryStatus::parse(&row.status).ok_or_else(|| {
+                sqlx::Error::Decode(format!("unknown delivery status: {}", row.status).into())
+            })?;
+
+            Ok(WebhookDelivery {
+                id: row.id,
+                subscription_id: row.subscription_id,
+                event_id: row.event_id,
+                event_type: row.event_type,
+                status,
+                attempts: row.attempts,
+                next_attempt_at: row.next_attempt_at,
+                last_status_code: row.last_status_code,
+                last_error: row.last_error,
+                delivered_at: row.delivered_at,
+                created_at: row.created_at,
+            })
+        })
+        .collect()
+}
+
+pub async fn get_delivery(
+    db: &PgPool,
+    user_id: Uuid,
+    delivery_id: Uuid,
+) -> Result<Option<WebhookDelivery>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            subscription_id,
+            event_id,
+            event_type,
+            status,
+            attempts,
+            next_attempt_at,
+            last_status_code,
+            last_error,
+            delivered_at,
+            created_at
+        FROM webhook_deliveries
+        WHERE id = $1 AND user_id = $2
+        "#,
+        delivery_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    row.map(|row| {
+        let status = WebhookDeliveryStatus::parse(&row.status).ok_or_else(|| {
+            sqlx::Error::Decode(format!("unknown delivery status: {}", row.status).into())
+        })?;
+
+        Ok(WebhookDelivery {
+            id: row.id,
+            subscription_id: row.subscription_id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            status,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_status_code: row.last_status_code,
+            last_error: row.last_error,
+            delivered_at: row.delivered_at,
+            created_at: row.created_at,
+        })
+    })
+    .transpose()
+}
@@ -0,0 +1,137 @@
+use std::time::Duration as StdDuration;
+
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+
+use crate::webhooks::{
+    repo::{DueWebhookDelivery, WebhookDeliveryStatus, record_delivery_attempt},
+    targets::{PublicOnlyResolver, WebhookTargetPolicy, check_url},
+};
+
+pub const MAX_ATTEMPTS: i32 = 6;
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_ID_HEADER: &str = "x-webhook-id";
+pub const EVENT_TYPE_HEADER: &str = "x-webhook-event";
+
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+const BATCH_SIZE: i64 = 50;
+
+/// HTTP client shared by every webhook delivery. Redirects are not followed,
+/// since they would let a receiver point the request anywhere.
+#[derive(Debug, Clone)]
+pub struct WebhookClient {
+    http: Client,
+    policy: WebhookTargetPolicy,
+}
+
+impl WebhookClient {
+    pub fn new(policy: WebhookTargetPolicy) -> Self {
+        let mut builder = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none());
+        if policy == WebhookTargetPolicy::PublicOnly {
+            builder = builder.dns_resolver(PublicOnlyResolver::shared());
+        }
+
+        Self {
+            http: builder
+                .build()
+                .expect("failed to build webhook http client"),
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> WebhookTargetPolicy {
+        self.policy
+    }
+}
+
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub fn retry_delay(attempts: i32) -> Duration {
+    Duration::seconds(30) * 2_i32.pow(attempts.clamp(1, 10) as u32 - 1)
+}
+
+pub async fn attempt_delivery(
+    db: &PgPool,
+    client: &WebhookClient,
+    delivery: &DueWebhookDelivery,
+) -> Result<WebhookDeliveryStatus, sqlx::Error> {
+    let body = serde_json::to_vec(&delivery.payload).expect("json value always serializes");
+    let timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+    // Literal IP hosts never reach the resolver, so check the url up front too.
+    let (status_code, error) = match check_url(&delivery.url, client.policy).await {
+        Err(e) => (None, Some(e)),
+        Ok(()) => {
+            let res = client
+                .http
+                .post(&delivery.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_ID_HEADER, delivery.event_id.to_string())
+                .header(EVENT_TYPE_HEADER, &delivery.event_type)
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, sign(&delivery.secret, timestamp, &body))
+                .body(body)
+                .send()
+                .await;
+
+            match res {
+                Ok(res) if res.status().is_success() => (Some(res.status().as_u16() as i32), None),
+                Ok(res) => (
+                    Some(res.status().as_u16() as i32),
+                    Some(format!("unexpected response status {}", res.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            }
+        }
+    };
+
+    let attempts = delivery.attempts + 1;
+    let now = OffsetDateTime::now_utc();
+    let (status, next_attempt_at) = match &error {
+        None => (WebhookDeliveryStatus::Delivered, now),
+        Some(_) if attempts >= MAX_ATTEMPTS => (WebhookDeliveryStatus::Failed, now),
+        Some(_) => (WebhookDeliveryStatus::Pending, now + retry_delay(attempts)),
+    };
+
+    match &error {
+        None => tracing::info!(
+            "webhook delivery {} ({}) delivered on attempt {attempts}",
+            delivery.id,
+            delivery.event_type
+        ),
+        Some(error) => tracing::warn!(
+            "webhook delivery {} ({}) attempt {attempts} failed: {error}",
+            delivery.id,
+            delivery.event_type
+        ),
+    }
+
+    record_delivery_attempt(db, delivery.id, status, next_attempt_at, status_code, error).await?;
+
+    Ok(status)
+}
+
+pub async fn deliver_due(db: &PgPool, client: &WebhookClient) -> Result<i64, sqlx::Error> {
+    let mut delivered = 0;
+
+    for delivery in crate::webhooks::repo::claim_due_deliveries(db, BATCH_SIZE).await? {
+        if attempt_delivery(db, client, &delivery).await? == WebhookDeliveryStatus::Delivered {
+            delivered += 1;
+        }
+    }
+
+    Ok(delivered)
+}
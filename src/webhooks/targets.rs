@@ -0,0 +1,118 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+};
+
+/// Which hosts webhooks may be delivered to. Subscriptions are created by any
+/// user, so by default they cannot reach the loopback interface, the private
+/// network or cloud metadata endpoints behind the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebhookTargetPolicy {
+    #[default]
+    PublicOnly,
+    AllowPrivate,
+}
+
+impl WebhookTargetPolicy {
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("WEBHOOK_ALLOW_PRIVATE_TARGETS").as_deref() {
+            Ok("true") => Ok(WebhookTargetPolicy::AllowPrivate),
+            Ok("false") | Err(_) => Ok(WebhookTargetPolicy::PublicOnly),
+            Ok(value) => Err(format!(
+                "WEBHOOK_ALLOW_PRIVATE_TARGETS must be true or false, got {value}"
+            )),
+        }
+    }
+}
+
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                // Shared address space (carrier-grade NAT), RFC 6598.
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // Unique local fc00::/7 and link-local fe80::/10.
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("failed to resolve {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{host} did not resolve to any address"));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "{host} resolves to {}, which is not a public address",
+            addr.ip()
+        ));
+    }
+
+    Ok(addrs)
+}
+
+/// Checks that `url` is an absolute http(s) url whose host only resolves to
+/// addresses the policy allows.
+pub async fn check_url(url: &str, policy: WebhookTargetPolicy) -> Result<(), String> {
+    let url = Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or("url must be an absolute http or https url".to_string())?;
+    let host = url
+        .host_str()
+        .ok_or("url must be an absolute http or https url".to_string())?;
+    if policy == WebhookTargetPolicy::AllowPrivate {
+        return Ok(());
+    }
+
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(443);
+    resolve_public(host, port).await.map(|_| ())
+}
+
+/// Resolver for the delivery client. It repeats the check at connect time so
+/// a host cannot pass validation and then rebind to an internal address.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PublicOnlyResolver;
+
+impl PublicOnlyResolver {
+    pub(crate) fn shared() -> Arc<Self> {
+        Arc::new(PublicOnlyResolver)
+    }
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use find_w::auth::session_cache::SessionCache;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn logout_and_session_revocation_end_access_tokens(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let (status, laptop) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let laptop_access = laptop["access_token"].as_str().unwrap();
+
+    let (status, _) = app.get_json("/me", Some(laptop_access)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/logout",
+            json!({ "refresh_token": laptop["refresh_token"] }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.get_json("/me", Some(laptop_access)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/me/sessions/revoke-all",
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn access_tokens_follow_their_rotated_session(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, rotated) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    for token in [
+        user.access_token.as_str(),
+        rotated["access_token"].as_str().unwrap(),
+    ] {
+        let (status, _) = app.get_json("/me", Some(token)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "strong-password-123", "new_password": "brand-new-password" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .get_json("/me", Some(rotated["access_token"].as_str().unwrap()))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .get_json("/me", Some(body["access_token"].as_str().unwrap()))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn revocations_from_other_replicas_apply_after_cache_ttl(pool: PgPool) {
+    let cached = TestApp::new(pool.clone());
+    let uncached = TestApp::with_state(pool.clone(), |state| {
+        state.sessions = SessionCache::new(Duration::ZERO);
+    });
+    let user = cached.register_and_login().await;
+
+    let (status, _) = cached.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now() WHERE user_id = $1",
+        user.id
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to revoke sessions");
+
+    let (status, _) = cached.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = uncached.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
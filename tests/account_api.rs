@@ -0,0 +1,263 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, discard_verification_emails, smtp::start_smtp_sink};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+const CONFIRM_LINK: &str = "http://localhost:3000/auth/email/confirm?token=";
+
+async fn confirm(app: &TestApp, token: &str) -> (StatusCode, Value) {
+    app.post_json("/auth/email/confirm", json!({ "token": token }), None)
+        .await
+}
+
+#[sqlx::test]
+async fn change_password_requires_current_password(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, _) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "wrong-password", "new_password": "brand-new-password" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "strong-password-123", "new_password": "short" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "strong-password-123", "new_password": "brand-new-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn change_password_revokes_other_sessions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, other) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "strong-password-123", "new_password": "brand-new-password" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let access_token = body["access_token"].as_str().expect("missing access token");
+    let refresh_token = body["refresh_token"]
+        .as_str()
+        .expect("missing refresh token");
+
+    for old in [
+        user.refresh_token.as_str(),
+        other["refresh_token"].as_str().unwrap(),
+    ] {
+        let (status, _) = app
+            .post_json("/auth/refresh", json!({ "refresh_token": old }), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get_json("/me", Some(access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "brand-new-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn change_email_applies_only_after_confirmation(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": "wrong-password@example.test", "current_password": "nope" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": "  New.Address@example.test ", "current_password": "strong-password-123" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (_, me) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(me["email"], user.email);
+
+    find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    let sent = sink.messages.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].recipients[0].contains("New.Address@example.test"));
+    let token = sent[0]
+        .link_after(CONFIRM_LINK)
+        .expect("email misses confirmation link");
+
+    // Opening the link only shows a page; link scanners must not confirm.
+    let (status, page) = app
+        .get_text(&format!("/auth/email/confirm?token={token}"), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("<form"));
+    let (_, me) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(me["email"], user.email);
+
+    let (status, _) = confirm(&app, "bogus").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, me) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(me["email"], "New.Address@example.test");
+    assert_ne!(me["email_verified_at"], Value::Null);
+
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    let sent = sink.messages.lock().unwrap().clone();
+    assert_eq!(sent.len(), 2);
+    assert!(sent[1].recipients[0].contains(&user.email));
+    assert!(sent[1].data.contains("New.Address@example.test"));
+
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": "New.Address@example.test", "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn change_email_rejects_taken_and_current_addresses(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": other.email.to_uppercase(), "current_password": "strong-password-123" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": user.email, "current_password": "strong-password-123" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": "not-an-email", "current_password": "strong-password-123" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn confirming_email_taken_meanwhile_conflicts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+
+    let (status, _) = app
+        .post_json(
+            "/me/email",
+            json!({ "new_email": "contested@example.test", "current_password": "strong-password-123" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    let token = sink.messages.lock().unwrap()[0]
+        .link_after(CONFIRM_LINK)
+        .expect("email misses confirmation link");
+
+    let (status, _) = app
+        .post_json(
+            "/auth/register",
+            json!({ "email": "contested@example.test", "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, me) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(me["email"], user.email);
+}
@@ -0,0 +1,373 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, TestUser};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn admin(app: &TestApp, pool: &PgPool) -> TestUser {
+    let user = app.register_and_login().await;
+    sqlx::query("UPDATE users SET email_verified_at = now() WHERE id = $1")
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .unwrap();
+    let admins = find_w::admin::repo::promote_admins(pool, std::slice::from_ref(&user.email))
+        .await
+        .unwrap();
+    assert_eq!(admins, vec![user.email.clone()]);
+    user
+}
+
+async fn record_crawl(pool: &PgPool, user_id: Uuid, data: Value) {
+    sqlx::query(
+        "INSERT INTO stream_events (user_id, event_type, data) VALUES ($1, 'crawl.finished', $2)",
+    )
+    .bind(user_id)
+    .bind(data)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test]
+async fn admin_endpoints_require_the_admin_role(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let admin = admin(&app, &pool).await;
+
+    let (status, _) = app.get_json("/admin/users", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = app.get_json("/admin/users", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "FORBIDDEN");
+
+    let (status, _) = app
+        .post_json(
+            &format!("/admin/users/{}/disable", admin.id),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app.get_json("/me", Some(&admin.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["role"], "admin");
+
+    let (_, created) = app
+        .post_json(
+            "/api-keys",
+            json!({ "name": "ops", "scopes": ["groups:read"] }),
+            Some(&admin.access_token),
+        )
+        .await;
+    let (status, _) = app
+        .get_json("/admin/users", Some(created["key"].as_str().unwrap()))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn admin_lists_accounts_with_usage(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+    let user = app.register_and_login().await;
+    common::seed_group(&pool, user.id, 100).await;
+    common::seed_vk_user(&pool, user.id, 7).await;
+    common::seed_vk_user(&pool, user.id, 8).await;
+    record_crawl(
+        &pool,
+        user.id,
+        json!({ "source": "batch", "status": "succeeded" }),
+    )
+    .await;
+
+    let (status, body) = app
+        .get_json("/admin/users", Some(&admin.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let accounts = body.as_array().unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0]["id"], admin.id.to_string());
+    assert_eq!(accounts[0]["role"], "admin");
+
+    let account = &accounts[1];
+    assert_eq!(account["email"], user.email);
+    assert_eq!(account["role"], "user");
+    assert_eq!(account["disabled_at"], Value::Null);
+    assert_eq!(account["two_factor_enabled"], false);
+    assert_eq!(account["usage"]["active_sessions"], 1);
+    assert_eq!(account["usage"]["groups"], 1);
+    assert_eq!(account["usage"]["vk_users"], 2);
+    assert_eq!(account["usage"]["notes"], 0);
+    assert_ne!(account["usage"]["last_crawl_at"], Value::Null);
+
+    let (_, body) = app
+        .get_json("/admin/users?limit=1&offset=1", Some(&admin.access_token))
+        .await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["id"], user.id.to_string());
+}
+
+#[sqlx::test]
+async fn disabled_account_cannot_log_in_or_refresh(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+    let user = app.register_and_login().await;
+    let (_, created) = app
+        .post_json(
+            "/api-keys",
+            json!({ "name": "script", "scopes": ["groups:read"] }),
+            Some(&user.access_token),
+        )
+        .await;
+    let key = created["key"].as_str().unwrap();
+
+    let (status, _) = app
+        .post_json(
+            &format!("/admin/users/{}/disable", user.id),
+            json!({}),
+            Some(&admin.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.get_json("/groups", Some(key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let login = json!({ "email": user.email, "password": "strong-password-123" });
+    let (status, body) = app.post_json("/auth/login", login.clone(), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "ACCOUNT_DISABLED");
+
+    let (_, body) = app
+        .get_json("/admin/users", Some(&admin.access_token))
+        .await;
+    assert_ne!(body[1]["disabled_at"], Value::Null);
+    assert_eq!(body[1]["usage"]["active_sessions"], 0);
+
+    let (status, _) = app
+        .post_json(
+            &format!("/admin/users/{}/enable", user.id),
+            json!({}),
+            Some(&admin.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = app.post_json("/auth/login", login, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .get_json("/me", Some(body["access_token"].as_str().unwrap()))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get_json("/groups", Some(key)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn refresh_is_refused_while_the_account_is_disabled(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    sqlx::query("UPDATE users SET disabled_at = now() WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "ACCOUNT_DISABLED");
+}
+
+#[sqlx::test]
+async fn disable_rejects_self_and_unknown_accounts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+
+    let (status, _) = app
+        .post_json(
+            &format!("/admin/users/{}/disable", admin.id),
+            json!({}),
+            Some(&admin.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for action in ["disable", "enable", "sessions/revoke"] {
+        let (status, _) = app
+            .post_json(
+                &format!("/admin/users/{}/{action}", Uuid::new_v4()),
+                json!({}),
+                Some(&admin.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
+
+#[sqlx::test]
+async fn admin_force_revokes_sessions(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+    let user = app.register_and_login().await;
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .post_json(
+            &format!("/admin/users/{}/sessions/revoke", user.id),
+            json!({}),
+            Some(&admin.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked"], 2);
+
+    let (status, _) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn crawl_health_aggregates_across_accounts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+    let healthy = app.register_and_login().await;
+    let failing = app.register_and_login().await;
+
+    record_crawl(
+        &pool,
+        healthy.id,
+        json!({ "source": "batch", "status": "succeeded" }),
+    )
+    .await;
+    record_crawl(
+        &pool,
+        failing.id,
+        json!({ "source": "wall.get", "status": "succeeded" }),
+    )
+    .await;
+    record_crawl(
+        &pool,
+        failing.id,
+        json!({ "source": "wall.get", "status": "failed", "error": "token expired" }),
+    )
+    .await;
+    sqlx::query(
+        "INSERT INTO stream_events (user_id, event_type, data, created_at) \
+         VALUES ($1, 'crawl.finished', $2, now() - interval '2 days')",
+    )
+    .bind(healthy.id)
+    .bind(json!({ "source": "batch", "status": "failed", "error": "old" }))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app
+        .get_json("/admin/crawl-health", Some(&admin.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["failed"], 1);
+    assert_eq!(body["active_accounts"], 2);
+    assert_eq!(body["failing_accounts"], 1);
+    assert_ne!(body["last_failure_at"], Value::Null);
+    assert_eq!(
+        body["by_source"],
+        json!([
+            { "source": "batch", "succeeded": 1, "failed": 0 },
+            { "source": "wall.get", "succeeded": 1, "failed": 1 },
+        ])
+    );
+    let top = body["top_failing_accounts"].as_array().unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0]["user_id"], failing.id.to_string());
+    assert_eq!(top[0]["email"], failing.email);
+    assert_eq!(top[0]["failed"], 1);
+    assert_eq!(top[0]["succeeded"], 1);
+    assert_eq!(top[0]["last_error"], "token expired");
+
+    let (_, body) = app
+        .get_json("/admin/crawl-health?hours=72", Some(&admin.access_token))
+        .await;
+    assert_eq!(body["failed"], 2);
+    assert_eq!(body["failing_accounts"], 2);
+
+    for hours in [0, 169] {
+        let (status, _) = app
+            .get_json(
+                &format!("/admin/crawl-health?hours={hours}"),
+                Some(&admin.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[sqlx::test]
+async fn only_verified_accounts_are_promoted(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let admin = admin(&app, &pool).await;
+    let unverified = app.register_and_login().await;
+
+    let emails = vec![
+        admin.email.to_uppercase(),
+        unverified.email.clone(),
+        "nobody@example.com".to_string(),
+    ];
+    let admins = find_w::admin::repo::promote_admins(&pool, &emails)
+        .await
+        .unwrap();
+    assert_eq!(admins, vec![admin.email.clone()]);
+
+    let (_, body) = app.get_json("/me", Some(&unverified.access_token)).await;
+    assert_eq!(body["role"], "user");
+    let (status, _) = app
+        .get_json("/admin/users", Some(&unverified.access_token))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
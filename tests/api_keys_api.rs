@@ -0,0 +1,211 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn create_key(app: &TestApp, access_token: &str, scopes: &[&str]) -> Value {
+    let (status, body) = app
+        .post_json(
+            "/api-keys",
+            json!({ "name": "CI export", "scopes": scopes }),
+            Some(access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    body
+}
+
+#[sqlx::test]
+async fn api_key_is_shown_once_and_stored_hashed(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let created = create_key(&app, &user.access_token, &["groups:read"]).await;
+    let key = created["key"].as_str().unwrap();
+    assert!(key.starts_with("fwk_"));
+    assert!(key.starts_with(created["key_prefix"].as_str().unwrap()));
+    assert_eq!(created["scopes"], json!(["groups:read"]));
+
+    let (status, body) = app.get_json("/api-keys", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let keys = body.as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["id"], created["id"]);
+    assert_eq!(keys[0]["name"], "CI export");
+    assert!(keys[0].get("key").is_none());
+    assert_eq!(keys[0]["last_used_at"], Value::Null);
+
+    let stored: String = sqlx::query_scalar("SELECT key_hash FROM api_keys")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_ne!(stored, key);
+    assert!(!stored.contains(&key[4..]));
+}
+
+#[sqlx::test]
+async fn api_key_authenticates_as_its_owner(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let created = create_key(&app, &user.access_token, &["groups:read"]).await;
+    let key = created["key"].as_str().unwrap();
+
+    let (status, _) = app
+        .post_json(
+            "/groups",
+            json!({ "group_id": 12345, "group_name": "Rustaceans", "screen_name": "rustaceans" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = app.get_json("/groups", Some(key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let (_, body) = app.get_json("/api-keys", Some(&user.access_token)).await;
+    assert_ne!(body[0]["last_used_at"], Value::Null);
+}
+
+#[sqlx::test]
+async fn api_key_is_limited_to_its_scopes(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let created = create_key(&app, &user.access_token, &["groups:read"]).await;
+    let key = created["key"].as_str().unwrap();
+
+    let (status, body) = app
+        .post_json(
+            "/groups",
+            json!({ "group_id": 12345, "group_name": "Rustaceans", "screen_name": "rustaceans" }),
+            Some(key),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "FORBIDDEN");
+
+    let (status, _) = app.get_json("/notes", Some(key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn api_key_cannot_manage_the_account(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let all_scopes = [
+        "vk_users:read",
+        "vk_users:write",
+        "groups:read",
+        "groups:write",
+        "notes:read",
+        "notes:write",
+        "searches:read",
+        "searches:write",
+        "webhooks:read",
+        "webhooks:write",
+        "ingest",
+    ];
+    let created = create_key(&app, &user.access_token, &all_scopes).await;
+    let key = created["key"].as_str().unwrap();
+
+    let (status, _) = app.get_json("/me", Some(key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app.get_json("/api-keys", Some(key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .post_json(
+            "/api-keys",
+            json!({ "name": "escalate", "scopes": ["ingest"] }),
+            Some(key),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app.get_json("/me/sessions", Some(key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app.get_json("/settings", Some(key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .post_json(
+            "/me/password",
+            json!({ "current_password": "strong-password-123", "new_password": "another-password-456" }),
+            Some(key),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn create_api_key_validates_input(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    for body in [
+        json!({ "name": "  ", "scopes": ["ingest"] }),
+        json!({ "name": "x", "scopes": [] }),
+        json!({ "name": "x", "scopes": ["admin"] }),
+        json!({ "name": "x", "scopes": ["ingest"], "expires_in_days": 0 }),
+        json!({ "name": "x", "scopes": ["ingest"], "expires_in_days": 366 }),
+    ] {
+        let (status, _) = app
+            .post_json("/api-keys", body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[sqlx::test]
+async fn revoked_api_key_is_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    let created = create_key(&app, &user.access_token, &["groups:read"]).await;
+    let key = created["key"].as_str().unwrap();
+    let path = format!("/api-keys/{}", created["id"].as_str().unwrap());
+
+    let status = app.delete(&path, Some(&other.access_token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let status = app.delete(&path, Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app.get_json("/groups", Some(key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, body) = app.get_json("/api-keys", Some(&user.access_token)).await;
+    assert!(body.as_array().unwrap().is_empty());
+
+    let status = app
+        .delete(
+            &format!("/api-keys/{}", Uuid::new_v4()),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn expired_api_key_is_rejected(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let created = create_key(&app, &user.access_token, &["groups:read"]).await;
+    let key = created["key"].as_str().unwrap();
+
+    sqlx::query("UPDATE api_keys SET expires_at = now() - interval '1 minute'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app.get_json("/groups", Some(key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.get_json("/groups", Some("fwk_not-a-real-key")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
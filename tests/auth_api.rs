@@ -95,7 +95,7 @@ async fn login_with_wrong_password_returns_unauthorized(pool: PgPool) {
 
 #[sqlx::test]
 async fn refresh_rotates_tokens_and_old_refresh_becomes_invalid(pool: PgPool) {
-    let app = TestApp::new(pool);
+    let app = TestApp::new(pool.clone());
     let user = app.register_and_login().await;
 
     let (status, refresh_json) = app
@@ -120,6 +120,19 @@ async fn refresh_rotates_tokens_and_old_refresh_becomes_invalid(pool: PgPool) {
     assert_ne!(new_refresh_token, user.refresh_token);
     assert!(!new_access_token.is_empty());
 
+    let (status, me_json) = app.get_json("/me", Some(new_access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let expected_id = user.id.to_string();
+    assert_eq!(
+        me_json.get("id").and_then(serde_json::Value::as_str),
+        Some(expected_id.as_str())
+    );
+
+    // Move the rotation out of the retry grace window.
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = now() - interval '1 minute' WHERE replaced_by IS NOT NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
     let (status, _) = app
         .post_json(
             "/auth/refresh",
@@ -131,13 +144,9 @@ async fn refresh_rotates_tokens_and_old_refresh_becomes_invalid(pool: PgPool) {
         .await;
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let (status, me_json) = app.get_json("/me", Some(new_access_token)).await;
-    assert_eq!(status, StatusCode::OK);
-    let expected_id = user.id.to_string();
-    assert_eq!(
-        me_json.get("id").and_then(serde_json::Value::as_str),
-        Some(expected_id.as_str())
-    );
+    // Reusing a rotated token ends the session, access tokens included.
+    let (status, _) = app.get_json("/me", Some(new_access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
 }
 
 #[sqlx::test]
@@ -1,12 +1,26 @@
 #![allow(dead_code)]
 
+pub mod smtp;
+
+use std::net::SocketAddr;
+
 use axum::{
     Router,
     body::{Body, to_bytes},
-    http::{Method, Request, StatusCode, header},
+    extract::connect_info::MockConnectInfo,
+    http::{HeaderMap, Method, Request, StatusCode, header},
 };
 use find_w::vk_users::repo::NewVkUser;
-use find_w::{AppState, app::router::build_router};
+use find_w::{
+    AppState,
+    app::{router::build_router, trusted_proxies::TrustedProxies},
+    auth::{
+        login_throttle::LoginThrottlePolicy, session_cache::SessionCache,
+        verification::EmailVerificationPolicy,
+    },
+    event_stream::notifier::StreamNotifier,
+    webhooks::{sender::WebhookClient, targets::WebhookTargetPolicy},
+};
 use find_w::{
     groups::repo::NewGroup, vk_posts::repo as vk_posts_repo, vk_posts::repo::NewVkPost,
     vk_users::repo as vk_users_repo,
@@ -21,6 +35,12 @@ use uuid::Uuid;
 pub const TEST_JWT_SECRET: &str = "integration-test-jwt-secret";
 pub const TEST_VK_TOKEN_ENC_KEY: &str = "integration-test-vk-token-enc-key";
 
+/// Requests reach the app from this address, which sits inside the trusted
+/// proxy ranges unless a test overrides `trusted_proxies`.
+pub const TEST_PEER: ([u8; 4], u16) = ([127, 0, 0, 1], 40000);
+
+pub const TEST_TRUSTED_PROXIES: &str = "127.0.0.0/8, 10.0.0.0/8";
+
 pub struct TestApp {
     app: Router,
 }
@@ -47,14 +67,14 @@ pub async fn create_user(db: &PgPool) -> Uuid {
     .expect("failed to create test user")
 }
 
-pub fn sample_vk_user(vk_user_id: i64, first_name: &str, finded_date: OffsetDateTime) -> NewVkUser {
+pub fn sample_vk_user(vk_user_id: i64, first_name: &str, seen_at: OffsetDateTime) -> NewVkUser {
     NewVkUser {
         vk_user_id,
         sex: Some(1),
         first_name: Some(first_name.to_string()),
         last_name: Some("Ivanov".to_string()),
         city: Some("Moscow".to_string()),
-        finded_date,
+        seen_at,
         is_closed: Some(false),
         screen_name: Some(format!("screen_{vk_user_id}")),
         can_access_closed: Some(true),
@@ -62,9 +82,17 @@ pub fn sample_vk_user(vk_user_id: i64, first_name: &str, finded_date: OffsetDate
         status: Some(format!("status_{vk_user_id}")),
         bdate: Some("01.01.1990".to_string()),
         photo: Some(format!("https://img.test/{vk_user_id}.jpg")),
+        source_group_id: None,
     }
 }
 
+pub async fn discard_verification_emails(db: &PgPool) {
+    sqlx::query!("DELETE FROM email_outbox WHERE kind = 'verification'")
+        .execute(db)
+        .await
+        .expect("failed to discard verification emails");
+}
+
 pub async fn seed_group(db: &PgPool, user_id: Uuid, group_id: i64) {
     find_w::groups::repo::save_group(
         db,
@@ -124,15 +152,27 @@ pub async fn seed_post(
 
 impl TestApp {
     pub fn new(db: PgPool) -> Self {
-        let state = AppState {
+        Self::with_state(db, |_| {})
+    }
+
+    pub fn with_state(db: PgPool, configure: impl FnOnce(&mut AppState)) -> Self {
+        let mut state = AppState {
             db,
             jwt_enc: EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
             jwt_dec: DecodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
             vk_token_enc_key: TEST_VK_TOKEN_ENC_KEY.to_string(),
+            email_verification: EmailVerificationPolicy::Off,
+            public_base_url: "http://localhost:3000".to_string(),
+            sessions: SessionCache::default(),
+            login_throttle: LoginThrottlePolicy::default(),
+            trusted_proxies: TrustedProxies::parse(TEST_TRUSTED_PROXIES).unwrap(),
+            stream_notifier: StreamNotifier::default(),
+            webhooks: WebhookClient::new(WebhookTargetPolicy::AllowPrivate),
         };
+        configure(&mut state);
 
         Self {
-            app: build_router(state),
+            app: build_router(state).layer(MockConnectInfo(SocketAddr::from(TEST_PEER))),
         }
     }
 
@@ -146,6 +186,34 @@ impl TestApp {
             .await
     }
 
+    pub async fn post_json_with_headers(
+        &self,
+        path: &str,
+        body: Value,
+        bearer: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Value) {
+        self.request_json_with_headers(Method::POST, path, Some(body), bearer, headers)
+            .await
+    }
+
+    pub async fn post_json_with_response_headers(
+        &self,
+        path: &str,
+        body: Value,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, HeaderMap, Value) {
+        let (status, response_headers, bytes) = self
+            .send(Method::POST, path, Some(body), None, headers)
+            .await;
+        let json = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).expect("response body is not valid json")
+        };
+        (status, response_headers, json)
+    }
+
     pub async fn delete_json(
         &self,
         path: &str,
@@ -166,16 +234,61 @@ impl TestApp {
             .await
     }
 
+    pub async fn put_json(
+        &self,
+        path: &str,
+        body: Value,
+        bearer: Option<&str>,
+    ) -> (StatusCode, Value) {
+        self.request_json(Method::PUT, path, Some(body), bearer)
+            .await
+    }
+
     pub async fn get_json(&self, path: &str, bearer: Option<&str>) -> (StatusCode, Value) {
         self.request_json(Method::GET, path, None, bearer).await
     }
 
     pub async fn get_text(&self, path: &str, bearer: Option<&str>) -> (StatusCode, String) {
-        let (status, bytes) = self.request(Method::GET, path, None, bearer).await;
+        let (status, bytes) = self.request(Method::GET, path, None, bearer, &[]).await;
         let text = String::from_utf8(bytes).expect("response is not valid utf-8 text");
         (status, text)
     }
 
+    pub async fn get_bytes(&self, path: &str, bearer: Option<&str>) -> (StatusCode, Vec<u8>) {
+        self.request(Method::GET, path, None, bearer, &[]).await
+    }
+
+    pub async fn get_stream(
+        &self,
+        path: &str,
+        bearer: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Body) {
+        let mut req_builder = Request::builder().method(Method::GET).uri(path);
+        if let Some(token) = bearer {
+            req_builder = req_builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        for (name, value) in headers {
+            req_builder = req_builder.header(*name, *value);
+        }
+
+        let req = req_builder
+            .body(Body::empty())
+            .expect("failed to build request");
+        let response = self
+            .app
+            .clone()
+            .oneshot(req)
+            .await
+            .expect("request execution failed");
+
+        (response.status(), response.into_body())
+    }
+
+    pub async fn put(&self, path: &str, bearer: Option<&str>) -> StatusCode {
+        self.request_status(Method::PUT, path, bearer).await
+    }
+
     pub async fn delete(&self, path: &str, bearer: Option<&str>) -> StatusCode {
         self.request_status(Method::DELETE, path, bearer).await
     }
@@ -187,7 +300,19 @@ impl TestApp {
         body: Option<Value>,
         bearer: Option<&str>,
     ) -> (StatusCode, Value) {
-        let (status, bytes) = self.request(method, path, body, bearer).await;
+        self.request_json_with_headers(method, path, body, bearer, &[])
+            .await
+    }
+
+    async fn request_json_with_headers(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+        bearer: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Value) {
+        let (status, bytes) = self.request(method, path, body, bearer, headers).await;
 
         if bytes.is_empty() {
             return (status, Value::Null);
@@ -198,7 +323,7 @@ impl TestApp {
     }
 
     async fn request_status(&self, method: Method, path: &str, bearer: Option<&str>) -> StatusCode {
-        let (status, _) = self.request(method, path, None, bearer).await;
+        let (status, _) = self.request(method, path, None, bearer, &[]).await;
         status
     }
 
@@ -208,8 +333,24 @@ impl TestApp {
         path: &str,
         body: Option<Value>,
         bearer: Option<&str>,
+        headers: &[(&str, &str)],
     ) -> (StatusCode, Vec<u8>) {
+        let (status, _, bytes) = self.send(method, path, body, bearer, headers).await;
+        (status, bytes)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+        bearer: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, HeaderMap, Vec<u8>) {
         let mut req_builder = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            req_builder = req_builder.header(*name, *value);
+        }
 
         if let Some(token) = bearer {
             req_builder = req_builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
@@ -232,11 +373,12 @@ impl TestApp {
             .expect("request execution failed");
 
         let status = response.status();
+        let response_headers = response.headers().clone();
         let bytes = to_bytes(response.into_body(), 1024 * 1024)
             .await
             .expect("failed to read response body");
 
-        (status, bytes.to_vec())
+        (status, response_headers, bytes.to_vec())
     }
 
     pub async fn register_and_login(&self) -> TestUser {
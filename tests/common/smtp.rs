@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use find_w::notifications::mailer::{Mailer, SmtpConfig, SmtpTls};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+#[derive(Clone, Debug)]
+pub struct SentEmail {
+    pub recipients: Vec<String>,
+    pub data: String,
+}
+
+#[derive(Clone, Default)]
+pub struct SmtpSink {
+    pub messages: Arc<Mutex<Vec<SentEmail>>>,
+}
+
+async fn handle_smtp(sink: SmtpSink, stream: tokio::net::TcpStream) -> std::io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut recipients = Vec::new();
+
+    write.write_all(b"220 sink ready\r\n").await?;
+    while let Some(line) = lines.next_line().await? {
+        let command = line.to_ascii_uppercase();
+        if command.starts_with("RCPT TO:") {
+            recipients.push(line[8..].trim_matches(['<', '>', ' ']).to_string());
+            write.write_all(b"250 ok\r\n").await?;
+        } else if command == "DATA" {
+            write.write_all(b"354 go ahead\r\n").await?;
+            let mut data = String::new();
+            while let Some(line) = lines.next_line().await? {
+                if line == "." {
+                    break;
+                }
+                data.push_str(&line);
+                data.push('\n');
+            }
+            sink.messages.lock().unwrap().push(SentEmail {
+                recipients: std::mem::take(&mut recipients),
+                data,
+            });
+            write.write_all(b"250 queued\r\n").await?;
+        } else if command == "QUIT" {
+            write.write_all(b"221 bye\r\n").await?;
+            break;
+        } else {
+            write.write_all(b"250 ok\r\n").await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn start_smtp_sink() -> (Mailer, SmtpSink) {
+    let sink = SmtpSink::default();
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind smtp sink");
+    let port = listener.local_addr().expect("sink has no address").port();
+
+    let accept_sink = sink.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_smtp(accept_sink.clone(), stream));
+        }
+    });
+
+    let mailer = Mailer::new(&SmtpConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        tls: SmtpTls::None,
+        username: None,
+        password: None,
+        from: "find-w <noreply@find-w.test>".to_string(),
+    })
+    .expect("failed to build mailer");
+
+    (mailer, sink)
+}
+
+impl SentEmail {
+    pub fn token_after(&self, intro: &str) -> Option<String> {
+        self.data
+            .lines()
+            .skip_while(|line| !line.starts_with(intro))
+            .map(str::trim)
+            .find(|line| line.len() == 43)
+            .map(str::to_string)
+    }
+
+    pub fn link_after(&self, prefix: &str) -> Option<String> {
+        let joined = self.data.replace("=\r\n", "").replace("=\n", "");
+        let mut decoded = String::with_capacity(joined.len());
+        let mut rest = joined.as_str();
+        while let Some(pos) = rest.find('=') {
+            decoded.push_str(&rest[..pos]);
+            match rest
+                .get(pos + 1..pos + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte as char);
+                    rest = &rest[pos + 3..];
+                }
+                None => {
+                    decoded.push('=');
+                    rest = &rest[pos + 1..];
+                }
+            }
+        }
+        decoded.push_str(rest);
+
+        decoded
+            .lines()
+            .map(str::trim)
+            .find_map(|line| line.strip_prefix(prefix))
+            .map(str::to_string)
+    }
+}
@@ -46,4 +46,8 @@ async fn docs_and_openapi_are_available(pool: PgPool) {
             .and_then(serde_json::Value::as_str),
         Some("3.1.0")
     );
+    assert_eq!(
+        openapi_json["components"]["schemas"]["VkUserDto"]["properties"]["finded_date"]["deprecated"],
+        true
+    );
 }
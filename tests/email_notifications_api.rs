@@ -0,0 +1,305 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{
+    TestApp, discard_verification_emails, sample_vk_user, seed_group, smtp::start_smtp_sink,
+};
+use find_w::notifications::mailer::{Mailer, SmtpConfig, SmtpTls};
+use serde_json::json;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use tokio::net::TcpListener;
+
+#[sqlx::test]
+async fn email_settings_default_off_and_validate(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, body) = app.get_json("/settings", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email_notify_matches"], false);
+    assert_eq!(body["email_notify_crawl_failures"], false);
+    assert_eq!(body["email_digest"], "off");
+
+    let (status, _) = app
+        .patch_json(
+            "/settings",
+            json!({ "email_digest": "hourly" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .patch_json(
+            "/settings",
+            json!({ "email_notify_matches": true, "email_digest": "weekly" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email_notify_matches"], true);
+    assert_eq!(body["email_notify_crawl_failures"], false);
+    assert_eq!(body["email_digest"], "weekly");
+    assert_eq!(body["search_interval_minutes"], 60);
+}
+
+#[sqlx::test]
+async fn match_and_crawl_failure_emails_respect_opt_in(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "error": { "error_code": 5, "error_msg": "User authorization failed" } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .patch_json(
+            "/settings",
+            json!({ "email_notify_matches": true, "email_notify_crawl_failures": true }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/saved-searches",
+            json!({ "name": "everyone in Moscow", "city": "Moscow" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    find_w::vk_users::repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[sample_vk_user(1000, "Ivan", OffsetDateTime::now_utc())],
+    )
+    .await
+    .expect("failed to seed vk user");
+    find_w::scheduler::run_due_searches(&pool)
+        .await
+        .expect("scheduler run failed");
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "error": { "error_code": 5, "error_msg": "User authorization failed" } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let sent = find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    assert_eq!(sent, 2);
+
+    let messages = sink.messages.lock().unwrap().clone();
+    assert_eq!(messages.len(), 2);
+    for message in &messages {
+        assert_eq!(message.recipients, vec![user.email.clone()]);
+    }
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.data.contains("1 new saved search matches")
+                && m.data.contains("https://vk.com/id1000"))
+    );
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.data.contains("Crawl failed: users.get"))
+    );
+
+    let pending = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM email_outbox WHERE status <> 'sent'"#
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to count unsent emails");
+    assert_eq!(pending, 0);
+}
+
+#[sqlx::test]
+async fn digest_summarises_new_vk_users_per_group(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+    seed_group(&pool, user.id, 10).await;
+    seed_group(&pool, user.id, 20).await;
+
+    let (status, _) = app
+        .patch_json(
+            "/settings",
+            json!({ "email_digest": "daily" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let now = OffsetDateTime::now_utc();
+    let mut users = Vec::new();
+    for (vk_user_id, group_id) in [(1000, 10), (1001, 10), (1002, 20)] {
+        let mut vk_user = sample_vk_user(vk_user_id, "Ivan", now);
+        vk_user.source_group_id = Some(group_id);
+        users.push(vk_user);
+    }
+    find_w::vk_users::repo::upsert_vk_users(&pool, user.id, &users)
+        .await
+        .expect("failed to seed vk users");
+
+    let enqueued = find_w::notifications::sender::enqueue_due_digests(&pool)
+        .await
+        .expect("failed to enqueue digests");
+    assert_eq!(enqueued, 1);
+    let enqueued = find_w::notifications::sender::enqueue_due_digests(&pool)
+        .await
+        .expect("failed to enqueue digests");
+    assert_eq!(enqueued, 0);
+
+    let sent = find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    assert_eq!(sent, 1);
+
+    let messages = sink.messages.lock().unwrap().clone();
+    assert_eq!(messages.len(), 1);
+    let data = &messages[0].data;
+    assert!(data.contains("Daily digest: 3 new VK users"));
+    assert!(data.contains("group-10 (club10): 2"));
+    assert!(data.contains("group-20 (club20): 1"));
+}
+
+#[sqlx::test]
+async fn one_failing_digest_does_not_hold_back_the_others(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let mut users = Vec::new();
+    for group_id in [10, 20] {
+        let user = app.register_and_login().await;
+        seed_group(&pool, user.id, group_id).await;
+        let (status, _) = app
+            .patch_json(
+                "/settings",
+                json!({ "email_digest": "daily" }),
+                Some(&user.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let mut vk_user = sample_vk_user(1000, "Ivan", OffsetDateTime::now_utc());
+        vk_user.source_group_id = Some(group_id);
+        find_w::vk_users::repo::upsert_vk_users(&pool, user.id, &[vk_user])
+            .await
+            .expect("failed to seed vk users");
+        users.push(user);
+    }
+    discard_verification_emails(&pool).await;
+    let (broken, healthy) = (&users[0], &users[1]);
+
+    // Only the first user's digest cannot be queued.
+    sqlx::query(&format!(
+        "ALTER TABLE email_outbox ADD CONSTRAINT broken_digest \
+         CHECK (kind <> 'digest' OR user_id <> '{}')",
+        broken.id
+    ))
+    .execute(&pool)
+    .await
+    .expect("failed to break one digest");
+
+    let enqueued = find_w::notifications::sender::enqueue_due_digests(&pool)
+        .await
+        .expect("failed to enqueue digests");
+    assert_eq!(enqueued, 1);
+    let last_digest_at = |user_id| {
+        sqlx::query_scalar::<_, Option<OffsetDateTime>>(
+            "SELECT last_digest_at FROM user_settings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+    };
+    assert!(last_digest_at(healthy.id).await.unwrap().is_some());
+    assert_eq!(last_digest_at(broken.id).await.unwrap(), None);
+
+    sqlx::query("ALTER TABLE email_outbox DROP CONSTRAINT broken_digest")
+        .execute(&pool)
+        .await
+        .expect("failed to repair digest");
+    let enqueued = find_w::notifications::sender::enqueue_due_digests(&pool)
+        .await
+        .expect("failed to enqueue digests");
+    assert_eq!(enqueued, 1);
+    assert!(last_digest_at(broken.id).await.unwrap().is_some());
+}
+
+#[sqlx::test]
+async fn failed_email_is_retried_with_backoff(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    sqlx::query!(
+        "UPDATE user_settings SET email_notify_crawl_failures = true WHERE user_id = $1",
+        user.id
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to opt in");
+
+    let enqueued =
+        find_w::notifications::sender::enqueue_crawl_failed_email(&pool, user.id, "batch", "boom")
+            .await
+            .expect("failed to enqueue email");
+    assert!(enqueued);
+
+    let closed_port = {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind");
+        listener.local_addr().expect("no address").port()
+    };
+    let unreachable = Mailer::new(&SmtpConfig {
+        host: "127.0.0.1".to_string(),
+        port: closed_port,
+        tls: SmtpTls::None,
+        username: None,
+        password: None,
+        from: "noreply@find-w.test".to_string(),
+    })
+    .expect("failed to build mailer");
+
+    let sent = find_w::notifications::sender::send_due(&pool, &unreachable)
+        .await
+        .expect("failed to send due emails");
+    assert_eq!(sent, 0);
+
+    let row = sqlx::query!(
+        r#"SELECT status, attempts, last_error, next_attempt_at > now() AS "delayed!" FROM email_outbox"#
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to fetch email");
+    assert_eq!(row.status, "pending");
+    assert_eq!(row.attempts, 1);
+    assert!(row.last_error.is_some());
+    assert!(row.delayed);
+
+    let (mailer, sink) = start_smtp_sink().await;
+    sqlx::query!("UPDATE email_outbox SET next_attempt_at = now()")
+        .execute(&pool)
+        .await
+        .expect("failed to make email due");
+    let sent = find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    assert_eq!(sent, 1);
+    assert_eq!(sink.messages.lock().unwrap().len(), 1);
+}
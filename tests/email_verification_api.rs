@@ -0,0 +1,278 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use common::{
+    TestApp,
+    smtp::{SmtpSink, start_smtp_sink},
+};
+use find_w::{auth::verification::EmailVerificationPolicy, notifications::mailer::Mailer};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+const PASSWORD: &str = "strong-password-123";
+
+async fn register(app: &TestApp, email: &str) -> StatusCode {
+    let (status, _) = app
+        .post_json(
+            "/auth/register",
+            json!({ "email": email, "password": PASSWORD }),
+            None,
+        )
+        .await;
+    status
+}
+
+async fn login(app: &TestApp, email: &str) -> (StatusCode, Value) {
+    app.post_json(
+        "/auth/login",
+        json!({ "email": email, "password": PASSWORD }),
+        None,
+    )
+    .await
+}
+
+/// Resent verification emails are queued by a background task after the 202.
+async fn wait_for_verification_emails(pool: &PgPool, expected: i64) {
+    for _ in 0..100 {
+        let queued = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM email_outbox WHERE kind = 'verification'"#
+        )
+        .fetch_one(pool)
+        .await
+        .expect("failed to count verification emails");
+        if queued >= expected {
+            assert_eq!(queued, expected);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("verification email was not queued");
+}
+
+async fn deliver_tokens(pool: &PgPool, mailer: &Mailer, sink: &SmtpSink) -> Vec<String> {
+    find_w::notifications::sender::send_due(pool, mailer)
+        .await
+        .expect("failed to send due emails");
+
+    sink.messages
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter(|message| message.data.contains("Verify your email address"))
+        .map(|message| {
+            message
+                .token_after("Use this token")
+                .expect("email misses verification token")
+        })
+        .collect()
+}
+
+#[sqlx::test]
+async fn register_rejects_malformed_emails(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    for email in [
+        "",
+        "plain",
+        "@example.test",
+        "user@",
+        "user@localhost",
+        "user@@example.test",
+        "us er@example.test",
+        "user@-example.test",
+        "user@example..test",
+    ] {
+        assert_eq!(
+            register(&app, email).await,
+            StatusCode::BAD_REQUEST,
+            "{email}"
+        );
+    }
+    assert_eq!(
+        register(&app, " user@example.test ").await,
+        StatusCode::CREATED
+    );
+}
+
+#[sqlx::test]
+async fn emailed_token_verifies_the_account_once(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let (mailer, sink) = start_smtp_sink().await;
+    let email = "verify-me@example.test";
+    assert_eq!(register(&app, email).await, StatusCode::CREATED);
+
+    let (_, tokens) = login(&app, email).await;
+    let access_token = tokens["access_token"].as_str().unwrap().to_string();
+    let (_, me) = app.get_json("/me", Some(&access_token)).await;
+    assert_eq!(me["email_verified_at"], Value::Null);
+
+    let tokens = deliver_tokens(&pool, &mailer, &sink).await;
+    assert_eq!(tokens.len(), 1);
+
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": "bogus" }), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": tokens[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, me) = app.get_json("/me", Some(&access_token)).await;
+    assert_ne!(me["email_verified_at"], Value::Null);
+
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": tokens[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json("/auth/resend-verification", json!({ "email": email }), None)
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(deliver_tokens(&pool, &mailer, &sink).await.is_empty());
+}
+
+#[sqlx::test]
+async fn resend_is_throttled_and_expired_tokens_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let (mailer, sink) = start_smtp_sink().await;
+    let email = "resend@example.test";
+    assert_eq!(register(&app, email).await, StatusCode::CREATED);
+
+    for email in [email, "nobody@example.test"] {
+        let (status, _) = app
+            .post_json("/auth/resend-verification", json!({ "email": email }), None)
+            .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    wait_for_verification_emails(&pool, 1).await;
+    let first = deliver_tokens(&pool, &mailer, &sink).await;
+    assert_eq!(first.len(), 1);
+
+    sqlx::query!(
+        "UPDATE email_verification_tokens SET created_at = now() - interval '2 minutes', expires_at = now()"
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to age verification tokens");
+
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": first[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/resend-verification",
+            json!({ "email": "RESEND@example.test" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    wait_for_verification_emails(&pool, 2).await;
+    let second = deliver_tokens(&pool, &mailer, &sink).await;
+    assert_eq!(second.len(), 1);
+    assert_ne!(second[0], first[0]);
+
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": second[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[sqlx::test]
+async fn resend_is_rate_limited_per_client(pool: PgPool) {
+    let app = TestApp::with_state(pool, |state| {
+        state.login_throttle.verification.free_attempts = 2;
+        state.login_throttle.verification.lockout_after = 3;
+    });
+
+    for i in 0..3 {
+        let (status, _) = app
+            .post_json(
+                "/auth/resend-verification",
+                json!({ "email": format!("nobody-{i}@example.test") }),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let (status, body) = app
+        .post_json(
+            "/auth/resend-verification",
+            json!({ "email": "someone-else@example.test" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+}
+
+#[sqlx::test]
+async fn login_policy_blocks_unverified_accounts(pool: PgPool) {
+    let app = TestApp::with_state(pool.clone(), |state| {
+        state.email_verification = EmailVerificationPolicy::Login;
+    });
+    let (mailer, sink) = start_smtp_sink().await;
+    let email = "blocked-login@example.test";
+    assert_eq!(register(&app, email).await, StatusCode::CREATED);
+
+    let (status, body) = login(&app, email).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "EMAIL_NOT_VERIFIED");
+
+    let tokens = deliver_tokens(&pool, &mailer, &sink).await;
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": tokens[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = login(&app, email).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn crawl_policy_blocks_ingest_for_unverified_accounts(pool: PgPool) {
+    let app = TestApp::with_state(pool.clone(), |state| {
+        state.email_verification = EmailVerificationPolicy::Crawl;
+    });
+    let (mailer, sink) = start_smtp_sink().await;
+    let user = app.register_and_login().await;
+    let batch =
+        json!({ "users": [], "posts": [], "comments": [], "post_likes": [], "comment_likes": [] });
+
+    let (status, body) = app
+        .post_json("/ingest/batch", batch.clone(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "EMAIL_NOT_VERIFIED");
+
+    let tokens = deliver_tokens(&pool, &mailer, &sink).await;
+    let (status, _) = app
+        .post_json("/auth/verify-email", json!({ "token": tokens[0] }), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app
+        .post_json("/ingest/batch", batch, Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[test]
+fn blocking_policies_require_a_mailer() {
+    assert!(EmailVerificationPolicy::Off.check_mailer(false).is_ok());
+    for policy in [
+        EmailVerificationPolicy::Login,
+        EmailVerificationPolicy::Crawl,
+    ] {
+        assert!(policy.check_mailer(false).is_err());
+        assert!(policy.check_mailer(true).is_ok());
+    }
+}
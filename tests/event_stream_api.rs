@@ -0,0 +1,277 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::{body::Body, http::StatusCode};
+use common::{TestApp, seed_group};
+use find_w::event_stream::repo::StreamEventKind;
+use futures_util::StreamExt;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+#[derive(Debug)]
+struct SseEvent {
+    id: i64,
+    event: String,
+    data: Value,
+}
+
+struct SseReader {
+    body: axum::body::BodyDataStream,
+    buffer: String,
+}
+
+impl SseReader {
+    fn new(body: Body) -> Self {
+        Self {
+            body: body.into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    async fn next_event(&mut self) -> SseEvent {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let frame: String = self.buffer.drain(..end + 2).collect();
+                let mut id = None;
+                let mut event = None;
+                let mut data = None;
+                for line in frame.lines() {
+                    if let Some(value) = line.strip_prefix("id: ") {
+                        id = value.parse().ok();
+                    } else if let Some(value) = line.strip_prefix("event: ") {
+                        event = Some(value.to_string());
+                    } else if let Some(value) = line.strip_prefix("data: ") {
+                        data = serde_json::from_str(value).ok();
+                    }
+                }
+                if let (Some(id), Some(event), Some(data)) = (id, event, data) {
+                    return SseEvent { id, event, data };
+                }
+                continue;
+            }
+
+            let chunk = tokio::time::timeout(Duration::from_secs(10), self.body.next())
+                .await
+                .expect("timed out waiting for sse event")
+                .expect("sse stream ended")
+                .expect("failed to read sse stream");
+            self.buffer
+                .push_str(std::str::from_utf8(&chunk).expect("sse chunk is not utf-8"));
+        }
+    }
+
+    async fn events_until(&mut self, event_type: &str) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        loop {
+            let event = self.next_event().await;
+            let done = event.event == event_type;
+            events.push(event);
+            if done {
+                return events;
+            }
+        }
+    }
+}
+
+fn crawl_batch() -> Value {
+    json!({
+        "users": [
+            { "vk_user_id": 1000, "first_name": "Ivan", "source_group_id": 10 }
+        ],
+        "posts": [
+            { "post_id": 1, "group_id": 10, "from_id": 1000, "created_date": 1_700_000_001 }
+        ],
+        "comments": [
+            { "group_id": 10, "post_id": 1, "comment_id": 5, "from_id": 1000, "created_date": 1_700_000_010, "comment_text": "hi" }
+        ],
+        "post_likes": [
+            { "vk_user_id": 1000, "group_id": 10, "post_id": 1 }
+        ]
+    })
+}
+
+#[sqlx::test]
+async fn event_stream_requires_auth_and_valid_last_event_id(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, _) = app.get_stream("/events/stream", None, &[]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .get_stream(
+            "/events/stream",
+            Some(&user.access_token),
+            &[("last-event-id", "abc")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn event_stream_pushes_new_finds_and_crawl_jobs(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+    seed_group(&pool, other.id, 10).await;
+
+    let (status, body) = app
+        .get_stream("/events/stream", Some(&user.access_token), &[])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut reader = SseReader::new(body);
+
+    let (status, _) = app
+        .post_json("/ingest/batch", crawl_batch(), Some(&other.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .post_json("/ingest/batch", crawl_batch(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let events = reader.events_until("crawl.finished").await;
+    let types: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+    assert_eq!(
+        types,
+        vec![
+            "crawl.started",
+            "vk_user.created",
+            "vk_post.created",
+            "vk_comment.created",
+            "vk_post_like.created",
+            "crawl.finished",
+        ]
+    );
+    assert_eq!(events[0].data["source"], "batch");
+    assert_eq!(events[0].data["job_id"], events[5].data["job_id"]);
+    assert_eq!(events[1].data["vk_user_id"], 1000);
+    assert_eq!(events[3].data["comment_id"], 5);
+    assert_eq!(events[5].data["status"], "succeeded");
+    assert_eq!(events[5].data["users"]["inserted"], 1);
+    assert!(events.windows(2).all(|w| w[0].id < w[1].id));
+
+    let (status, _) = app
+        .post_json("/ingest/batch", crawl_batch(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let events = reader.events_until("crawl.finished").await;
+    let types: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+    assert_eq!(types, vec!["crawl.started", "crawl.finished"]);
+    assert_eq!(events[1].data["users"]["updated"], 1);
+}
+
+#[sqlx::test]
+async fn event_stream_resumes_after_last_event_id(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, _) = app
+        .post_json("/ingest/batch", crawl_batch(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "error": { "error_code": 5, "error_msg": "User authorization failed" } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .get_stream(
+            "/events/stream",
+            Some(&user.access_token),
+            &[("last-event-id", "0")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut reader = SseReader::new(body);
+    let all = reader.events_until("crawl.finished").await;
+    assert_eq!(all.len(), 6);
+    let user_created = &all[1];
+    assert_eq!(user_created.event, "vk_user.created");
+
+    let last_event_id = user_created.id.to_string();
+    let (status, body) = app
+        .get_stream(
+            "/events/stream",
+            Some(&user.access_token),
+            &[("last-event-id", last_event_id.as_str())],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut reader = SseReader::new(body);
+    let resumed = reader.events_until("crawl.finished").await;
+    let types: Vec<&str> = resumed.iter().map(|e| e.event.as_str()).collect();
+    assert_eq!(
+        types,
+        vec![
+            "vk_post.created",
+            "vk_comment.created",
+            "vk_post_like.created",
+            "crawl.finished",
+        ]
+    );
+
+    let failed = reader.events_until("crawl.finished").await;
+    assert_eq!(failed[0].event, "crawl.started");
+    assert_eq!(failed[0].data["source"], "users.get");
+    assert_eq!(failed[1].data["status"], "failed");
+}
+
+#[sqlx::test]
+async fn event_stream_delivers_events_from_transactions_that_commit_late(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (status, body) = app
+        .get_stream("/events/stream", Some(&user.access_token), &[])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut reader = SseReader::new(body);
+
+    // The long transaction takes the lower id but commits last.
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query(
+        "INSERT INTO stream_events (user_id, event_type, data) VALUES ($1, 'crawl.started', $2)",
+    )
+    .bind(user.id)
+    .bind(json!({ "source": "late" }))
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    find_w::event_stream::repo::record_event(
+        &pool,
+        user.id,
+        StreamEventKind::CrawlFinished,
+        json!({ "source": "early" }),
+    )
+    .await
+    .unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    tx.commit().await.unwrap();
+
+    let events = reader.events_until("crawl.finished").await;
+    let sources: Vec<&str> = events
+        .iter()
+        .map(|e| e.data["source"].as_str().unwrap())
+        .collect();
+    assert_eq!(sources, vec!["late", "early"]);
+
+    let last_event_id = events[0].id.to_string();
+    let (_, body) = app
+        .get_stream(
+            "/events/stream",
+            Some(&user.access_token),
+            &[("last-event-id", last_event_id.as_str())],
+        )
+        .await;
+    let resumed = SseReader::new(body).next_event().await;
+    assert_eq!(resumed.data["source"], "early");
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, sample_vk_user, seed_group, seed_post};
+use find_w::{export::format::MAX_XLSX_ROWS, vk_users::repo as vk_users_repo};
+use serde_json::Value;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+#[sqlx::test]
+async fn export_vk_users_supports_csv_ndjson_and_xlsx(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let mut member = sample_vk_user(1000, "Ivan, \"the\" first", OffsetDateTime::now_utc());
+    member.source_group_id = Some(10);
+    vk_users_repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[
+            member,
+            sample_vk_user(1001, "Petr", OffsetDateTime::now_utc()),
+        ],
+    )
+    .await
+    .expect("failed to seed vk users");
+
+    let (status, csv) = app
+        .get_text("/export/vk-users", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("vk_user_id,"));
+    assert!(csv.contains("\"Ivan, \"\"the\"\" first\""));
+
+    let (status, ndjson) = app
+        .get_text(
+            "/export/vk-users?format=ndjson&group_id=10&source_kind=member",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let rows: Vec<Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid ndjson line"))
+        .collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["vk_user_id"], 1000);
+
+    let (status, xlsx) = app
+        .get_bytes("/export/vk-users?format=xlsx", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(xlsx.starts_with(b"PK"));
+
+    let (status, _) = app
+        .get_text("/export/vk-users?format=pdf", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn export_activity_filters_by_kind_and_is_scoped_to_user(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    for owner in [user.id, other.id] {
+        seed_group(&pool, owner, 10).await;
+        common::seed_vk_user(&pool, owner, 1000).await;
+        seed_post(&pool, owner, 10, 1000, 1, 1_700_000_001).await;
+    }
+    seed_post(&pool, user.id, 10, 1000, 2, 1_700_000_002).await;
+
+    let (status, ndjson) = app
+        .get_text(
+            "/export/activity?format=ndjson&kind=post",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let rows: Vec<Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid ndjson line"))
+        .collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row["kind"] == "post"));
+
+    let (status, _) = app
+        .get_text("/export/activity?kind=repost", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app.get_text("/export/activity", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn xlsx_export_is_capped(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    sqlx::query(
+        r#"
+        INSERT INTO vk_users (user_id, vk_user_id, first_seen_at, last_seen_at)
+        SELECT $1, n, now(), now()
+        FROM generate_series(1, $2::bigint) AS n
+        "#,
+    )
+    .bind(user.id)
+    .bind(MAX_XLSX_ROWS as i64 + 1)
+    .execute(&pool)
+    .await
+    .expect("failed to seed vk users");
+
+    let (status, body) = app
+        .get_json("/export/vk-users?format=xlsx", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "BAD_REQUEST");
+}
@@ -0,0 +1,298 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, seed_group};
+use find_w::{
+    vk_comments::repo as vk_comments_repo, vk_post_likes::repo as vk_post_likes_repo,
+    vk_posts::repo as vk_posts_repo,
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn ingest_vk_raw_loads_wall_comments_and_likes_in_order(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=wall.get&group_id=10",
+            json!({
+                "response": {
+                    "count": 2,
+                    "items": [
+                        { "id": 1, "owner_id": -10, "from_id": 1000, "date": 1_700_000_001, "post_type": "post", "text": "hello" },
+                        { "id": 2, "owner_id": -10, "from_id": -10, "date": 1_700_000_002, "post_type": "post", "text": "from group" }
+                    ],
+                    "profiles": [
+                        { "id": 1000, "first_name": "Ivan", "last_name": "Ivanov", "sex": 2, "city": { "id": 1, "title": "Moscow" }, "photo_100": "https://img.test/1000.jpg" }
+                    ]
+                }
+            }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["method"], "wall.get");
+    assert_eq!(body["users"]["inserted"], 1);
+    assert_eq!(body["posts"]["inserted"], 1);
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=wall.getComments&group_id=10&post_id=1",
+            json!({
+                "response": {
+                    "count": 1,
+                    "items": [
+                        {
+                            "id": 5, "from_id": 1001, "date": 1_700_000_010, "text": "first",
+                            "thread": { "count": 1, "items": [
+                                { "id": 6, "from_id": 1000, "date": 1_700_000_011, "text": "reply" }
+                            ] }
+                        }
+                    ],
+                    "profiles": [ { "id": 1001, "first_name": "Petr" } ]
+                }
+            }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["users"]["inserted"], 1);
+    assert_eq!(body["comments"]["inserted"], 2);
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=likes.getList&group_id=10&post_id=1",
+            json!({ "response": { "count": 2, "items": [1000, 1001] } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["post_likes"]["inserted"], 2);
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=likes.getList&group_id=10&post_id=1&comment_id=5",
+            json!({ "response": { "count": 1, "items": [
+                { "type": "profile", "id": 1002, "first_name": "Olga" },
+                { "type": "group", "id": 77, "name": "Some group" }
+            ] } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["users"]["inserted"], 1);
+    assert_eq!(body["comment_likes"]["inserted"], 1);
+
+    let posts = vk_posts_repo::list_vk_posts(&pool, user.id, 100, 0)
+        .await
+        .expect("failed to list posts");
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].post_text.as_deref(), Some("hello"));
+
+    let comments = vk_comments_repo::list_vk_comments(&pool, user.id, 100, 0)
+        .await
+        .expect("failed to list comments");
+    assert_eq!(comments.len(), 2);
+
+    let likes = vk_post_likes_repo::list_vk_post_likes(&pool, user.id, 100, 0)
+        .await
+        .expect("failed to list likes");
+    assert_eq!(likes.len(), 2);
+
+    let (status, body) = app
+        .get_json("/vk-users?limit=100", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let ivan = body
+        .as_array()
+        .expect("vk users response is not an array")
+        .iter()
+        .find(|vk_user| vk_user["vk_user_id"] == 1000)
+        .expect("ingested vk user not found");
+    assert_eq!(ivan["city"], "Moscow");
+    assert_eq!(ivan["photo"], "https://img.test/1000.jpg");
+}
+
+#[sqlx::test]
+async fn ingest_vk_raw_users_get_records_group_membership(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get&group_id=10",
+            json!({ "response": [
+                { "id": 1000, "first_name": "Ivan", "is_closed": false, "can_access_closed": true },
+                { "id": 1001, "first_name": "Petr", "bdate": "1.2.1990" }
+            ] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["users"]["inserted"], 2);
+
+    let (status, body) = app
+        .get_json(
+            "/vk-users?group_id=10&source_kind=member",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(2));
+}
+
+#[sqlx::test]
+async fn ingest_vk_raw_rejects_invalid_requests_and_orphans(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=groups.get",
+            json!({ "response": [] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=wall.get",
+            json!({ "response": { "items": [] } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "error": { "error_code": 5, "error_msg": "User authorization failed" } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/vk-raw?method=likes.getList&group_id=10&post_id=1",
+            json!({ "response": { "items": [1000] } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["post_likes"]["inserted"], 0);
+    assert_eq!(body["post_likes"]["rejected"], 1);
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "response": [] }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn ingest_batch_reports_counts_per_entity(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/batch",
+            json!({
+                "users": [
+                    { "vk_user_id": 1000, "first_name": "Ivan", "source_group_id": 10 },
+                    { "vk_user_id": 1001, "first_name": "Petr" }
+                ],
+                "posts": [
+                    { "post_id": 1, "group_id": 10, "from_id": 1000, "created_date": 1_700_000_001 }
+                ],
+                "comments": [
+                    { "group_id": 10, "post_id": 1, "comment_id": 5, "from_id": 1001, "created_date": 1_700_000_010 },
+                    { "group_id": 10, "post_id": 2, "comment_id": 6, "from_id": 1001, "created_date": 1_700_000_011 }
+                ],
+                "post_likes": [ { "vk_user_id": 1001, "group_id": 10, "post_id": 1 } ],
+                "comment_likes": [ { "vk_user_id": 1000, "group_id": 10, "post_id": 1, "comment_id": 5 } ]
+            }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["users"]["inserted"], 2);
+    assert_eq!(body["posts"]["inserted"], 1);
+    assert_eq!(body["comments"]["inserted"], 1);
+    assert_eq!(body["comments"]["rejected"], 1);
+    assert_eq!(body["post_likes"]["inserted"], 1);
+    assert_eq!(body["comment_likes"]["inserted"], 1);
+
+    let (status, _) = app.post_json("/ingest/batch", json!({}), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn ingest_batch_keeps_the_last_copy_of_duplicate_rows(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, body) = app
+        .post_json(
+            "/ingest/batch",
+            json!({
+                "users": [
+                    { "vk_user_id": 1000, "first_name": "Ivan", "source_group_id": 10 },
+                    { "vk_user_id": 1000, "first_name": "Ivan Jr", "source_group_id": 10 }
+                ],
+                "posts": [
+                    { "post_id": 1, "group_id": 10, "from_id": 1000, "created_date": 1_700_000_001, "post_text": "draft" },
+                    { "post_id": 1, "group_id": 10, "from_id": 1000, "created_date": 1_700_000_001, "post_text": "final" }
+                ],
+                "comments": [
+                    { "group_id": 10, "post_id": 1, "comment_id": 5, "from_id": 1000, "created_date": 1_700_000_010, "comment_text": "first" },
+                    { "group_id": 10, "post_id": 1, "comment_id": 5, "from_id": 1000, "created_date": 1_700_000_010, "comment_text": "edited" }
+                ],
+                "post_likes": [
+                    { "vk_user_id": 1000, "group_id": 10, "post_id": 1 },
+                    { "vk_user_id": 1000, "group_id": 10, "post_id": 1 }
+                ],
+                "comment_likes": [
+                    { "vk_user_id": 1000, "group_id": 10, "post_id": 1, "comment_id": 5 },
+                    { "vk_user_id": 1000, "group_id": 10, "post_id": 1, "comment_id": 5 }
+                ]
+            }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    for entity in ["users", "posts", "comments", "post_likes", "comment_likes"] {
+        assert_eq!(body[entity]["inserted"], 1, "{entity}");
+        assert_eq!(body[entity]["updated"], 0, "{entity}");
+        assert_eq!(body[entity]["rejected"], 0, "{entity}");
+    }
+
+    let stored: (Option<String>, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT v.first_name, p.post_text, c.comment_text \
+         FROM vk_users v, vk_posts p, vk_comments c \
+         WHERE v.user_id = $1 AND p.user_id = $1 AND c.user_id = $1",
+    )
+    .bind(user.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        stored,
+        (
+            Some("Ivan Jr".to_string()),
+            Some("final".to_string()),
+            Some("edited".to_string())
+        )
+    );
+}
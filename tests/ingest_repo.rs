@@ -0,0 +1,136 @@
+mod common;
+
+use crate::common::{create_user, sample_vk_user, seed_group};
+use find_w::ingest::repo::{self, CrawlBatch};
+use find_w::vk_comment_likes::repo::NewVkCommentLike;
+use find_w::vk_comments::repo::NewVkComment;
+use find_w::vk_post_likes::repo::NewVkPostLike;
+use find_w::vk_posts::repo::NewVkPost;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+fn post(post_id: i64, group_id: i64, from_id: i64) -> NewVkPost {
+    NewVkPost {
+        post_id,
+        group_id,
+        from_id,
+        created_date: 1_700_000_000 + post_id,
+        post_type: Some("post".to_string()),
+        post_text: Some(format!("post-{post_id}")),
+    }
+}
+
+fn comment(post_id: i64, comment_id: i64, from_id: i64) -> NewVkComment {
+    NewVkComment {
+        group_id: 10,
+        post_id,
+        comment_id,
+        from_id,
+        created_date: 1_700_000_100 + comment_id,
+        comment_text: Some(format!("comment-{comment_id}")),
+    }
+}
+
+#[sqlx::test]
+async fn ingest_batch_writes_parents_first_and_rejects_orphans(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    seed_group(&pool, user_id, 10).await;
+    let now = OffsetDateTime::now_utc();
+
+    let mut member = sample_vk_user(1000, "Ivan", now);
+    member.source_group_id = Some(10);
+    let mut stranger = sample_vk_user(1002, "Olga", now);
+    stranger.source_group_id = Some(99);
+
+    let batch = CrawlBatch {
+        users: vec![member, sample_vk_user(1001, "Petr", now), stranger],
+        posts: vec![post(1, 10, 1000), post(2, 10, 5555), post(3, 99, 1000)],
+        comments: vec![comment(1, 11, 1001), comment(2, 12, 1001)],
+        post_likes: vec![
+            NewVkPostLike {
+                vk_user_id: 1001,
+                group_id: 10,
+                post_id: 1,
+                seen_at: now,
+            },
+            NewVkPostLike {
+                vk_user_id: 1002,
+                group_id: 10,
+                post_id: 1,
+                seen_at: now,
+            },
+        ],
+        comment_likes: vec![
+            NewVkCommentLike {
+                vk_user_id: 1000,
+                group_id: 10,
+                post_id: 1,
+                comment_id: 11,
+                seen_at: now,
+            },
+            NewVkCommentLike {
+                vk_user_id: 1000,
+                group_id: 10,
+                post_id: 1,
+                comment_id: 404,
+                seen_at: now,
+            },
+        ],
+    };
+
+    let res = repo::ingest_batch(&pool, user_id, &batch)
+        .await
+        .expect("failed to ingest batch");
+
+    assert_eq!((res.users.inserted, res.users.rejected), (2, 1));
+    assert_eq!((res.posts.inserted, res.posts.rejected), (1, 2));
+    assert_eq!((res.comments.inserted, res.comments.rejected), (1, 1));
+    assert_eq!((res.post_likes.inserted, res.post_likes.rejected), (1, 1));
+    assert_eq!(
+        (res.comment_likes.inserted, res.comment_likes.rejected),
+        (1, 1)
+    );
+
+    let res = repo::ingest_batch(&pool, user_id, &batch)
+        .await
+        .expect("failed to ingest batch again");
+    assert_eq!((res.users.inserted, res.users.updated), (0, 2));
+    assert_eq!((res.posts.inserted, res.posts.updated), (0, 1));
+    assert_eq!((res.comments.inserted, res.comments.updated), (0, 1));
+    assert_eq!((res.post_likes.inserted, res.post_likes.updated), (0, 1));
+    assert_eq!(
+        (res.comment_likes.inserted, res.comment_likes.updated),
+        (0, 1)
+    );
+}
+
+#[sqlx::test]
+async fn ingest_batch_rolls_back_on_failure(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    seed_group(&pool, user_id, 10).await;
+    let now = OffsetDateTime::now_utc();
+
+    // Postgres rejects NUL bytes in text, so the posts insert fails after the
+    // users were already written.
+    let batch = CrawlBatch {
+        users: vec![sample_vk_user(1000, "Ivan", now)],
+        posts: vec![NewVkPost {
+            post_text: Some("broken\0text".to_string()),
+            ..post(1, 10, 1000)
+        }],
+        ..CrawlBatch::default()
+    };
+
+    repo::ingest_batch(&pool, user_id, &batch)
+        .await
+        .expect_err("a failing insert must fail the batch");
+
+    let vk_users = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM vk_users WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to count vk users");
+    assert_eq!(vk_users, 0);
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use axum::http::{HeaderMap, StatusCode, header};
+use common::TestApp;
+use find_w::{
+    app::trusted_proxies::TrustedProxies,
+    auth::login_throttle::{LoginThrottlePolicy, ThrottleRule},
+};
+use futures_util::future::join_all;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::Duration;
+
+const PASSWORD: &str = "strong-password-123";
+
+fn strict_app(pool: PgPool) -> TestApp {
+    TestApp::with_state(pool, |state| {
+        state.login_throttle = LoginThrottlePolicy {
+            email: ThrottleRule {
+                free_attempts: 2,
+                lockout_after: 4,
+                base_delay: Duration::minutes(1),
+                lockout: Duration::minutes(10),
+            },
+            ip: ThrottleRule {
+                free_attempts: 5,
+                lockout_after: 6,
+                base_delay: Duration::minutes(1),
+                lockout: Duration::minutes(10),
+            },
+            ..LoginThrottlePolicy::default()
+        };
+    })
+}
+
+async fn login(
+    app: &TestApp,
+    email: &str,
+    password: &str,
+    ip: &str,
+) -> (StatusCode, HeaderMap, Value) {
+    app.post_json_with_response_headers(
+        "/auth/login",
+        json!({ "email": email, "password": password }),
+        &[("x-forwarded-for", ip)],
+    )
+    .await
+}
+
+fn retry_after(headers: &HeaderMap) -> u64 {
+    headers
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .expect("429 response misses Retry-After")
+}
+
+async fn expire_blocks(pool: &PgPool) {
+    sqlx::query!("UPDATE login_throttles SET blocked_until = now() - interval '1 second'")
+        .execute(pool)
+        .await
+        .expect("failed to expire login blocks");
+}
+
+#[sqlx::test]
+async fn failed_logins_are_delayed_then_locked_out(pool: PgPool) {
+    let app = strict_app(pool.clone());
+    let user = app.register_and_login().await;
+    let email = user.email.to_uppercase();
+
+    for _ in 0..3 {
+        let (status, _, _) = login(&app, &email, "wrong-password", "203.0.113.1").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, headers, body) = login(&app, &user.email, PASSWORD, "203.0.113.2").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+    let delay = retry_after(&headers);
+    assert!((1..=61).contains(&delay), "unexpected delay {delay}");
+
+    expire_blocks(&pool).await;
+    let (status, _, _) = login(&app, &user.email, "wrong-password", "203.0.113.1").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, headers, _) = login(&app, &user.email, PASSWORD, "203.0.113.3").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after(&headers) > 60);
+
+    expire_blocks(&pool).await;
+    let (status, _, body) = login(&app, &user.email, PASSWORD, "203.0.113.3").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["access_token"].is_string());
+
+    let left = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM login_throttles WHERE scope = 'email'"#
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to count throttles");
+    assert_eq!(left, 0);
+}
+
+#[sqlx::test]
+async fn one_ip_guessing_many_accounts_is_blocked(pool: PgPool) {
+    let app = strict_app(pool);
+    let user = app.register_and_login().await;
+
+    for i in 0..6 {
+        let (status, _, _) = login(
+            &app,
+            &format!("nobody-{i}@example.test"),
+            "wrong-password",
+            "198.51.100.9",
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, headers, _) = login(&app, &user.email, PASSWORD, "198.51.100.9").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after(&headers) > 60);
+
+    let (status, _, _) = login(&app, &user.email, PASSWORD, "198.51.100.10").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn parallel_guesses_cannot_exceed_the_lockout(pool: PgPool) {
+    let app = strict_app(pool);
+    let user = app.register_and_login().await;
+
+    let attempts = (0..12).map(|i| {
+        let ip = format!("203.0.113.{}", 100 + i);
+        let app = &app;
+        let email = &user.email;
+        async move { login(app, email, "wrong-password", &ip).await.0 }
+    });
+    let statuses = join_all(attempts).await;
+
+    let guessed = statuses
+        .iter()
+        .filter(|s| **s == StatusCode::UNAUTHORIZED)
+        .count();
+    assert!(guessed <= 4, "{guessed} guesses got through");
+    assert!(statuses.contains(&StatusCode::TOO_MANY_REQUESTS));
+}
+
+#[sqlx::test]
+async fn rotating_forwarded_for_from_an_untrusted_peer_does_not_reset_the_ip_counter(pool: PgPool) {
+    let app = TestApp::with_state(pool, |state| {
+        state.trusted_proxies = TrustedProxies::default();
+        state.login_throttle.ip = ThrottleRule {
+            free_attempts: 1,
+            lockout_after: 3,
+            base_delay: Duration::minutes(1),
+            lockout: Duration::minutes(10),
+        };
+    });
+
+    for i in 0..3 {
+        let (status, _, _) = login(
+            &app,
+            &format!("nobody-{i}@example.test"),
+            "wrong-password",
+            &format!("192.0.2.{i}"),
+        )
+        .await;
+        assert!(
+            status == StatusCode::UNAUTHORIZED || status == StatusCode::TOO_MANY_REQUESTS,
+            "unexpected status {status}"
+        );
+    }
+
+    let (status, _, _) = login(
+        &app,
+        "someone-else@example.test",
+        "wrong-password",
+        "192.0.2.200",
+    )
+    .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[sqlx::test]
+async fn guesses_at_a_locked_account_do_not_count_against_the_ip(pool: PgPool) {
+    let app = strict_app(pool.clone());
+    let locked = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    for i in 0..3 {
+        let ip = format!("203.0.113.{}", 50 + i);
+        let (status, _, _) = login(&app, &locked.email, "wrong-password", &ip).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    for _ in 0..10 {
+        let (status, _, _) = login(&app, &locked.email, "wrong-password", "198.51.100.20").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let ip_failures = sqlx::query_scalar!(
+        "SELECT failures FROM login_throttles WHERE scope = 'ip' AND key = '198.51.100.20'"
+    )
+    .fetch_optional(&pool)
+    .await
+    .expect("failed to read ip throttle");
+    assert!(ip_failures.unwrap_or(0) == 0, "ip counted {ip_failures:?}");
+
+    let (status, _, _) = login(&app, &other.email, PASSWORD, "198.51.100.20").await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,276 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, seed_group, seed_vk_user};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+fn note_titles(body: &Value) -> Vec<&str> {
+    body.as_array()
+        .expect("response is not an array")
+        .iter()
+        .map(|note| note["title"].as_str().expect("note misses title"))
+        .collect()
+}
+
+#[sqlx::test]
+async fn notes_can_reference_vk_users_and_groups(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    seed_vk_user(&pool, user.id, 1000).await;
+    seed_vk_user(&pool, user.id, 1001).await;
+    seed_group(&pool, user.id, 10).await;
+
+    for (title, vk_user_id, group_id) in [
+        ("about ivan", Some(1000), None),
+        ("ivan in group", Some(1000), Some(10)),
+        ("group only", None, Some(10)),
+        ("plain", None, None),
+    ] {
+        let (status, note) = app
+            .post_json(
+                "/notes",
+                json!({ "title": title, "body": "text", "vk_user_id": vk_user_id, "group_id": group_id }),
+                Some(&user.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(note["vk_user_id"], json!(vk_user_id));
+        assert_eq!(note["group_id"], json!(group_id));
+    }
+
+    for body in [
+        json!({ "title": "t", "body": "b", "vk_user_id": 9999 }),
+        json!({ "title": "t", "body": "b", "group_id": 99 }),
+        json!({ "title": "t", "body": "b", "vk_user_id": 0 }),
+    ] {
+        let (status, _) = app
+            .post_json("/notes", body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, _) = app
+        .post_json(
+            "/notes",
+            json!({ "title": "t", "body": "b", "vk_user_id": 1000 }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, notes) = app
+        .get_json("/notes?vk_user_id=1000", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(note_titles(&notes), vec!["ivan in group", "about ivan"]);
+
+    let (_, notes) = app
+        .get_json("/notes?group_id=10", Some(&user.access_token))
+        .await;
+    assert_eq!(note_titles(&notes), vec!["group only", "ivan in group"]);
+
+    let (_, notes) = app
+        .get_json(
+            "/notes?vk_user_id=1000&group_id=10",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(note_titles(&notes), vec!["ivan in group"]);
+
+    let (_, notes) = app.get_json("/notes", Some(&user.access_token)).await;
+    assert_eq!(notes.as_array().map(Vec::len), Some(4));
+
+    let (status, notes) = app
+        .get_json("/vk-users/1000/notes", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(note_titles(&notes), vec!["ivan in group", "about ivan"]);
+
+    let (status, _) = app
+        .get_json("/vk-users/1000/notes", Some(&other.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, vk_users) = app.get_json("/vk-users", Some(&user.access_token)).await;
+    let count = |vk_user_id: i64| {
+        vk_users
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|vk_user| vk_user["vk_user_id"] == vk_user_id)
+            .map(|vk_user| vk_user["notes_count"].clone())
+    };
+    assert_eq!(count(1000), Some(json!(2)));
+    assert_eq!(count(1001), Some(json!(0)));
+}
+
+#[sqlx::test]
+async fn notes_outlive_deleted_vk_users_and_groups(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_vk_user(&pool, user.id, 1000).await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, note) = app
+        .post_json(
+            "/notes",
+            json!({ "title": "t", "body": "b", "vk_user_id": 1000, "group_id": 10 }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = note["id"].as_str().expect("note misses id");
+
+    find_w::vk_users::repo::delete_vk_users(&pool, user.id, &[1000])
+        .await
+        .expect("failed to delete vk user");
+    let status = app.delete("/groups/10", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, note) = app
+        .get_json(&format!("/notes/{id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(note["vk_user_id"], Value::Null);
+    assert_eq!(note["group_id"], Value::Null);
+}
+
+#[sqlx::test]
+async fn notes_can_be_edited_and_restored_from_versions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    let (_, note) = app
+        .post_json(
+            "/notes",
+            json!({ "title": "Ivna", "body": "met at the meetup" }),
+            Some(&user.access_token),
+        )
+        .await;
+    let id = note["id"].as_str().expect("note misses id").to_string();
+    assert_eq!(note["updated_at"], note["created_at"]);
+
+    for body in [json!({}), json!({ "title": "  " }), json!({ "body": "" })] {
+        let (status, _) = app
+            .patch_json(&format!("/notes/{id}"), body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, _) = app
+        .patch_json(
+            &format!("/notes/{id}"),
+            json!({ "title": "stolen" }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, edited) = app
+        .patch_json(
+            &format!("/notes/{id}"),
+            json!({ "title": "Ivan" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["title"], "Ivan");
+    assert_eq!(edited["body"], "met at the meetup");
+    assert_ne!(edited["updated_at"], note["updated_at"]);
+
+    let (_, unchanged) = app
+        .patch_json(
+            &format!("/notes/{id}"),
+            json!({ "title": "Ivan" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(unchanged["updated_at"], edited["updated_at"]);
+
+    let (status, versions) = app
+        .get_json(&format!("/notes/{id}/versions"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(note_titles(&versions), vec!["Ivna"]);
+    let version_id = versions[0]["id"].as_str().expect("version misses id");
+
+    let (status, _) = app
+        .get_json(&format!("/notes/{id}/versions"), Some(&other.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .post_json(
+            &format!("/notes/{id}/versions/{version_id}/restore"),
+            json!({}),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, restored) = app
+        .post_json(
+            &format!("/notes/{id}/versions/{version_id}/restore"),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["title"], "Ivna");
+
+    let (_, versions) = app
+        .get_json(&format!("/notes/{id}/versions"), Some(&user.access_token))
+        .await;
+    let mut titles = note_titles(&versions);
+    titles.sort();
+    assert_eq!(titles, vec!["Ivan", "Ivna"]);
+}
+
+#[sqlx::test]
+async fn notes_can_be_searched_by_title_and_body(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    for (title, body) in [
+        ("Ivan", "likes hiking and photography"),
+        ("Petr", "photography club in Moscow"),
+        ("Anna", "plays chess"),
+    ] {
+        let (status, _) = app
+            .post_json(
+                "/notes",
+                json!({ "title": title, "body": body }),
+                Some(&user.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    app.post_json(
+        "/notes",
+        json!({ "title": "Oleg", "body": "photography" }),
+        Some(&other.access_token),
+    )
+    .await;
+
+    let (status, notes) = app
+        .get_json("/notes?q=photography", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut titles = note_titles(&notes);
+    titles.sort();
+    assert_eq!(titles, vec!["Ivan", "Petr"]);
+
+    let (_, notes) = app
+        .get_json("/notes?q=photography%20-moscow", Some(&user.access_token))
+        .await;
+    assert_eq!(note_titles(&notes), vec!["Ivan"]);
+
+    let (_, notes) = app
+        .get_json("/notes?q=anna", Some(&user.access_token))
+        .await;
+    assert_eq!(note_titles(&notes), vec!["Anna"]);
+
+    let (_, notes) = app.get_json("/notes?q=%20", Some(&user.access_token)).await;
+    assert_eq!(notes.as_array().map(Vec::len), Some(3));
+}
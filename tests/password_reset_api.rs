@@ -0,0 +1,161 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use common::{TestApp, discard_verification_emails, smtp::start_smtp_sink};
+use serde_json::json;
+use sqlx::PgPool;
+
+/// Reset emails are queued by a background task after the 202 is sent.
+async fn wait_for_reset_emails(pool: &PgPool, expected: i64) {
+    for _ in 0..100 {
+        let queued = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM email_outbox WHERE kind = 'password_reset'"#
+        )
+        .fetch_one(pool)
+        .await
+        .expect("failed to count reset emails");
+        if queued >= expected {
+            assert_eq!(queued, expected);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("reset email was not queued");
+}
+
+#[sqlx::test]
+async fn forgot_password_does_not_reveal_accounts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+
+    for email in [user.email.as_str(), "nobody@example.test"] {
+        let (status, body) = app
+            .post_json("/auth/password/forgot", json!({ "email": email }), None)
+            .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body, serde_json::Value::Null);
+    }
+
+    wait_for_reset_emails(&pool, 1).await;
+}
+
+#[sqlx::test]
+async fn reset_token_changes_password_once_and_revokes_sessions(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+
+    let (status, _) = app
+        .post_json(
+            "/auth/password/forgot",
+            json!({ "email": user.email }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    wait_for_reset_emails(&pool, 1).await;
+    find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    let token = sink.messages.lock().unwrap()[0]
+        .token_after("Use this token")
+        .expect("email misses reset token");
+
+    let stored = sqlx::query_scalar!("SELECT token_hash FROM password_reset_tokens")
+        .fetch_one(&pool)
+        .await
+        .expect("failed to fetch reset token");
+    assert_ne!(stored, token);
+
+    for body in [
+        json!({ "token": token, "new_password": "short" }),
+        json!({ "token": "bogus", "new_password": "brand-new-password" }),
+    ] {
+        let (status, _) = app.post_json("/auth/password/reset", body, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let (status, _) = app
+        .post_json(
+            "/auth/password/reset",
+            json!({ "token": token, "new_password": "brand-new-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/password/reset",
+            json!({ "token": token, "new_password": "another-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "brand-new-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn expired_reset_token_is_rejected(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    discard_verification_emails(&pool).await;
+    let (mailer, sink) = start_smtp_sink().await;
+
+    app.post_json(
+        "/auth/password/forgot",
+        json!({ "email": user.email }),
+        None,
+    )
+    .await;
+    wait_for_reset_emails(&pool, 1).await;
+    find_w::notifications::sender::send_due(&pool, &mailer)
+        .await
+        .expect("failed to send due emails");
+    let token = sink.messages.lock().unwrap()[0]
+        .token_after("Use this token")
+        .expect("email misses reset token");
+
+    sqlx::query!("UPDATE password_reset_tokens SET expires_at = now()")
+        .execute(&pool)
+        .await
+        .expect("failed to expire reset token");
+
+    let (status, _) = app
+        .post_json(
+            "/auth/password/reset",
+            json!({ "token": token, "new_password": "brand-new-password" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,301 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, seed_vk_user};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+fn vk_user_ids(body: &Value) -> Vec<i64> {
+    let mut ids: Vec<i64> = body
+        .as_array()
+        .expect("response is not an array")
+        .iter()
+        .map(|vk_user| vk_user["vk_user_id"].as_i64().expect("missing vk_user_id"))
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn stage_id(stages: &Value, name: &str) -> String {
+    stages
+        .as_array()
+        .expect("response is not an array")
+        .iter()
+        .find(|stage| stage["name"] == name)
+        .and_then(|stage| stage["id"].as_str())
+        .expect("stage not found")
+        .to_string()
+}
+
+#[sqlx::test]
+async fn new_accounts_get_default_stages_that_can_be_configured(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    let (status, stages) = app
+        .get_json("/pipeline/stages", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<&str> = stages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|stage| stage["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["new", "reviewed", "contacted", "replied", "closed"]
+    );
+
+    let (status, stage) = app
+        .post_json(
+            "/pipeline/stages",
+            json!({ "name": " meeting " }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(stage["name"], "meeting");
+    assert_eq!(stage["position"], 6);
+    let meeting = stage["id"].as_str().unwrap().to_string();
+
+    for body in [
+        json!({ "name": "" }),
+        json!({ "name": "x", "position": -1 }),
+    ] {
+        let (status, _) = app
+            .post_json("/pipeline/stages", body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, _) = app
+        .post_json(
+            "/pipeline/stages",
+            json!({ "name": "closed" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, stage) = app
+        .patch_json(
+            &format!("/pipeline/stages/{meeting}"),
+            json!({ "position": 0 }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stage["position"], 0);
+    let (status, _) = app
+        .patch_json(
+            &format!("/pipeline/stages/{meeting}"),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .patch_json(
+            &format!("/pipeline/stages/{meeting}"),
+            json!({ "name": "stolen" }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, stages) = app
+        .get_json("/pipeline/stages", Some(&user.access_token))
+        .await;
+    assert_eq!(stages[0]["name"], "meeting");
+
+    let status = app
+        .delete(
+            &format!("/pipeline/stages/{meeting}"),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let status = app
+        .delete(
+            &format!("/pipeline/stages/{meeting}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, stages) = app
+        .get_json("/pipeline/stages", Some(&user.access_token))
+        .await;
+    assert_eq!(stages.as_array().map(Vec::len), Some(5));
+}
+
+#[sqlx::test]
+async fn vk_users_move_through_stages_with_history_filters_and_counts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    for vk_user_id in [1000, 1001, 1002] {
+        seed_vk_user(&pool, user.id, vk_user_id).await;
+    }
+    let (_, stages) = app
+        .get_json("/pipeline/stages", Some(&user.access_token))
+        .await;
+    let reviewed = stage_id(&stages, "reviewed");
+    let contacted = stage_id(&stages, "contacted");
+    let (_, other_stages) = app
+        .get_json("/pipeline/stages", Some(&other.access_token))
+        .await;
+    let foreign = stage_id(&other_stages, "reviewed");
+
+    let (status, vk_user) = app
+        .put_json(
+            "/vk-users/1000/stage",
+            json!({ "stage_id": reviewed }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(vk_user["stage"]["name"], "reviewed");
+
+    let (status, vk_user) = app
+        .put_json(
+            "/vk-users/1000/stage",
+            json!({ "stage_id": contacted }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(vk_user["stage"]["id"], contacted.as_str());
+
+    let (status, _) = app
+        .put_json(
+            "/vk-users/1000/stage",
+            json!({ "stage_id": contacted }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .put_json(
+            "/vk-users/1001/stage",
+            json!({ "stage_id": reviewed }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    for (path, stage, token) in [
+        ("/vk-users/1002/stage", &foreign, &user.access_token),
+        ("/vk-users/9999/stage", &reviewed, &user.access_token),
+        ("/vk-users/1002/stage", &reviewed, &other.access_token),
+    ] {
+        let (status, _) = app
+            .put_json(path, json!({ "stage_id": stage }), Some(token))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let (status, history) = app
+        .get_json("/vk-users/1000/stage-history", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history.as_array().map(Vec::len), Some(2));
+    assert_eq!(history[0]["from_stage"], "reviewed");
+    assert_eq!(history[0]["to_stage"], "contacted");
+    assert_eq!(history[1]["from_stage"], Value::Null);
+    assert_eq!(history[1]["to_stage"], "reviewed");
+
+    let (_, body) = app
+        .get_json(
+            &format!("/vk-users?stage={reviewed}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(vk_user_ids(&body), vec![1001]);
+    let (_, body) = app
+        .get_json("/vk-users?stage=none", Some(&user.access_token))
+        .await;
+    assert_eq!(vk_user_ids(&body), vec![1002]);
+    let (status, _) = app
+        .get_json("/vk-users?stage=later", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, counts) = app
+        .get_json("/pipeline/counts", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(counts["unstaged"], 1);
+    let count = |name: &str| {
+        counts["stages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|stage| stage["name"] == name)
+            .map(|stage| stage["count"].clone())
+    };
+    assert_eq!(count("reviewed"), Some(json!(1)));
+    assert_eq!(count("contacted"), Some(json!(1)));
+    assert_eq!(count("new"), Some(json!(0)));
+
+    let (status, vk_user) = app
+        .put_json(
+            "/vk-users/1000/stage",
+            json!({ "stage_id": null }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(vk_user["stage"], Value::Null);
+
+    let status = app
+        .delete(
+            &format!("/pipeline/stages/{reviewed}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, counts) = app
+        .get_json("/pipeline/counts", Some(&user.access_token))
+        .await;
+    assert_eq!(counts["unstaged"], 3);
+
+    let (_, history) = app
+        .get_json("/vk-users/1000/stage-history", Some(&user.access_token))
+        .await;
+    assert_eq!(history.as_array().map(Vec::len), Some(3));
+    assert_eq!(history[2]["to_stage"], "reviewed");
+    assert_eq!(history[2]["to_stage_id"], Value::Null);
+
+    let (_, history) = app
+        .get_json("/vk-users/1001/stage-history", Some(&user.access_token))
+        .await;
+    assert_eq!(history.as_array().map(Vec::len), Some(2));
+    assert_eq!(history[0]["from_stage"], "reviewed");
+    assert_eq!(history[0]["to_stage"], Value::Null);
+}
+
+#[sqlx::test]
+async fn vk_users_cannot_be_placed_in_another_accounts_stage(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    seed_vk_user(&pool, user.id, 1000).await;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO vk_user_stages (user_id, vk_user_id, stage_id)
+        SELECT $1, 1000, id
+        FROM pipeline_stages
+        WHERE user_id = $2
+        LIMIT 1
+        "#,
+    )
+    .bind(user.id)
+    .bind(other.id)
+    .execute(&pool)
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn refresh(app: &TestApp, refresh_token: &str) -> (StatusCode, serde_json::Value) {
+    app.post_json_with_headers(
+        "/auth/refresh",
+        json!({ "refresh_token": refresh_token }),
+        None,
+        &[
+            ("user-agent", "Thief/1.0"),
+            ("x-forwarded-for", "198.51.100.66"),
+        ],
+    )
+    .await
+}
+
+async fn security_alerts(pool: &PgPool) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM email_outbox WHERE kind = 'security_alert'"#
+    )
+    .fetch_one(pool)
+    .await
+    .expect("failed to count security alerts")
+}
+
+/// Backdates every rotation past the retry grace window.
+async fn leave_grace_window(pool: &PgPool) {
+    sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = now() - interval '1 minute' \
+         WHERE replaced_by IS NOT NULL",
+    )
+    .execute(pool)
+    .await
+    .expect("failed to backdate rotations");
+}
+
+#[sqlx::test]
+async fn reusing_a_rotated_token_revokes_its_family(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let (status, other_login) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, first) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, second) = refresh(&app, first["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = refresh(&app, second["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = refresh(&app, other_login["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let event = sqlx::query!(
+        r#"
+        SELECT kind, user_agent, ip_address, details
+        FROM security_events
+        WHERE user_id = $1
+        "#,
+        user.id
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to fetch security event");
+    assert_eq!(event.kind, "refresh_token_reuse");
+    assert_eq!(event.user_agent.as_deref(), Some("Thief/1.0"));
+    assert_eq!(event.ip_address.as_deref(), Some("198.51.100.66"));
+    assert_eq!(event.details["revoked_sessions"], 1);
+    assert_eq!(security_alerts(&pool).await, 1);
+
+    // Replaying again finds nothing left to revoke and does not email twice.
+    let (status, _) = refresh(&app, first["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(security_alerts(&pool).await, 1);
+}
+
+#[sqlx::test]
+async fn security_alerts_respect_user_settings(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (status, body) = app.get_json("/settings", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email_notify_security"], true);
+    let (status, body) = app
+        .patch_json(
+            "/settings",
+            json!({ "email_notify_security": false }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email_notify_security"], false);
+
+    let (status, rotated) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::OK);
+    leave_grace_window(&pool).await;
+    let (status, _) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = refresh(&app, rotated["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    assert_eq!(security_alerts(&pool).await, 0);
+}
+
+#[sqlx::test]
+async fn retrying_a_refresh_right_after_rotation_is_not_treated_as_reuse(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (status, rotated) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The client lost the response and retries with the old token.
+    let (status, _) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(security_alerts(&pool).await, 0);
+    let (status, _) = app.get_json("/me", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Once the grace window has passed the replay counts as theft again.
+    leave_grace_window(&pool).await;
+    let (status, _) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(security_alerts(&pool).await, 1);
+    let (status, _) = refresh(&app, rotated["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn grace_window_ends_once_the_new_token_is_used(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (_, rotated) = refresh(&app, &user.refresh_token).await;
+    let (status, _) = refresh(&app, rotated["refresh_token"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = refresh(&app, &user.refresh_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(security_alerts(&pool).await, 1);
+}
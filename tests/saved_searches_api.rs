@@ -0,0 +1,222 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, sample_vk_user, seed_group};
+use find_w::vk_users::repo::{self as vk_users_repo, NewVkUser};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+fn candidate(vk_user_id: i64, sex: i16, bdate: &str, is_closed: bool, group_id: i64) -> NewVkUser {
+    let mut vk_user = sample_vk_user(vk_user_id, "Anna", OffsetDateTime::now_utc());
+    vk_user.sex = Some(sex);
+    vk_user.bdate = Some(bdate.to_string());
+    vk_user.is_closed = Some(is_closed);
+    vk_user.source_group_id = Some(group_id);
+    vk_user
+}
+
+async fn seed_candidates(pool: &PgPool, user_id: Uuid) {
+    let year = OffsetDateTime::now_utc().year();
+    let age_25 = format!("1.1.{}", year - 25);
+    let age_40 = format!("1.1.{}", year - 40);
+
+    for group_id in [10, 20] {
+        vk_users_repo::upsert_vk_users(
+            pool,
+            user_id,
+            &[candidate(1000, 1, &age_25, false, group_id)],
+        )
+        .await
+        .expect("failed to seed matching vk user");
+    }
+
+    vk_users_repo::upsert_vk_users(
+        pool,
+        user_id,
+        &[
+            candidate(1001, 2, &age_25, false, 10),
+            candidate(1002, 1, &age_25, true, 10),
+            candidate(1003, 1, &age_40, false, 10),
+            candidate(1004, 1, "1.1", false, 10),
+            candidate(1005, 1, &age_25, false, 10),
+        ],
+    )
+    .await
+    .expect("failed to seed other vk users");
+}
+
+fn search_body() -> Value {
+    json!({
+        "name": "Moscow, 20-28",
+        "sex": 1,
+        "city": "moscow",
+        "age_min": 20,
+        "age_max": 28,
+        "open_profile": true,
+        "min_groups": 2
+    })
+}
+
+#[sqlx::test]
+async fn saved_search_returns_matching_vk_users(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+    seed_group(&pool, user.id, 20).await;
+    seed_candidates(&pool, user.id).await;
+
+    let (status, created) = app
+        .post_json("/saved-searches", search_body(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["name"], "Moscow, 20-28");
+    let id = created["id"].as_str().expect("saved search misses id");
+
+    let (status, list) = app
+        .get_json("/saved-searches", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().map(Vec::len), Some(1));
+
+    let (status, results) = app
+        .get_json(
+            &format!("/saved-searches/{id}/results"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<i64> = results
+        .as_array()
+        .expect("results are not an array")
+        .iter()
+        .filter_map(|vk_user| vk_user["vk_user_id"].as_i64())
+        .collect();
+    assert_eq!(ids, vec![1000]);
+
+    let (status, _) = app
+        .get_json(
+            &format!("/saved-searches/{id}/results"),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let status = app
+        .delete(&format!("/saved-searches/{id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let status = app
+        .delete(&format!("/saved-searches/{id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn saved_search_rejects_invalid_criteria(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    for body in [
+        json!({ "name": "  " }),
+        json!({ "name": "bad sex", "sex": 3 }),
+        json!({ "name": "bad ages", "age_min": 30, "age_max": 20 }),
+        json!({ "name": "bad groups", "min_groups": 0 }),
+    ] {
+        let (status, _) = app
+            .post_json("/saved-searches", body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[sqlx::test]
+async fn scheduler_marks_new_finds_matching_saved_searches(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    seed_group(&pool, user.id, 10).await;
+    seed_group(&pool, user.id, 20).await;
+
+    let (status, created) = app
+        .post_json("/saved-searches", search_body(), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().expect("saved search misses id");
+
+    seed_candidates(&pool, user.id).await;
+
+    let matched = find_w::scheduler::run_due_searches(&pool)
+        .await
+        .expect("scheduler run failed");
+    assert_eq!(matched, 1);
+
+    let (status, matches) = app
+        .get_json(
+            &format!("/saved-searches/{id}/matches"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let matches = matches.as_array().expect("matches are not an array");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["vk_user"]["vk_user_id"], 1000);
+
+    let matched = find_w::scheduler::run_due_searches(&pool)
+        .await
+        .expect("second scheduler run failed");
+    assert_eq!(matched, 0);
+}
+
+#[sqlx::test]
+async fn saved_search_picks_up_older_finds_that_commit_late(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let (status, _) = app
+        .post_json(
+            "/saved-searches",
+            json!({ "name": "everyone in Moscow", "city": "Moscow" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let now = OffsetDateTime::now_utc();
+
+    // A slow crawl saw its user first but is still writing.
+    let mut slow_crawl = pool.begin().await.unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO vk_users (user_id, vk_user_id, first_name, city, first_seen_at, last_seen_at)
+        VALUES ($1, 1001, 'Petr', 'Moscow', $2, $2)
+        "#,
+    )
+    .bind(user.id)
+    .bind(now - time::Duration::minutes(10))
+    .execute(&mut *slow_crawl)
+    .await
+    .unwrap();
+
+    // A later crawl with a newer timestamp commits first.
+    vk_users_repo::upsert_vk_users(&pool, user.id, &[sample_vk_user(1000, "Ivan", now)])
+        .await
+        .unwrap();
+    let matches = find_w::saved_searches::repo::mark_new_matches(&pool, user.id)
+        .await
+        .unwrap();
+    assert!(matches.is_empty());
+
+    slow_crawl.commit().await.unwrap();
+    let matches = find_w::saved_searches::repo::mark_new_matches(&pool, user.id)
+        .await
+        .unwrap();
+    let mut matched: Vec<i64> = matches.iter().map(|m| m.vk_user_id).collect();
+    matched.sort();
+    assert_eq!(matched, vec![1000, 1001]);
+
+    let matches = find_w::saved_searches::repo::mark_new_matches(&pool, user.id)
+        .await
+        .unwrap();
+    assert!(matches.is_empty());
+}
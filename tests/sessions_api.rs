@@ -0,0 +1,189 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use find_w::app::trusted_proxies::TrustedProxies;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn login_from(app: &TestApp, email: &str, user_agent: &str, ip: &str) -> Value {
+    let (status, body) = app
+        .post_json_with_headers(
+            "/auth/login",
+            json!({ "email": email, "password": "strong-password-123" }),
+            None,
+            &[("user-agent", user_agent), ("x-forwarded-for", ip)],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+#[sqlx::test]
+async fn sessions_record_client_and_follow_refresh(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let phone = login_from(&app, &user.email, "Phone/1.0", "203.0.113.7, 10.0.0.1").await;
+
+    let (status, body) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let sessions = body.as_array().expect("sessions must be an array");
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0]["user_agent"], "Phone/1.0");
+    assert_eq!(sessions[0]["ip_address"], "203.0.113.7");
+    assert_eq!(sessions[1]["user_agent"], Value::Null);
+    let phone_session = sessions[0]["id"].clone();
+
+    let (status, _) = app
+        .post_json_with_headers(
+            "/auth/refresh",
+            json!({ "refresh_token": phone["refresh_token"] }),
+            None,
+            &[("user-agent", "Phone/1.1"), ("x-real-ip", "198.51.100.2")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    let sessions = body.as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0]["user_agent"], "Phone/1.1");
+    assert_eq!(sessions[0]["ip_address"], "198.51.100.2");
+    assert_ne!(sessions[0]["id"], phone_session);
+}
+
+#[sqlx::test]
+async fn revoking_a_session_logs_that_device_out(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    let lost = login_from(&app, &user.email, "Lost/1.0", "203.0.113.9").await;
+
+    let (_, body) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    let lost_id = body[0]["id"].as_str().unwrap().to_string();
+    assert_eq!(body[0]["user_agent"], "Lost/1.0");
+
+    let status = app
+        .delete(
+            &format!("/me/sessions/{lost_id}"),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let status = app
+        .delete(
+            &format!("/me/sessions/{}", Uuid::new_v4()),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let status = app
+        .delete(&format!("/me/sessions/{lost_id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let status = app
+        .delete(&format!("/me/sessions/{lost_id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": lost["refresh_token"] }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .post_json(
+            "/auth/refresh",
+            json!({ "refresh_token": user.refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn revoke_all_ends_every_session(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    let second = login_from(&app, &user.email, "Laptop/2.0", "203.0.113.10").await;
+
+    let (status, body) = app
+        .post_json(
+            "/me/sessions/revoke-all",
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked"], 2);
+
+    for token in [
+        user.refresh_token.as_str(),
+        second["refresh_token"].as_str().unwrap(),
+    ] {
+        let (status, _) = app
+            .post_json("/auth/refresh", json!({ "refresh_token": token }), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, _) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let fresh = login_from(&app, &user.email, "Laptop/2.0", "203.0.113.10").await;
+    let (_, body) = app
+        .get_json("/me/sessions", fresh["access_token"].as_str())
+        .await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    let (_, body) = app
+        .get_json("/me/sessions", Some(&other.access_token))
+        .await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test]
+async fn forwarded_ip_uses_the_rightmost_untrusted_hop(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    login_from(
+        &app,
+        &user.email,
+        "Spoofer/1.0",
+        "1.2.3.4, 198.51.100.9, 10.0.0.1",
+    )
+    .await;
+
+    let (_, body) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    assert_eq!(body[0]["user_agent"], "Spoofer/1.0");
+    assert_eq!(body[0]["ip_address"], "198.51.100.9");
+}
+
+#[sqlx::test]
+async fn forwarding_headers_are_ignored_from_untrusted_peers(pool: PgPool) {
+    let app = TestApp::with_state(pool, |state| {
+        state.trusted_proxies = TrustedProxies::default();
+    });
+    let user = app.register_and_login().await;
+    let (status, _) = app
+        .post_json_with_headers(
+            "/auth/login",
+            json!({ "email": user.email, "password": "strong-password-123" }),
+            None,
+            &[
+                ("user-agent", "Spoofer/1.0"),
+                ("x-forwarded-for", "203.0.113.7"),
+                ("x-real-ip", "203.0.113.8"),
+            ],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.get_json("/me/sessions", Some(&user.access_token)).await;
+    assert_eq!(body[0]["user_agent"], "Spoofer/1.0");
+    assert_eq!(body[0]["ip_address"], "127.0.0.1");
+}
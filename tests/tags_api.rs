@@ -0,0 +1,306 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, sample_vk_user, seed_vk_user};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+fn vk_user_ids(body: &Value) -> Vec<i64> {
+    body.as_array()
+        .expect("response is not an array")
+        .iter()
+        .map(|vk_user| vk_user["vk_user_id"].as_i64().expect("missing vk_user_id"))
+        .collect()
+}
+
+#[sqlx::test]
+async fn tags_can_be_created_renamed_recolored_and_deleted(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    for body in [
+        json!({ "name": "   " }),
+        json!({ "name": "x".repeat(65) }),
+        json!({ "name": "friends", "color": "red" }),
+        json!({ "name": "friends", "color": "#12345g" }),
+    ] {
+        let (status, _) = app.post_json("/tags", body, Some(&user.access_token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let (status, tag) = app
+        .post_json(
+            "/tags",
+            json!({ "name": " friends ", "color": "#FF8800" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(tag["name"], "friends");
+    assert_eq!(tag["color"], "#ff8800");
+    let id = tag["id"].as_str().expect("tag misses id");
+
+    let (status, _) = app
+        .post_json(
+            "/tags",
+            json!({ "name": "friends" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = app
+        .post_json(
+            "/tags",
+            json!({ "name": "friends" }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, tag) = app
+        .patch_json(
+            &format!("/tags/{id}"),
+            json!({ "name": "close friends" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tag["name"], "close friends");
+    assert_eq!(tag["color"], "#ff8800");
+
+    let (status, tag) = app
+        .patch_json(
+            &format!("/tags/{id}"),
+            json!({ "color": "" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tag["color"], Value::Null);
+
+    let (status, _) = app
+        .patch_json(
+            &format!("/tags/{id}"),
+            json!({ "name": "stolen" }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, tags) = app.get_json("/tags", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tags.as_array().map(Vec::len), Some(1));
+
+    let status = app
+        .delete(&format!("/tags/{id}"), Some(&other.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let status = app
+        .delete(&format!("/tags/{id}"), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, tags) = app.get_json("/tags", Some(&user.access_token)).await;
+    assert_eq!(tags, json!([]));
+}
+
+#[sqlx::test]
+async fn vk_users_can_be_tagged_starred_hidden_and_filtered(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+    for vk_user_id in [1000, 1001, 1002] {
+        seed_vk_user(&pool, user.id, vk_user_id).await;
+    }
+
+    let (_, tag) = app
+        .post_json(
+            "/tags",
+            json!({ "name": "friends", "color": "#00aa00" }),
+            Some(&user.access_token),
+        )
+        .await;
+    let tag_id = tag["id"].as_str().expect("tag misses id").to_string();
+
+    let status = app
+        .put(
+            &format!("/vk-users/1000/tags/{tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let status = app
+        .put(
+            &format!("/vk-users/1000/tags/{tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let status = app
+        .put(
+            &format!("/vk-users/9999/tags/{tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let status = app
+        .put(
+            &format!("/vk-users/1000/tags/{tag_id}"),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, vk_user) = app
+        .patch_json(
+            "/vk-users/1001",
+            json!({ "is_favorite": true }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(vk_user["is_favorite"], true);
+    assert_eq!(vk_user["is_hidden"], false);
+
+    let (status, _) = app
+        .patch_json("/vk-users/1002", json!({}), Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .patch_json(
+            "/vk-users/1002",
+            json!({ "is_hidden": true }),
+            Some(&other.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .patch_json(
+            "/vk-users/1002",
+            json!({ "is_hidden": true }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app.get_json("/vk-users", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut ids = vk_user_ids(&body);
+    ids.sort();
+    assert_eq!(ids, vec![1000, 1001]);
+    let tagged = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|vk_user| vk_user["vk_user_id"] == 1000)
+        .unwrap();
+    assert_eq!(tagged["tags"][0]["name"], "friends");
+    assert_eq!(tagged["tags"][0]["color"], "#00aa00");
+
+    let (_, body) = app
+        .get_json("/vk-users?include_hidden=true", Some(&user.access_token))
+        .await;
+    assert_eq!(vk_user_ids(&body).len(), 3);
+
+    let (_, body) = app
+        .get_json(
+            &format!("/vk-users?tags={tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(vk_user_ids(&body), vec![1000]);
+
+    let (_, body) = app
+        .get_json("/vk-users?favorite=true", Some(&user.access_token))
+        .await;
+    assert_eq!(vk_user_ids(&body), vec![1001]);
+
+    let (status, _) = app
+        .get_json("/vk-users?tags=not-a-uuid", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let status = app
+        .delete(
+            &format!("/vk-users/1000/tags/{tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let status = app
+        .delete(
+            &format!("/vk-users/1000/tags/{tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, body) = app
+        .get_json(
+            &format!("/vk-users?tags={tag_id}"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(body, json!([]));
+}
+
+#[sqlx::test]
+async fn hidden_vk_users_are_excluded_from_saved_search_results_and_matches(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (_, search) = app
+        .post_json(
+            "/saved-searches",
+            json!({ "name": "everyone in Moscow", "city": "Moscow" }),
+            Some(&user.access_token),
+        )
+        .await;
+    let search_id = search["id"].as_str().expect("search misses id");
+
+    let now = OffsetDateTime::now_utc();
+    find_w::vk_users::repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[
+            sample_vk_user(1000, "Ivan", now),
+            sample_vk_user(1001, "Petr", now),
+        ],
+    )
+    .await
+    .expect("failed to seed vk users");
+
+    let (status, _) = app
+        .patch_json(
+            "/vk-users/1001",
+            json!({ "is_hidden": true }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, results) = app
+        .get_json(
+            &format!("/saved-searches/{search_id}/results"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(vk_user_ids(&results), vec![1000]);
+
+    let matched = find_w::scheduler::run_due_searches(&pool)
+        .await
+        .expect("scheduler run failed");
+    assert_eq!(matched, 1);
+
+    let (_, matches) = app
+        .get_json(
+            &format!("/saved-searches/{search_id}/matches"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(matches.as_array().map(Vec::len), Some(1));
+    assert_eq!(matches[0]["vk_user"]["vk_user_id"], 1000);
+}
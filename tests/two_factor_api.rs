@@ -0,0 +1,371 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, TestUser};
+use find_w::auth::totp;
+use futures_util::future::join_all;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+const PASSWORD: &str = "strong-password-123";
+
+fn code(secret: &str, step: i64) -> String {
+    totp::code_at_step(secret, step).expect("invalid totp secret")
+}
+
+fn current_step() -> i64 {
+    totp::step_at(OffsetDateTime::now_utc().unix_timestamp())
+}
+
+async fn enroll(app: &TestApp, user: &TestUser) -> (String, Vec<String>, i64) {
+    let (status, body) = app
+        .post_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = body["secret"].as_str().unwrap().to_string();
+    let recovery_codes = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+
+    let step = current_step();
+    let (status, _) = app
+        .post_json(
+            "/me/2fa/confirm",
+            json!({ "code": code(&secret, step) }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    (secret, recovery_codes, step)
+}
+
+async fn challenge(app: &TestApp, user: &TestUser) -> String {
+    let (status, body) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(body.get("access_token").is_none());
+    body["challenge_token"].as_str().unwrap().to_string()
+}
+
+async fn second_step(app: &TestApp, challenge_token: &str, code: &str) -> (StatusCode, Value) {
+    app.post_json(
+        "/auth/login/2fa",
+        json!({ "challenge_token": challenge_token, "code": code }),
+        None,
+    )
+    .await
+}
+
+#[sqlx::test]
+async fn enrollment_is_confirmed_with_a_code(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+
+    let (status, _) = app
+        .post_json(
+            "/me/2fa",
+            json!({ "password": "wrong-password" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = app
+        .post_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = body["secret"].as_str().unwrap();
+    let uri = body["otpauth_uri"].as_str().unwrap();
+    assert!(uri.starts_with("otpauth://totp/find-w:"));
+    assert!(uri.contains(&format!("secret={secret}")));
+    let recovery_codes = body["recovery_codes"].as_array().unwrap();
+    assert_eq!(recovery_codes.len(), totp::RECOVERY_CODE_COUNT);
+
+    // A pending enrollment does not change how login works yet.
+    let (status, _) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/me/2fa/confirm",
+            json!({ "code": "000000" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .post_json(
+            "/me/2fa/confirm",
+            json!({ "code": code(secret, current_step()) }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = app
+        .post_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let stored = sqlx::query!(
+        r#"
+        SELECT
+            encode(t.secret_encrypted, 'escape') AS "secret!",
+            array_agg(c.code_hash) AS "code_hashes!"
+        FROM user_totp AS t
+        JOIN totp_recovery_codes AS c ON c.user_id = t.user_id
+        WHERE t.user_id = $1
+        GROUP BY t.secret_encrypted
+        "#,
+        user.id
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("failed to fetch stored 2fa data");
+    assert!(!stored.secret.contains(secret));
+    for recovery_code in recovery_codes {
+        let recovery_code = recovery_code.as_str().unwrap();
+        assert!(!stored.code_hashes.iter().any(|h| h.contains(recovery_code)));
+    }
+}
+
+#[sqlx::test]
+async fn login_requires_a_fresh_second_factor(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let (secret, recovery_codes, step) = enroll(&app, &user).await;
+
+    let token = challenge(&app, &user).await;
+    let (status, _) = second_step(&app, "not-a-challenge", &code(&secret, step + 1)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = second_step(&app, &token, "123").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = second_step(&app, &token, &code(&secret, step + 1)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get_json("/me", body["access_token"].as_str()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Neither the challenge nor the code can be used twice.
+    let (status, _) = second_step(&app, &token, &code(&secret, step + 1)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let token = challenge(&app, &user).await;
+    let (status, _) = second_step(&app, &token, &code(&secret, step + 1)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let recovery_code = recovery_codes[0].to_uppercase();
+    let (status, _) = second_step(&app, &token, &recovery_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = challenge(&app, &user).await;
+    let (status, _) = second_step(&app, &token, &recovery_code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn challenges_allow_limited_attempts(pool: PgPool) {
+    // Keep the per-account throttle out of the way of the per-challenge cap.
+    let app = TestApp::with_state(pool, |state| {
+        state.login_throttle.two_factor.free_attempts = 10;
+        state.login_throttle.two_factor.lockout_after = 10;
+    });
+    let user = app.register_and_login().await;
+    let (_, recovery_codes, _) = enroll(&app, &user).await;
+
+    let token = challenge(&app, &user).await;
+    for _ in 0..5 {
+        let (status, _) = second_step(&app, &token, "wrong-code").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, _) = second_step(&app, &token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn two_factor_can_be_disabled(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, _) = app
+        .delete_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD, "code": "000000" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, recovery_codes, _) = enroll(&app, &user).await;
+    let (status, _) = app
+        .delete_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD, "code": "wrong-code" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .delete_json(
+            "/me/2fa",
+            json!({ "password": "wrong-password", "code": recovery_codes[1] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .delete_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD, "code": recovery_codes[1] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = app
+        .post_json(
+            "/auth/login",
+            json!({ "email": user.email, "password": PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["access_token"].is_string());
+}
+
+#[sqlx::test]
+async fn wrong_codes_lock_the_second_factor_across_challenges(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let (_, recovery_codes, _) = enroll(&app, &user).await;
+
+    for _ in 0..3 {
+        let token = challenge(&app, &user).await;
+        for _ in 0..2 {
+            let (status, _) = second_step(&app, &token, "000000").await;
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                continue;
+            }
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    let token = challenge(&app, &user).await;
+    let (status, body) = second_step(&app, &token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+}
+
+#[sqlx::test]
+async fn password_alone_does_not_reset_the_login_throttle(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let (_, recovery_codes, _) = enroll(&app, &user).await;
+    let email_failures = || {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM login_throttles WHERE scope = 'email' AND failures > 0",
+        )
+        .fetch_one(&pool)
+    };
+
+    let token = challenge(&app, &user).await;
+    assert_eq!(email_failures().await.unwrap(), 1);
+
+    let (status, _) = second_step(&app, &token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(email_failures().await.unwrap(), 0);
+}
+
+#[sqlx::test]
+async fn parallel_guesses_share_the_challenge_attempts(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    enroll(&app, &user).await;
+    let token = challenge(&app, &user).await;
+
+    let guesses = (0..12).map(|_| second_step(&app, &token, "000000"));
+    for (status, _) in join_all(guesses).await {
+        assert!(
+            status == StatusCode::UNAUTHORIZED || status == StatusCode::TOO_MANY_REQUESTS,
+            "unexpected status {status}"
+        );
+    }
+
+    let attempts: i32 = sqlx::query_scalar("SELECT attempts FROM login_challenges")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(attempts, 5);
+    let failures: i32 =
+        sqlx::query_scalar("SELECT failures FROM login_throttles WHERE scope = 'two_factor'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(failures <= 5, "{failures} codes were checked");
+}
+
+#[sqlx::test]
+async fn disabling_two_factor_shares_the_lockout(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let (_, recovery_codes, _) = enroll(&app, &user).await;
+
+    for body in [
+        json!({ "password": "wrong-password", "code": recovery_codes[0] }),
+        json!({ "password": PASSWORD, "code": "000000" }),
+    ]
+    .iter()
+    .cycle()
+    .take(5)
+    {
+        let (status, _) = app
+            .delete_json("/me/2fa", body.clone(), Some(&user.access_token))
+            .await;
+        assert!(
+            status == StatusCode::UNAUTHORIZED
+                || status == StatusCode::BAD_REQUEST
+                || status == StatusCode::TOO_MANY_REQUESTS,
+            "unexpected status {status}"
+        );
+    }
+
+    let (status, body) = app
+        .delete_json(
+            "/me/2fa",
+            json!({ "password": PASSWORD, "code": recovery_codes[0] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+
+    // The login second step is locked for the same account.
+    let token = challenge(&app, &user).await;
+    let (status, _) = second_step(&app, &token, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
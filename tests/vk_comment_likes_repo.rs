@@ -43,7 +43,9 @@ async fn vk_comment_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
     seed_comment(&pool, user_id, 10, 1, 102, 1000).await;
     seed_comment(&pool, user_id, 10, 1, 103, 1000).await;
 
-    let first_found = OffsetDateTime::now_utc();
+    let first_found = OffsetDateTime::now_utc()
+        .replace_nanosecond(0)
+        .expect("failed to truncate timestamp");
     let second_found = first_found + time::Duration::minutes(5);
     let third_found = first_found + time::Duration::minutes(10);
 
@@ -53,14 +55,14 @@ async fn vk_comment_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
             group_id: 10,
             post_id: 1,
             comment_id: 101,
-            found_date: first_found,
+            seen_at: first_found,
         },
         NewVkCommentLike {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 1,
             comment_id: 102,
-            found_date: second_found,
+            seen_at: second_found,
         },
     ];
 
@@ -76,14 +78,14 @@ async fn vk_comment_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
             group_id: 10,
             post_id: 1,
             comment_id: 101,
-            found_date: third_found,
+            seen_at: third_found,
         },
         NewVkCommentLike {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 1,
             comment_id: 103,
-            found_date: second_found,
+            seen_at: second_found,
         },
     ];
 
@@ -107,7 +109,8 @@ async fn vk_comment_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
                 && row.vk_user_id == 1000
         })
         .expect("updated like not found");
-    assert_eq!(updated.found_date, third_found);
+    assert_eq!(updated.first_seen_at, first_found);
+    assert_eq!(updated.last_seen_at, third_found);
 }
 
 #[sqlx::test]
@@ -134,14 +137,14 @@ async fn vk_comment_likes_delete_is_batch_and_scoped_to_user(pool: PgPool) {
                 group_id: 20,
                 post_id: 11,
                 comment_id: 501,
-                found_date: OffsetDateTime::now_utc(),
+                seen_at: OffsetDateTime::now_utc(),
             },
             NewVkCommentLike {
                 vk_user_id: 2000,
                 group_id: 20,
                 post_id: 11,
                 comment_id: 502,
-                found_date: OffsetDateTime::now_utc(),
+                seen_at: OffsetDateTime::now_utc(),
             },
         ],
     )
@@ -156,7 +159,7 @@ async fn vk_comment_likes_delete_is_batch_and_scoped_to_user(pool: PgPool) {
             group_id: 20,
             post_id: 11,
             comment_id: 501,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
@@ -215,7 +218,7 @@ async fn vk_comment_likes_cascade_from_comments_and_vk_users(pool: PgPool) {
             group_id: 30,
             post_id: 21,
             comment_id: 701,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
@@ -229,7 +232,7 @@ async fn vk_comment_likes_cascade_from_comments_and_vk_users(pool: PgPool) {
             group_id: 30,
             post_id: 21,
             comment_id: 702,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
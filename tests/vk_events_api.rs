@@ -0,0 +1,121 @@
+mod common;
+
+use axum::http::StatusCode;
+use find_w::{
+    vk_comment_likes::repo::{self as vk_comment_likes_repo, NewVkCommentLike},
+    vk_comments::repo::{self as vk_comments_repo, NewVkComment, VkCommentKey},
+    vk_posts::repo::VkPostKey,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+use crate::common::{TestApp, seed_group, seed_post, seed_vk_user};
+
+fn comment(comment_id: i64, from_id: i64) -> NewVkComment {
+    NewVkComment {
+        group_id: 10,
+        post_id: 1,
+        comment_id,
+        from_id,
+        created_date: 1_700_040_000 + comment_id,
+        comment_text: Some(format!("comment-{comment_id}")),
+    }
+}
+
+#[sqlx::test]
+async fn vk_events_list_deleted_comments_and_removed_comment_likes(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let other = app.register_and_login().await;
+
+    seed_group(&pool, user.id, 10).await;
+    seed_vk_user(&pool, user.id, 1000).await;
+    seed_vk_user(&pool, user.id, 1001).await;
+    seed_post(&pool, user.id, 10, 1000, 1, 1_700_030_001).await;
+
+    vk_comments_repo::upsert_vk_comments(&pool, user.id, &[comment(101, 1000), comment(102, 1001)])
+        .await
+        .expect("failed to seed comments");
+
+    let like = |vk_user_id: i64| NewVkCommentLike {
+        vk_user_id,
+        group_id: 10,
+        post_id: 1,
+        comment_id: 101,
+        seen_at: OffsetDateTime::now_utc(),
+    };
+    vk_comment_likes_repo::upsert_vk_comment_likes(&pool, user.id, &[like(1000), like(1001)])
+        .await
+        .expect("failed to seed comment likes");
+
+    let post = VkPostKey {
+        group_id: 10,
+        post_id: 1,
+    };
+    let res = vk_comments_repo::reconcile_vk_comments(&pool, user.id, &post, &[comment(101, 1000)])
+        .await
+        .expect("failed to reconcile comments");
+    assert_eq!(res.deleted, 1);
+
+    let comment_key = VkCommentKey {
+        group_id: 10,
+        post_id: 1,
+        comment_id: 101,
+    };
+    let res = vk_comment_likes_repo::reconcile_vk_comment_likes(
+        &pool,
+        user.id,
+        &comment_key,
+        &[like(1000)],
+    )
+    .await
+    .expect("failed to reconcile comment likes");
+    assert_eq!(res.removed, 1);
+
+    let (status, body) = app.get_json("/vk-events", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let events = body.as_array().expect("response must be array");
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| {
+        event["kind"] == "comment_deleted"
+            && event["comment_id"] == 102
+            && event["vk_user_id"] == 1001
+    }));
+    assert!(events.iter().any(|event| {
+        event["kind"] == "comment_like_removed"
+            && event["comment_id"] == 101
+            && event["vk_user_id"] == 1001
+    }));
+
+    let (status, body) = app
+        .get_json(
+            "/vk-events?kind=comment_like_removed",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+
+    let (status, body) = app.get_json("/vk-events", Some(&other.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(0));
+}
+
+#[sqlx::test]
+async fn vk_events_validate_kind_and_require_auth(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    let (status, body) = app
+        .get_json("/vk-events?kind=post_edited", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body.get("error").and_then(Value::as_str),
+        Some("BAD_REQUEST")
+    );
+
+    let (status, _) = app.get_json("/vk-events", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -2,6 +2,7 @@ mod common;
 
 use crate::common::{create_user, seed_group, seed_post, seed_vk_user};
 use find_w::vk_post_likes::repo::{self, NewVkPostLike, VkPostLikeKey};
+use find_w::vk_posts::repo::VkPostKey;
 use sqlx::PgPool;
 use time::OffsetDateTime;
 
@@ -14,7 +15,9 @@ async fn vk_post_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
     seed_post(&pool, user_id, 10, 1000, 2, 1_700_010_002).await;
     seed_post(&pool, user_id, 10, 1000, 3, 1_700_010_003).await;
 
-    let first_found = OffsetDateTime::now_utc();
+    let first_found = OffsetDateTime::now_utc()
+        .replace_nanosecond(0)
+        .expect("failed to truncate timestamp");
     let second_found = first_found + time::Duration::minutes(5);
     let third_found = first_found + time::Duration::minutes(10);
 
@@ -23,13 +26,13 @@ async fn vk_post_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 1,
-            found_date: first_found,
+            seen_at: first_found,
         },
         NewVkPostLike {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 2,
-            found_date: second_found,
+            seen_at: second_found,
         },
     ];
 
@@ -44,13 +47,13 @@ async fn vk_post_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 1,
-            found_date: third_found,
+            seen_at: third_found,
         },
         NewVkPostLike {
             vk_user_id: 1000,
             group_id: 10,
             post_id: 3,
-            found_date: second_found,
+            seen_at: second_found,
         },
     ];
 
@@ -69,7 +72,8 @@ async fn vk_post_likes_upsert_inserts_and_updates_in_batch(pool: PgPool) {
         .iter()
         .find(|row| row.post_id == 1 && row.group_id == 10 && row.vk_user_id == 1000)
         .expect("updated like not found");
-    assert_eq!(updated.found_date, third_found);
+    assert_eq!(updated.first_seen_at, first_found);
+    assert_eq!(updated.last_seen_at, third_found);
 }
 
 #[sqlx::test]
@@ -93,13 +97,13 @@ async fn vk_post_likes_delete_is_batch_and_scoped_to_user(pool: PgPool) {
                 vk_user_id: 2000,
                 group_id: 20,
                 post_id: 11,
-                found_date: OffsetDateTime::now_utc(),
+                seen_at: OffsetDateTime::now_utc(),
             },
             NewVkPostLike {
                 vk_user_id: 2000,
                 group_id: 20,
                 post_id: 12,
-                found_date: OffsetDateTime::now_utc(),
+                seen_at: OffsetDateTime::now_utc(),
             },
         ],
     )
@@ -113,7 +117,7 @@ async fn vk_post_likes_delete_is_batch_and_scoped_to_user(pool: PgPool) {
             vk_user_id: 2000,
             group_id: 20,
             post_id: 11,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
@@ -168,7 +172,7 @@ async fn vk_post_likes_cascade_from_vk_posts_and_vk_users(pool: PgPool) {
             vk_user_id: 3000,
             group_id: 30,
             post_id: 21,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
@@ -181,7 +185,7 @@ async fn vk_post_likes_cascade_from_vk_posts_and_vk_users(pool: PgPool) {
             vk_user_id: 3001,
             group_id: 30,
             post_id: 22,
-            found_date: OffsetDateTime::now_utc(),
+            seen_at: OffsetDateTime::now_utc(),
         }],
     )
     .await
@@ -229,3 +233,50 @@ async fn vk_post_likes_cascade_from_vk_posts_and_vk_users(pool: PgPool) {
     .expect("failed to count likes by vk user");
     assert_eq!(left_for_deleted_vk_user, 0);
 }
+
+#[sqlx::test]
+async fn vk_post_likes_reconcile_marks_retracted_likes_removed(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    seed_group(&pool, user_id, 10).await;
+    seed_vk_user(&pool, user_id, 1000).await;
+    seed_vk_user(&pool, user_id, 1001).await;
+    seed_post(&pool, user_id, 10, 1000, 1, 1_700_010_001).await;
+    seed_post(&pool, user_id, 10, 1000, 2, 1_700_010_002).await;
+
+    let like = |vk_user_id: i64, post_id: i64| NewVkPostLike {
+        vk_user_id,
+        group_id: 10,
+        post_id,
+        seen_at: OffsetDateTime::now_utc(),
+    };
+
+    repo::upsert_vk_post_likes(
+        &pool,
+        user_id,
+        &[like(1000, 1), like(1001, 1), like(1001, 2)],
+    )
+    .await
+    .expect("failed to seed likes");
+
+    let post = VkPostKey {
+        group_id: 10,
+        post_id: 1,
+    };
+    let res = repo::reconcile_vk_post_likes(&pool, user_id, &post, &[like(1000, 1)])
+        .await
+        .expect("failed to reconcile likes");
+    assert_eq!(res.inserted, 0);
+    assert_eq!(res.updated, 1);
+    assert_eq!(res.removed, 1);
+
+    let rows = repo::list_vk_post_likes(&pool, user_id, 100, 0)
+        .await
+        .expect("failed to list likes");
+    assert_eq!(rows.len(), 3);
+    let removed: Vec<(i64, i64)> = rows
+        .iter()
+        .filter(|row| row.removed_at.is_some())
+        .map(|row| (row.vk_user_id, row.post_id))
+        .collect();
+    assert_eq!(removed, vec![(1001, 1)]);
+}
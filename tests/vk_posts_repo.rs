@@ -232,3 +232,55 @@ async fn vk_posts_have_fk_to_groups_and_vk_users_with_cascade(pool: PgPool) {
     .expect("failed to count posts after vk_user delete");
     assert_eq!(vk_user_post_count, 0);
 }
+
+#[sqlx::test]
+async fn vk_posts_reconcile_marks_missing_posts_deleted_and_restores_returning(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    seed_group(&pool, user_id, 10).await;
+    seed_group(&pool, user_id, 20).await;
+    seed_vk_user(&pool, user_id, 1000).await;
+
+    let post = |group_id: i64, post_id: i64| NewVkPost {
+        post_id,
+        group_id,
+        from_id: 1000,
+        created_date: 1_700_000_000 + post_id,
+        post_type: Some("post".to_string()),
+        post_text: Some(format!("post-{post_id}")),
+    };
+
+    repo::upsert_vk_posts(&pool, user_id, &[post(10, 1), post(10, 2), post(20, 3)])
+        .await
+        .expect("failed to seed posts");
+
+    let res = repo::reconcile_vk_posts(&pool, user_id, 10, &[post(10, 2), post(10, 4)])
+        .await
+        .expect("failed to reconcile posts");
+    assert_eq!(res.inserted, 1);
+    assert_eq!(res.updated, 1);
+    assert_eq!(res.deleted, 1);
+
+    let rows = repo::list_vk_posts(&pool, user_id, 100, 0)
+        .await
+        .expect("failed to list posts");
+    assert_eq!(rows.len(), 4);
+    let deleted: Vec<(i64, i64)> = rows
+        .iter()
+        .filter(|row| row.deleted_at.is_some())
+        .map(|row| (row.group_id, row.post_id))
+        .collect();
+    assert_eq!(deleted, vec![(10, 1)]);
+
+    let res = repo::reconcile_vk_posts(&pool, user_id, 10, &[post(10, 2), post(10, 4)])
+        .await
+        .expect("failed to reconcile posts twice");
+    assert_eq!(res.deleted, 0);
+
+    repo::upsert_vk_posts(&pool, user_id, &[post(10, 1)])
+        .await
+        .expect("failed to upsert returning post");
+    let rows = repo::list_vk_posts(&pool, user_id, 100, 0)
+        .await
+        .expect("failed to list posts after restore");
+    assert!(rows.iter().all(|row| row.deleted_at.is_none()));
+}
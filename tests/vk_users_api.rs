@@ -1,11 +1,12 @@
 mod common;
 
 use axum::http::StatusCode;
-use find_w::vk_users::repo::{self, NewVkUser};
+use find_w::vk_post_likes::repo::{self as vk_post_likes_repo, NewVkPostLike};
+use find_w::vk_users::repo::{self, NewVkUser, VkUsersFilter};
 use sqlx::PgPool;
 use time::{Duration, OffsetDateTime};
 
-use crate::common::{TestApp, create_user, sample_vk_user};
+use crate::common::{TestApp, create_user, sample_vk_user, seed_group, seed_post};
 
 #[sqlx::test]
 async fn vk_users_list_is_paginated_and_scoped_to_current_user(pool: PgPool) {
@@ -42,6 +43,7 @@ async fn vk_users_list_is_paginated_and_scoped_to_current_user(pool: PgPool) {
             .and_then(serde_json::Value::as_i64),
         Some(1002)
     );
+    assert_eq!(page[0]["finded_date"], page[0]["first_seen_at"]);
 
     let (status, body) = app
         .get_json("/vk-users?limit=100&offset=0", Some(&user_one.access_token))
@@ -87,7 +89,7 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
             first_name: Some("Ivan-updated".to_string()),
             last_name: Some("Updated".to_string()),
             city: Some("Saint Petersburg".to_string()),
-            finded_date: now + Duration::hours(1),
+            seen_at: now + Duration::hours(1),
             is_closed: Some(true),
             screen_name: Some("ivan_updated".to_string()),
             can_access_closed: Some(false),
@@ -95,6 +97,7 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
             status: Some("updated status".to_string()),
             bdate: Some("02.02.1992".to_string()),
             photo: Some("https://img.test/ivan-updated.jpg".to_string()),
+            source_group_id: None,
         },
         sample_vk_user(103, "Petr", now + Duration::hours(2)),
     ];
@@ -116,7 +119,7 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
     .await
     .expect("failed to upsert second user rows");
 
-    let user_one_rows = repo::list_vk_users(&pool, user_one, 100, 0)
+    let user_one_rows = repo::list_vk_users(&pool, user_one, &VkUsersFilter::default(), 100, 0)
         .await
         .expect("failed to list user one rows");
     assert_eq!(user_one_rows.len(), 3);
@@ -129,7 +132,7 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
     assert_eq!(updated.is_closed, Some(true));
     assert_eq!(updated.can_access_closed, Some(false));
 
-    let page = repo::list_vk_users(&pool, user_one, 1, 1)
+    let page = repo::list_vk_users(&pool, user_one, &VkUsersFilter::default(), 1, 1)
         .await
         .expect("failed to list paginated rows");
     assert_eq!(page.len(), 1);
@@ -140,13 +143,13 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
         .expect("failed to delete rows");
     assert_eq!(deleted, 1);
 
-    let user_one_rows = repo::list_vk_users(&pool, user_one, 100, 0)
+    let user_one_rows = repo::list_vk_users(&pool, user_one, &VkUsersFilter::default(), 100, 0)
         .await
         .expect("failed to list user one rows after delete");
     assert_eq!(user_one_rows.len(), 2);
     assert!(user_one_rows.iter().all(|row| row.vk_user_id != 101));
 
-    let user_two_rows = repo::list_vk_users(&pool, user_two, 100, 0)
+    let user_two_rows = repo::list_vk_users(&pool, user_two, &VkUsersFilter::default(), 100, 0)
         .await
         .expect("failed to list user two rows after delete");
     assert_eq!(user_two_rows.len(), 1);
@@ -175,3 +178,240 @@ async fn vk_users_repo_upsert_delete_and_cascade_behaviour(pool: PgPool) {
     .expect("failed to count cascade rows");
     assert_eq!(remaining, 0);
 }
+
+#[sqlx::test]
+async fn vk_users_history_records_only_changed_tracked_fields(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let now = OffsetDateTime::now_utc();
+
+    repo::upsert_vk_users(&pool, user.id, &[sample_vk_user(501, "Ivan", now)])
+        .await
+        .expect("failed to insert vk user");
+    repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[sample_vk_user(501, "Ivan", now + Duration::hours(1))],
+    )
+    .await
+    .expect("failed to re-upsert unchanged vk user");
+
+    let (status, body) = app
+        .get_json("/vk-users/501/history", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(0));
+
+    let mut changed = sample_vk_user(501, "Ivan", now + Duration::hours(2));
+    changed.status = Some("new status".to_string());
+    changed.photo = Some("https://img.test/501-new.jpg".to_string());
+    repo::upsert_vk_users(&pool, user.id, &[changed.clone()])
+        .await
+        .expect("failed to upsert changed vk user");
+
+    changed.city = Some("Kazan".to_string());
+    repo::upsert_vk_users(&pool, user.id, &[changed])
+        .await
+        .expect("failed to upsert moved vk user");
+
+    let (status, body) = app
+        .get_json("/vk-users/501/history", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let history = body.as_array().expect("response must be array");
+    assert_eq!(history.len(), 2);
+
+    let latest = history[0]["changes"]
+        .as_array()
+        .expect("changes must be array");
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[0]["field"], "city");
+    assert_eq!(latest[0]["old_value"], "Moscow");
+    assert_eq!(latest[0]["new_value"], "Kazan");
+
+    let earliest = history[1]["changes"]
+        .as_array()
+        .expect("changes must be array");
+    let fields: Vec<&str> = earliest
+        .iter()
+        .filter_map(|change| change["field"].as_str())
+        .collect();
+    assert_eq!(fields, vec!["status", "photo"]);
+    assert_eq!(earliest[0]["old_value"], "status_501");
+    assert_eq!(earliest[0]["new_value"], "new status");
+}
+
+#[sqlx::test]
+async fn vk_users_history_is_scoped_to_current_user(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user_one = app.register_and_login().await;
+    let user_two = app.register_and_login().await;
+
+    repo::upsert_vk_users(
+        &pool,
+        user_one.id,
+        &[sample_vk_user(601, "Anna", OffsetDateTime::now_utc())],
+    )
+    .await
+    .expect("failed to insert vk user");
+
+    let (status, _) = app
+        .get_json("/vk-users/601/history", Some(&user_two.access_token))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app.get_json("/vk-users/601/history", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn vk_users_upsert_keeps_first_seen_and_advances_last_seen(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let first_seen = OffsetDateTime::now_utc()
+        .replace_nanosecond(0)
+        .expect("failed to truncate timestamp");
+    let last_seen = first_seen + Duration::days(1);
+
+    repo::upsert_vk_users(&pool, user_id, &[sample_vk_user(901, "Oleg", first_seen)])
+        .await
+        .expect("failed to insert vk user");
+    repo::upsert_vk_users(&pool, user_id, &[sample_vk_user(901, "Oleg", last_seen)])
+        .await
+        .expect("failed to re-upsert vk user");
+    repo::upsert_vk_users(
+        &pool,
+        user_id,
+        &[sample_vk_user(901, "Oleg", first_seen + Duration::hours(1))],
+    )
+    .await
+    .expect("failed to upsert out-of-order crawl");
+
+    let vk_user = repo::get_vk_user(&pool, user_id, 901)
+        .await
+        .expect("failed to load vk user")
+        .expect("vk user not found");
+    assert_eq!(vk_user.first_seen_at, first_seen);
+    assert_eq!(vk_user.last_seen_at, last_seen);
+}
+
+#[sqlx::test]
+async fn vk_users_list_exposes_sources_and_filters_by_origin(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let now = OffsetDateTime::now_utc();
+
+    seed_group(&pool, user.id, 10).await;
+    seed_group(&pool, user.id, 20).await;
+
+    let mut member = sample_vk_user(301, "Member", now);
+    member.source_group_id = Some(10);
+    repo::upsert_vk_users(&pool, user.id, &[member, sample_vk_user(302, "Liker", now)])
+        .await
+        .expect("failed to insert vk users");
+
+    seed_post(&pool, user.id, 20, 301, 5, 1_700_050_005).await;
+    vk_post_likes_repo::upsert_vk_post_likes(
+        &pool,
+        user.id,
+        &[NewVkPostLike {
+            vk_user_id: 302,
+            group_id: 20,
+            post_id: 5,
+            seen_at: now,
+        }],
+    )
+    .await
+    .expect("failed to insert like");
+
+    let (status, body) = app
+        .get_json("/vk-users?group_id=10", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let found = body.as_array().expect("response must be array");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["vk_user_id"], 301);
+    let sources = found[0]["sources"]
+        .as_array()
+        .expect("sources must be array");
+    assert_eq!(sources.len(), 2);
+    assert!(sources.iter().any(|source| {
+        source["group_id"] == 10 && source["source_kind"] == "member" && source["source_ref"] == ""
+    }));
+    assert!(sources.iter().any(|source| {
+        source["group_id"] == 20 && source["source_kind"] == "author" && source["source_ref"] == "5"
+    }));
+
+    let (status, body) = app
+        .get_json(
+            "/vk-users?group_id=20&source_kind=liker",
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let found = body.as_array().expect("response must be array");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["vk_user_id"], 302);
+
+    let (status, body) = app
+        .get_json("/vk-users?source_kind=friend", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "BAD_REQUEST");
+}
+
+#[sqlx::test]
+async fn vk_users_history_keeps_order_within_one_transaction(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[sample_vk_user(501, "Ivan", OffsetDateTime::now_utc())],
+    )
+    .await
+    .expect("failed to insert vk user");
+
+    // Every snapshot below shares the transaction's changed_at.
+    let cities: Vec<String> = (1..=10).map(|n| format!("City {n}")).collect();
+    let mut tx = pool.begin().await.expect("failed to begin");
+    for city in &cities {
+        sqlx::query(
+            r#"
+            INSERT INTO vk_user_snapshots (user_id, vk_user_id, first_name, last_name, city)
+            SELECT user_id, vk_user_id, first_name, last_name, city
+            FROM vk_users
+            WHERE user_id = $1 AND vk_user_id = 501
+            "#,
+        )
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .expect("failed to record snapshot");
+        sqlx::query("UPDATE vk_users SET city = $2 WHERE user_id = $1 AND vk_user_id = 501")
+            .bind(user.id)
+            .bind(city)
+            .execute(&mut *tx)
+            .await
+            .expect("failed to move vk user");
+    }
+    tx.commit().await.expect("failed to commit");
+
+    let (status, body) = app
+        .get_json("/vk-users/501/history", Some(&user.access_token))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let new_cities: Vec<&str> = body
+        .as_array()
+        .expect("response must be array")
+        .iter()
+        .rev()
+        .map(|entry| {
+            let change = entry["changes"]
+                .as_array()
+                .and_then(|changes| changes.iter().find(|c| c["field"] == "city"))
+                .expect("missing city change");
+            change["new_value"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(new_cities, cities);
+}
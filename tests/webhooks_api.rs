@@ -0,0 +1,351 @@
+mod common;
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Redirect,
+    routing::post,
+};
+use common::{TestApp, sample_vk_user, seed_group};
+use find_w::webhooks::{sender::WebhookClient, targets::WebhookTargetPolicy};
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+#[derive(Clone, Default)]
+struct Listener {
+    received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    failures_left: Arc<AtomicUsize>,
+}
+
+async fn receive(State(listener): State<Listener>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    listener.received.lock().unwrap().push((headers, body));
+    let fail = listener
+        .failures_left
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+            left.checked_sub(1)
+        })
+        .is_ok();
+    if fail {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
+fn local_client() -> WebhookClient {
+    WebhookClient::new(WebhookTargetPolicy::AllowPrivate)
+}
+
+async fn start_listener(failures: usize) -> (String, Listener) {
+    let listener = Listener::default();
+    listener.failures_left.store(failures, Ordering::SeqCst);
+
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .route("/redirect", post(|| async { Redirect::temporary("/hook") }))
+        .with_state(listener.clone());
+    let tcp = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind webhook listener");
+    let addr = tcp.local_addr().expect("listener has no address");
+    tokio::spawn(async move {
+        axum::serve(tcp, app)
+            .await
+            .expect("webhook listener failed");
+    });
+
+    (format!("http://{addr}/hook"), listener)
+}
+
+fn assert_signed(headers: &HeaderMap, body: &[u8], secret: &str) {
+    let timestamp = headers["x-webhook-timestamp"]
+        .to_str()
+        .expect("timestamp header is not ascii");
+    let signature = headers["x-webhook-signature"]
+        .to_str()
+        .expect("signature header is not ascii");
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("invalid hmac key");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+    assert_eq!(signature, expected);
+}
+
+#[sqlx::test]
+async fn webhook_subscription_validates_and_hides_secret(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+
+    for body in [
+        json!({ "url": "ftp://example.test/hook", "event_types": ["crawl.failed"] }),
+        json!({ "url": "not a url", "event_types": ["crawl.failed"] }),
+        json!({ "url": "http://example.test/hook", "event_types": [] }),
+        json!({ "url": "http://example.test/hook", "event_types": ["test"] }),
+        json!({ "url": "http://example.test/hook", "event_types": ["crawl.failed"], "secret": "short" }),
+    ] {
+        let (status, _) = app
+            .post_json("/webhooks", body, Some(&user.access_token))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let (status, created) = app
+        .post_json(
+            "/webhooks",
+            json!({ "url": "http://example.test/hook", "event_types": ["crawl.failed", "vk_user.matched"] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["secret"].as_str().map(str::len), Some(64));
+
+    let (status, list) = app.get_json("/webhooks", Some(&user.access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().map(Vec::len), Some(1));
+    assert!(list[0].get("secret").is_none());
+    assert_eq!(
+        list[0]["event_types"],
+        json!(["crawl.failed", "vk_user.matched"])
+    );
+}
+
+#[sqlx::test]
+async fn webhook_test_event_is_signed_and_retried(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let (url, listener) = start_listener(1).await;
+    let secret = "local-listener-secret";
+
+    let (status, created) = app
+        .post_json(
+            "/webhooks",
+            json!({ "url": url, "event_types": ["crawl.failed"], "secret": secret }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().expect("webhook misses id");
+
+    let (status, delivery) = app
+        .post_json(
+            &format!("/webhooks/{id}/test"),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(delivery["status"], "pending");
+    assert_eq!(delivery["attempts"], 1);
+    assert_eq!(delivery["last_status_code"], 500);
+
+    sqlx::query!("UPDATE webhook_deliveries SET next_attempt_at = now()")
+        .execute(&pool)
+        .await
+        .expect("failed to make delivery due");
+    let delivered = find_w::webhooks::sender::deliver_due(&pool, &local_client())
+        .await
+        .expect("failed to deliver due webhooks");
+    assert_eq!(delivered, 1);
+
+    let (status, log) = app
+        .get_json(
+            &format!("/webhooks/{id}/deliveries"),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(log[0]["status"], "delivered");
+    assert_eq!(log[0]["attempts"], 2);
+    assert_eq!(log[0]["last_status_code"], 204);
+
+    let received = listener.received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2);
+    for (headers, body) in &received {
+        assert_signed(headers, body, secret);
+        assert_eq!(headers["x-webhook-event"], "test");
+        let payload: Value = serde_json::from_slice(body).expect("webhook body is not json");
+        assert_eq!(payload["type"], "test");
+        assert_eq!(payload["data"]["subscription_id"], id);
+    }
+}
+
+#[sqlx::test]
+async fn webhooks_receive_matches_comments_and_crawl_failures(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let user = app.register_and_login().await;
+    let (url, listener) = start_listener(0).await;
+    seed_group(&pool, user.id, 10).await;
+
+    let (status, _) = app
+        .post_json(
+            "/webhooks",
+            json!({ "url": url, "event_types": ["vk_user.matched", "vk_user.commented", "crawl.failed"] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = app
+        .post_json(
+            "/saved-searches",
+            json!({ "name": "everyone in Moscow", "city": "Moscow" }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    find_w::vk_users::repo::upsert_vk_users(
+        &pool,
+        user.id,
+        &[sample_vk_user(1000, "Ivan", OffsetDateTime::now_utc())],
+    )
+    .await
+    .expect("failed to seed vk user");
+    find_w::scheduler::run_due_searches(&pool)
+        .await
+        .expect("scheduler run failed");
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/batch",
+            json!({
+                "posts": [
+                    { "post_id": 1, "group_id": 10, "from_id": 1000, "created_date": 1_700_000_001 }
+                ],
+                "comments": [
+                    { "group_id": 10, "post_id": 1, "comment_id": 5, "from_id": 1000, "created_date": 1_700_000_010, "comment_text": "hi" }
+                ]
+            }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_json(
+            "/ingest/vk-raw?method=users.get",
+            json!({ "error": { "error_code": 5, "error_msg": "User authorization failed" } }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let delivered = find_w::webhooks::sender::deliver_due(&pool, &local_client())
+        .await
+        .expect("failed to deliver due webhooks");
+    assert_eq!(delivered, 3);
+
+    let mut events: Vec<Value> = listener
+        .received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, body)| serde_json::from_slice(body).expect("webhook body is not json"))
+        .collect();
+    events.sort_by_key(|event| event["type"].as_str().map(str::to_string));
+
+    assert_eq!(events[0]["type"], "crawl.failed");
+    assert_eq!(events[0]["data"]["source"], "users.get");
+    assert_eq!(events[1]["type"], "vk_user.commented");
+    assert_eq!(events[1]["data"]["comment_id"], 5);
+    assert_eq!(events[2]["type"], "vk_user.matched");
+    assert_eq!(events[2]["data"]["vk_user_id"], 1000);
+}
+
+#[sqlx::test]
+async fn webhooks_cannot_target_private_addresses(pool: PgPool) {
+    let app = TestApp::with_state(pool.clone(), |state| {
+        state.webhooks = WebhookClient::new(WebhookTargetPolicy::PublicOnly);
+    });
+    let user = app.register_and_login().await;
+    let (url, listener) = start_listener(0).await;
+
+    for target in [
+        url.as_str(),
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://192.168.0.10:8080/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://0.0.0.0/hook",
+        "http://[::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let (status, body) = app
+            .post_json(
+                "/webhooks",
+                json!({ "url": target, "event_types": ["crawl.failed"] }),
+                Some(&user.access_token),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{target} was accepted");
+        assert_eq!(body["error"], "BAD_REQUEST");
+    }
+
+    // Subscriptions stored before the check are refused at delivery time.
+    let id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO webhook_subscriptions (user_id, url, event_types, secret) \
+         VALUES ($1, $2, ARRAY['crawl.failed'], 'local-listener-secret') RETURNING id",
+    )
+    .bind(user.id)
+    .bind(&url)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let (status, delivery) = app
+        .post_json(
+            &format!("/webhooks/{id}/test"),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(delivery["status"], "pending");
+    assert_eq!(delivery["last_status_code"], Value::Null);
+    assert!(
+        delivery["last_error"]
+            .as_str()
+            .unwrap()
+            .contains("not a public address")
+    );
+    assert!(listener.received.lock().unwrap().is_empty());
+}
+
+#[sqlx::test]
+async fn webhook_redirects_are_not_followed(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.register_and_login().await;
+    let (url, listener) = start_listener(0).await;
+
+    let (status, created) = app
+        .post_json(
+            "/webhooks",
+            json!({ "url": url.replace("/hook", "/redirect"), "event_types": ["crawl.failed"] }),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().expect("webhook misses id");
+
+    let (status, delivery) = app
+        .post_json(
+            &format!("/webhooks/{id}/test"),
+            json!({}),
+            Some(&user.access_token),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(delivery["status"], "pending");
+    assert_eq!(delivery["last_status_code"], 307);
+    assert!(listener.received.lock().unwrap().is_empty());
+}